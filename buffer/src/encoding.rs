@@ -0,0 +1,131 @@
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// How the text of a buffer is stored on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl Encoding {
+    /// Guesses the encoding of `bytes`: a byte order mark wins, then UTF-8
+    /// if the bytes are valid UTF-8, and Latin-1 otherwise since every byte
+    /// sequence is valid Latin-1.
+    pub fn detect(bytes: &[u8]) -> Encoding {
+        if bytes.starts_with(UTF8_BOM) {
+            Encoding::Utf8Bom
+        } else if bytes.starts_with(UTF16LE_BOM) {
+            Encoding::Utf16Le
+        } else if bytes.starts_with(UTF16BE_BOM) {
+            Encoding::Utf16Be
+        } else if ::std::str::from_utf8(bytes).is_ok() {
+            Encoding::Utf8
+        } else {
+            Encoding::Latin1
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8    => "utf-8",
+            Encoding::Utf8Bom => "utf-8-bom",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1  => "latin1",
+        }
+    }
+
+    /// Decodes `bytes`, dropping the byte order mark if there is one.
+    pub fn decode(self, bytes: &[u8]) -> io::Result<String> {
+        match self {
+            Encoding::Utf8    => decode_utf8(bytes),
+            Encoding::Utf8Bom => decode_utf8(strip_prefix(bytes, UTF8_BOM)),
+            Encoding::Utf16Le => decode_utf16(strip_prefix(bytes, UTF16LE_BOM), u16::from_le_bytes),
+            Encoding::Utf16Be => decode_utf16(strip_prefix(bytes, UTF16BE_BOM), u16::from_be_bytes),
+            Encoding::Latin1  => Ok(bytes.iter().map(|&byte| byte as char).collect()),
+        }
+    }
+
+    /// Encodes `text`, starting with a byte order mark for the encodings
+    /// that carry one.
+    pub fn encode(self, text: &str) -> io::Result<Vec<u8>> {
+        match self {
+            Encoding::Utf8    => Ok(text.as_bytes().to_vec()),
+            Encoding::Utf8Bom => {
+                let mut bytes = UTF8_BOM.to_vec();
+                bytes.extend_from_slice(text.as_bytes());
+                Ok(bytes)
+            }
+            Encoding::Utf16Le => Ok(encode_utf16(text, UTF16LE_BOM, u16::to_le_bytes)),
+            Encoding::Utf16Be => Ok(encode_utf16(text, UTF16BE_BOM, u16::to_be_bytes)),
+            Encoding::Latin1  => text.chars().map(|ch| {
+                if (ch as u32) <= 0xFF {
+                    Ok(ch as u8)
+                } else {
+                    Err(invalid_data(format!("cannot encode '{}' as {}", ch, self)))
+                }
+            }).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "utf-8" | "utf8"                    => Ok(Encoding::Utf8),
+            "utf-8-bom" | "utf8-bom"            => Ok(Encoding::Utf8Bom),
+            "utf-16le" | "utf16le"              => Ok(Encoding::Utf16Le),
+            "utf-16be" | "utf16be"              => Ok(Encoding::Utf16Be),
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            _                                   => Err(format!("unknown encoding: {}", name)),
+        }
+    }
+}
+
+fn strip_prefix<'a>(bytes: &'a [u8], prefix: &[u8]) -> &'a [u8] {
+    if bytes.starts_with(prefix) { &bytes[prefix.len()..] } else { bytes }
+}
+
+fn decode_utf8(bytes: &[u8]) -> io::Result<String> {
+    String::from_utf8(bytes.to_vec()).map_err(|err| invalid_data(err.to_string()))
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> io::Result<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(invalid_data("odd number of bytes in UTF-16 text".to_string()));
+    }
+    let units = bytes.chunks(2).map(|pair| unit([pair[0], pair[1]]));
+    ::std::char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|err| invalid_data(err.to_string()))
+}
+
+fn encode_utf16(text: &str, bom: &[u8], bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+    let mut encoded = bom.to_vec();
+    for unit in text.encode_utf16() {
+        encoded.extend_from_slice(&bytes(unit));
+    }
+    encoded
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
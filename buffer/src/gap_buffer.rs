@@ -1,5 +1,5 @@
 use std::default::Default;
-use std::fmt::Debug;
+use std::fmt;
 
 const MAXIMUM_GAP_SIZE: usize = 512;
 
@@ -53,53 +53,44 @@ impl LineBuffer {
         }
     }
 
+    /// The index of the first `ch` after `from`, or the last one before it
+    /// when not `forward`.
+    pub fn find_char(&self, ch: char, from: usize, forward: bool) -> Option<usize> {
+        let is_ch = |idx: &usize| self.get_char_at(*idx) == Option::Some(&ch);
+        if forward {
+            (from.saturating_add(1)..self.count()).find(is_ch)
+        } else {
+            (0..from.min(self.count())).rev().find(is_ch)
+        }
+    }
+
 
     /*
-       columns start from 0
+       columns start from 0, the caret sits right before the char at `seek_to`
 
        ---S-------A----
-       seek(3) should lead to
+       seek(2) should lead to
        --S-------A-----
        seek(4)
        ----S-------A---
      */
     pub fn seek(&mut self, seek_to: usize) {
-        let gap_size = self.gap_end - self.gap_start;
+        let seek_to = if seek_to > self.count() { self.count() } else { seek_to };
 
-        let seek_to  = if seek_to > 0 && seek_to >= self.count() {
-            self.count() - 1
-        } else {
-            seek_to
-        };
-
-        if seek_to < 0 {
-            return;
-        } else if seek_to <= self.gap_start {
-            let (swap_start, swap_end) = (seek_to, self.gap_start - 1);
-            let mut index = swap_end;
-            while index >= swap_start {
-                self.buffer.swap(index, self.gap_end);
-                self.gap_start -= 1;
-                self.gap_end -= 1;
-                if index == 0 {
-                    break;
-                }
-                index -= 1;
-            }
-        } else {
-            let (swap_start, swap_end) = (self.gap_end + 1, self.gap_end + seek_to);
-            let mut index = swap_start;
-            while index <= swap_end {
-                self.buffer.swap(index, self.gap_start);
-                self.gap_start += 1;
-                self.gap_end += 1;
-                index += 1;
-            }
+        while self.gap_start > seek_to {
+            self.gap_start -= 1;
+            self.buffer.swap(self.gap_start, self.gap_end);
+            self.gap_end -= 1;
+        }
+        while self.gap_start < seek_to {
+            self.gap_end += 1;
+            self.buffer.swap(self.gap_start, self.gap_end);
+            self.gap_start += 1;
         }
     }
 
     pub fn get_current_index(&self) -> Option<usize> {
-        if self.gap_start <= 0 { Option::None }
+        if self.gap_start == 0 { Option::None }
         else { Option::Some(self.gap_start - 1) }
     }
 
@@ -108,30 +99,26 @@ impl LineBuffer {
     }
 
     pub fn insert(&mut self, ch: char) {
-        if self.gap_start == self.gap_end {
-            let mut new_line_buffer: Vec<char> = LineBuffer::fill(self.count() + MAXIMUM_GAP_SIZE);
-            let mut index = 0;
-            let mut new_index = 0;
-            while index < self.len {
-                if index == self.gap_start - 1 {
-                    new_index += MAXIMUM_GAP_SIZE + 1;
-                    index += 1;
-                } else {
-                    if let Some(item) = self.buffer.pop() {
-                        new_line_buffer[new_index] = item;
-                    }
-                    index += 1;
-                    new_index += 1;
-                }
-            }
-            self.buffer = new_line_buffer;
-            self.gap_end = self.gap_start + MAXIMUM_GAP_SIZE;
+        if self.gap_start >= self.gap_end {
+            self.grow();
         }
         self.buffer[self.gap_start] = ch;
         self.len += 1;
         self.gap_start += 1;
     }
 
+    fn grow(&mut self) {
+        let after_gap = if self.buffer.len() > self.gap_end {
+            self.buffer.split_off(self.gap_end + 1)
+        } else {
+            Vec::new()
+        };
+        self.buffer.truncate(self.gap_start);
+        self.buffer.append(&mut LineBuffer::fill(MAXIMUM_GAP_SIZE));
+        self.gap_end = self.buffer.len() - 1;
+        self.buffer.extend(after_gap);
+    }
+
     pub fn delete(&mut self) {
         if self.gap_start > 0 {
             self.gap_start -= 1;
@@ -139,42 +126,23 @@ impl LineBuffer {
         }
     }
 
-    pub fn apply_to_all(&self, f: &Fn(&char) -> char) -> LineBuffer {
-        let mut transformed: Vec<char> = LineBuffer::fill(self.buffer.capacity());
-        let mut index = 0;
-        let len = self.buffer.len();
-        while index < len {
-            if index == self.gap_start {
-                index = self.gap_end;
-            } else {
-                unsafe {
-                    let elem = self.buffer.get_unchecked(index);
-                    let telem = f(elem);
-                    transformed.insert(index, telem);
-                }
-            }
-            index += 1;
-        }
-
+    pub fn apply_to_all(&self, f: &dyn Fn(&char) -> char) -> LineBuffer {
         LineBuffer {
-            buffer: transformed,
+            buffer: self.buffer.iter().map(f).collect(),
             len: self.len,
             gap_start: self.gap_start,
             gap_end: self.gap_end,
         }
     }
 
-    pub fn for_each(&self, f: &mut FnMut(&char) -> ()) {
+    pub fn for_each(&self, f: &mut dyn FnMut(&char)) {
         let mut index = 0;
         let len = self.buffer.len();
         while index < len {
             if index == self.gap_start {
                 index = self.gap_end;
             } else {
-                unsafe {
-                    let mut elem = self.buffer.get_unchecked(index);
-                    f(elem);
-                }
+                f(&self.buffer[index]);
             }
             index += 1;
         }
@@ -184,3 +152,22 @@ impl LineBuffer {
         self.len
     }
 }
+
+impl<'a> From<&'a str> for LineBuffer {
+    fn from(text: &'a str) -> Self {
+        let mut line = LineBuffer::with_capacity(text.len() + MAXIMUM_GAP_SIZE);
+        for ch in text.chars() {
+            line.insert(ch);
+        }
+        line.seek(0);
+        line
+    }
+}
+
+impl fmt::Display for LineBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut text = String::with_capacity(self.count());
+        self.for_each(&mut |ch| text.push(*ch));
+        f.write_str(&text)
+    }
+}
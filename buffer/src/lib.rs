@@ -1,13 +1,62 @@
+#[cfg(unix)]
+extern crate libc;
+
+pub mod encoding;
 pub mod gap_buffer;
+pub mod save;
+pub mod swap;
+pub mod undo;
+pub mod undofile;
 
+use encoding::Encoding;
 use gap_buffer::LineBuffer;
 use std::fs::File;
+use std::io;
 use std::path::Path;
-use std::io::prelude::*;
-use std::io::{BufReader, BufRead, BufWriter, Write};
+use std::io::Read;
+use std::time::Duration;
+use undo::{Edit, UndoTree};
 
 
 const MAXIMUM_GAP_SIZE: usize = 512;
+
+/// What terminates the lines of a buffer on disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Unix,
+    Dos,
+}
+
+impl LineEnding {
+    /// `Dos` only when every line of `text` ends in `\r\n`, so that a stray
+    /// `\r` in a Unix file is kept as part of its line.
+    pub fn detect(text: &str) -> LineEnding {
+        let mut lines = text.split('\n');
+        lines.next_back();
+        let mut terminated = lines.peekable();
+        if terminated.peek().is_some() && terminated.all(|line| line.ends_with('\r')) {
+            LineEnding::Dos
+        } else {
+            LineEnding::Unix
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Unix => "\n",
+            LineEnding::Dos  => "\r\n",
+        }
+    }
+}
+
+/// Which way `Buffer::find` searches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
 #[derive(Debug)]
 pub struct Buffer {
     file_name: Option<String>,
@@ -15,32 +64,171 @@ pub struct Buffer {
     buffer: Vec<LineBuffer>,
     gap_start: usize,
     gap_end: usize,
-    len: usize
+    len: usize,
+    missing_eol: bool,
+    encoding: Encoding,
+    line_ending: LineEnding,
+    keep_backup: bool,
+    changes: u64,
+    undo: UndoTree,
+    saved_state: Option<usize>,
 }
 
 impl Buffer {
     pub fn with_capacity(capacity: usize) -> Self {
         Buffer {
             file_name: Option::None,
-            is_saved: false,
+            is_saved: true,
             buffer: Buffer::fill(capacity),
             len: 0,
             gap_start: 0,
             gap_end: if capacity > 0 { capacity - 1 } else { capacity },
+            missing_eol: false,
+            encoding: Encoding::default(),
+            line_ending: LineEnding::default(),
+            keep_backup: false,
+            changes: 0,
+            undo: UndoTree::new(),
+            saved_state: Option::Some(0),
         }
     }
 
     pub fn new() -> Self {
         Buffer {
             file_name: Option::None,
-            is_saved: false,
+            is_saved: true,
             buffer: Buffer::fill(MAXIMUM_GAP_SIZE),
             gap_start: 0,
             len: 0,
             gap_end: MAXIMUM_GAP_SIZE - 1,
+            missing_eol: false,
+            encoding: Encoding::default(),
+            line_ending: LineEnding::default(),
+            keep_backup: false,
+            changes: 0,
+            undo: UndoTree::new(),
+            saved_state: Option::Some(0),
         }
     }
 
+    /// Opens the file at `path` and loads it into a new buffer, one
+    /// `LineBuffer` per line. The returned buffer remembers `path` as its
+    /// file name and starts out saved. The encoding is detected from the
+    /// contents, see `Encoding::detect`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Buffer::open_file(path.as_ref(), Option::None)
+    }
+
+    /// Like `open`, but reads the file as `encoding` instead of guessing.
+    pub fn open_with_encoding<P: AsRef<Path>>(path: P, encoding: Encoding) -> io::Result<Self> {
+        Buffer::open_file(path.as_ref(), Option::Some(encoding))
+    }
+
+    fn open_file(path: &Path, encoding: Option<Encoding>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let mut buffer = Buffer::read_from(file, encoding)?;
+        buffer.file_name = Option::Some(path.to_string_lossy().into_owned());
+        Ok(buffer)
+    }
+
+    /// Loads everything `reader` yields into a new, unnamed buffer.
+    ///
+    /// The encoding and line ending are detected and kept with the buffer,
+    /// as is a missing newline at the end of the input, so that saving
+    /// writes the text back byte for byte.
+    pub fn from_reader<R: Read>(reader: R) -> io::Result<Self> {
+        Buffer::read_from(reader, Option::None)
+    }
+
+    /// Like `from_reader`, but decodes the input as `encoding`. Input that
+    /// is not valid in it is reported as `io::ErrorKind::InvalidData`.
+    pub fn from_reader_with_encoding<R: Read>(reader: R, encoding: Encoding) -> io::Result<Self> {
+        Buffer::read_from(reader, Option::Some(encoding))
+    }
+
+    fn read_from<R: Read>(mut reader: R, encoding: Option<Encoding>) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let encoding = encoding.unwrap_or_else(|| Encoding::detect(&bytes));
+        let text = encoding.decode(&bytes)?;
+        let line_ending = LineEnding::detect(&text);
+
+        let mut lines: Vec<&str> = text.split('\n').collect();
+        let last = lines.pop().unwrap_or("");
+        let mut buffer = Buffer::with_capacity(lines.len() + MAXIMUM_GAP_SIZE);
+        for line in lines {
+            let line = if line_ending == LineEnding::Dos { &line[..line.len() - 1] } else { line };
+            buffer.insert_line(LineBuffer::from(line));
+        }
+        if !last.is_empty() {
+            buffer.insert_line(LineBuffer::from(last));
+            buffer.missing_eol = true;
+        }
+        buffer.encoding = encoding;
+        buffer.line_ending = line_ending;
+        buffer.move_cursor(0, 0);
+        buffer.is_saved = true;
+        buffer.saved_state = Option::Some(0);
+        Ok(buffer)
+    }
+
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    pub fn set_file_name(&mut self, file_name: &str) {
+        self.file_name = Option::Some(file_name.to_string());
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Changes the encoding used the next time the buffer is written.
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        if self.encoding != encoding {
+            self.encoding = encoding;
+            self.modified();
+        }
+    }
+
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        if self.line_ending != line_ending {
+            self.line_ending = line_ending;
+            self.modified();
+        }
+    }
+
+    /// Whether writing over an existing file first copies it to `file~`.
+    pub fn keep_backup(&self) -> bool {
+        self.keep_backup
+    }
+
+    pub fn set_keep_backup(&mut self, keep_backup: bool) {
+        self.keep_backup = keep_backup;
+    }
+
+    /// Whether the text is as it was last loaded or saved. A new, empty
+    /// buffer has nothing to lose and counts as saved.
+    pub fn is_saved(&self) -> bool {
+        self.is_saved
+    }
+
+    /// Counts every change made to the buffer, so that anyone keeping a
+    /// copy of it can tell when the copy went stale.
+    pub fn changes(&self) -> u64 {
+        self.changes
+    }
+
+    fn modified(&mut self) {
+        self.is_saved = false;
+        self.changes += 1;
+    }
+
     fn fill(size: usize) -> Vec<LineBuffer> {
         let mut index = 0;
         let mut vec = Vec::with_capacity(size);
@@ -56,13 +244,13 @@ impl Buffer {
     }
 
     pub fn get_line_at(&self, idx: usize) -> Option<&LineBuffer> {
-        if self.gap_start != 0 {
-            if idx < self.gap_start {
-                self.buffer.get(idx)
-            } else {
-                self.buffer.get(idx + self.gap_end - self.gap_start + 1)
-            }
-        } else { Option::None }
+        if idx >= self.len {
+            Option::None
+        } else if idx < self.gap_start {
+            self.buffer.get(idx)
+        } else {
+            self.buffer.get(idx + self.gap_end - self.gap_start + 1)
+        }
     }
 
     pub fn get_current_line_index(&self) -> Option<usize> {
@@ -78,13 +266,13 @@ impl Buffer {
     }
 
     pub fn get_mut_line_at(&mut self, idx: usize) -> Option<&mut LineBuffer> {
-        if self.gap_start != 0 {
-            if idx < self.gap_start {
-                self.buffer.get_mut(idx)
-            } else {
-                self.buffer.get_mut(idx + self.gap_end - self.gap_start + 1)
-            }
-        } else { Option::None }
+        if idx >= self.len {
+            Option::None
+        } else if idx < self.gap_start {
+            self.buffer.get_mut(idx)
+        } else {
+            self.buffer.get_mut(idx + self.gap_end - self.gap_start + 1)
+        }
     }
 
     pub fn get_mut_current_line(&mut self) -> Option<&mut LineBuffer> {
@@ -100,53 +288,41 @@ impl Buffer {
 
     pub fn get_cursor_index(&self) -> Option<(usize, usize)> {
         if let Some(line) = self.get_current_line() {
-            if let Some(col) = line.get_current_index() {
-                self.get_current_line_index().map(|row| { (row, col) })
-            } else { Option::None }
+            let col = line.get_caret();
+            self.get_current_line_index().map(|row| { (row, col) })
         } else { Option::None }
     }
 
     /*
-       columns start from 0
+       rows start from 0, the current line is the one right before the gap
 
        ---S-------A----
-       seek(3) should lead to
+       seek(1) should lead to
        --S-------A-----
-       seek(4)
+       seek(3)
        ----S-------A---
      */
     pub fn seek(&mut self, seek_to: usize) {
-        let gap_size = self.gap_end - self.gap_start;
-
-        let seek_to  = if seek_to > 0 && seek_to >= self.count() {
-            self.count() - 1
-        } else {
-            seek_to
-        };
-
-        if seek_to < 0 {
+        if self.is_empty() {
             return;
-        } else if seek_to <= self.gap_start {
-            let (swap_start, swap_end) = (seek_to, self.gap_start - 1);
-            let mut index = swap_end;
-            while index >= swap_start {
-                self.buffer.swap(index, self.gap_end);
-                self.gap_start -= 1;
-                self.gap_end -= 1;
-                if index == 0 {
-                    break;
-                }
-                index -= 1;
-            }
-        } else {
-            let (swap_start, swap_end) = (self.gap_end + 1, self.gap_end + seek_to);
-            let mut index = swap_start;
-            while index <= swap_end {
-                self.buffer.swap(index, self.gap_start);
-                self.gap_start += 1;
-                self.gap_end += 1;
-                index += 1;
-            }
+        }
+        let seek_to = if seek_to >= self.count() { self.count() } else { seek_to + 1 };
+        self.move_gap(seek_to);
+    }
+
+    /// Moves the gap so that it starts right after the first `gap_start`
+    /// lines.
+    fn move_gap(&mut self, gap_start: usize) {
+        let seek_to = gap_start.min(self.count());
+        while self.gap_start > seek_to {
+            self.gap_start -= 1;
+            self.buffer.swap(self.gap_start, self.gap_end);
+            self.gap_end -= 1;
+        }
+        while self.gap_start < seek_to {
+            self.gap_end += 1;
+            self.buffer.swap(self.gap_start, self.gap_end);
+            self.gap_start += 1;
         }
     }
 
@@ -157,69 +333,346 @@ impl Buffer {
         };
     }
 
-    /// mutating buffers
+    /// The text of line `row`.
+    pub fn line_text(&self, row: usize) -> Option<String> {
+        self.get_line_at(row).map(|line| line.to_string())
+    }
 
-    pub fn new_line(&mut self) {
-        if self.gap_start == self.gap_end {
-            let mut new_buffer = Buffer::fill(self.count() + MAXIMUM_GAP_SIZE);
-            let mut index = 0;
-            let mut new_index = 0;
-            while index < self.len {
-                if index == self.gap_start - 1 {
-                    new_index += MAXIMUM_GAP_SIZE + 1;
-                    index += 1;
-                } else {
-                    if let Some(item) = self.buffer.pop() {
-                        new_buffer[new_index] = item;
-                    }
-                    index += 1;
-                    new_index += 1;
-                }
+    /// Where the next match of `pattern` starts after `from`, or the one
+    /// before it going `Backward`, going round the end of the buffer to
+    /// the other end. A match does not go over the end of its line.
+    pub fn find(&self, pattern: &str, from: (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        if pattern.is_empty() || self.is_empty() {
+            return Option::None;
+        }
+        let count = self.count();
+        let (row, col) = (from.0.min(count - 1), from.1);
+        let matches = |line: &LineBuffer, at: usize| {
+            at + pattern.len() <= line.count()
+                && pattern.iter().enumerate().all(|(index, ch)| line.get_char_at(at + index) == Option::Some(ch))
+        };
+        // the line of `from` comes last again, for the matches on its other
+        // side
+        for step in 0..=count {
+            let row = match direction {
+                Direction::Forward  => (row + step) % count,
+                Direction::Backward => (row + count - step % count) % count,
+            };
+            let line = self.get_line_at(row)?;
+            let mut cols = 0..(line.count() + 1).saturating_sub(pattern.len());
+            let found = match direction {
+                Direction::Forward if step == 0 => cols.find(|&at| at > col && matches(line, at)),
+                Direction::Forward              => cols.find(|&at| matches(line, at)),
+                Direction::Backward if step == 0 => cols.rev().find(|&at| at < col && matches(line, at)),
+                Direction::Backward              => cols.rev().find(|&at| matches(line, at)),
+            };
+            if let Some(col) = found {
+                return Option::Some((row, col));
             }
-            self.buffer = new_buffer;
-            self.gap_end = self.gap_start + MAXIMUM_GAP_SIZE;
         }
-        self.buffer[self.gap_start] = LineBuffer::new();
-        self.len += 1;
-        self.gap_start += 1;
-        self.is_saved = false;
+        Option::None
+    }
+
+    /// The text from `start` up to but not including `end`, lines joined
+    /// with `\n`. A column past the end of a line stands for its newline.
+    pub fn text_range(&self, start: (usize, usize), end: (usize, usize)) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let start = self.normalize(start);
+        let end = self.normalize(end);
+        let mut text = String::new();
+        for row in start.0..end.0 + 1 {
+            let line: Vec<char> = self.line_text(row).unwrap_or_default().chars().collect();
+            let from = if row == start.0 { start.1 } else { 0 };
+            let to = if row == end.0 { end.1 } else { line.len() };
+            if from < to {
+                text.extend(&line[from..to]);
+            }
+            if row < end.0 {
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Clamps `(row, col)` into the buffer, a column past the end of a line
+    /// becoming the start of the next one.
+    fn normalize(&self, (row, col): (usize, usize)) -> (usize, usize) {
+        let last = self.count().saturating_sub(1);
+        if row > last {
+            return (last, self.line_len(last));
+        }
+        let len = self.line_len(row);
+        if col > len && row < last {
+            (row + 1, 0)
+        } else {
+            (row, col.min(len))
+        }
     }
 
+    // mutating buffers
+
+    /// Opens an empty line below the cursor and moves the cursor onto it.
+    pub fn new_line(&mut self) {
+        let row = match self.get_current_line_index() {
+            Option::Some(row) => row + 1,
+            Option::None      => 0,
+        };
+        self.replace_lines(row, 0, vec![String::new()]);
+        self.move_cursor(row, 0);
+    }
+
+    /// Deletes the line under the cursor.
     pub fn delete(&mut self) {
-        if self.gap_start > 0 {
-            self.gap_start -= 1;
-            self.len -= 1;
-            self.is_saved = false;
+        if let Some(row) = self.get_current_line_index() {
+            self.replace_lines(row, 1, Vec::new());
+            self.move_cursor(row, 0);
         }
     }
 
+    /// Inserts `ch` before the cursor, `\n` splitting the line. Other chars
+    /// go straight into the gap of the cursor line, and typing on one line
+    /// only copies it for undo once.
     pub fn put_char(&mut self, ch: char) {
         if ch == '\r' {
             return;
-        } else if ch == '\n' {
-            self.new_line();
+        }
+        let (row, col) = self.get_cursor_index().unwrap_or((0, 0));
+        if ch == '\n' || self.is_empty() {
+            let mut text = [0; 4];
+            self.insert_text(row, col, ch.encode_utf8(&mut text));
+            return;
+        }
+        let before = if self.undo.extends_line(row) { Option::None } else { self.line_text(row) };
+        if let Some(line) = self.get_mut_current_line() {
+            line.seek(col);
+            line.insert(ch);
+        }
+        self.undo.record_char(row, col, ch, before, (row, col));
+        self.modified();
+    }
+
+    /// Inserts `text` at `(row, col)` and puts the cursor right after it,
+    /// which is also the position returned.
+    pub fn insert_text(&mut self, row: usize, col: usize, text: &str) -> (usize, usize) {
+        if self.is_empty() {
+            self.replace_lines(0, 0, vec![String::new()]);
+        }
+        let row = row.min(self.count() - 1);
+        let line: Vec<char> = self.line_text(row).unwrap_or_default().chars().collect();
+        let col = col.min(line.len());
+        let after: String = line[col..].iter().collect();
+
+        let mut lines: Vec<String> = text.split('\n').map(|piece| piece.to_string()).collect();
+        lines[0] = line[..col].iter().collect::<String>() + &lines[0];
+        let last = lines.len() - 1;
+        let end = (row + last, lines[last].chars().count());
+        lines[last].push_str(&after);
+
+        self.replace_lines(row, 1, lines);
+        self.move_cursor(end.0, end.1);
+        end
+    }
+
+    /// Removes the text from `start` up to but not including `end`, as
+    /// `text_range` reads it, and returns it. The cursor ends up at
+    /// `start`.
+    pub fn remove_text(&mut self, start: (usize, usize), end: (usize, usize)) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let removed = self.text_range(start, end);
+        let start = self.normalize(start);
+        let end = self.normalize(end);
+        if removed.is_empty() {
+            return removed;
+        }
+        let head: String = self.line_text(start.0).unwrap_or_default().chars().take(start.1).collect();
+        let tail: String = self.line_text(end.0).unwrap_or_default().chars().skip(end.1).collect();
+        self.replace_lines(start.0, end.0 - start.0 + 1, vec![head + &tail]);
+        self.move_cursor(start.0, start.1);
+        removed
+    }
+
+    /// Replaces the text from `start` up to but not including `end` with
+    /// `text` as one change, returning what was there. The cursor ends up at
+    /// `start`.
+    pub fn replace_text(&mut self, start: (usize, usize), end: (usize, usize), text: &str) -> String {
+        if self.is_empty() {
+            self.insert_text(0, 0, text);
+            self.move_cursor(0, 0);
+            return String::new();
+        }
+        let replaced = self.text_range(start, end);
+        let start = self.normalize(start);
+        let end = self.normalize(end);
+        let head: String = self.line_text(start.0).unwrap_or_default().chars().take(start.1).collect();
+        let tail: String = self.line_text(end.0).unwrap_or_default().chars().skip(end.1).collect();
+        let lines = (head + text + &tail).split('\n').map(|line| line.to_string()).collect();
+        self.replace_lines(start.0, end.0 - start.0 + 1, lines);
+        self.move_cursor(start.0, start.1);
+        replaced
+    }
+
+    /// Removes `count` whole lines starting at `row` and returns them.
+    pub fn delete_lines(&mut self, row: usize, count: usize) -> Vec<String> {
+        let count = count.min(self.count().saturating_sub(row));
+        let removed = (row..row + count).filter_map(|row| self.line_text(row)).collect();
+        self.replace_lines(row, count, Vec::new());
+        self.move_cursor(row, 0);
+        removed
+    }
+
+    pub fn line_len(&self, row: usize) -> usize {
+        self.get_line_at(row).map(|line| line.count()).unwrap_or(0)
+    }
+
+    /// Replaces the `remove` lines starting at `row` with `insert`. Every
+    /// change to the text goes through here so that it can be undone.
+    pub fn replace_lines(&mut self, row: usize, remove: usize, insert: Vec<String>) {
+        let cursor = self.get_cursor_index().unwrap_or((0, 0));
+        let removed = self.splice(row, remove, &insert);
+        self.undo.record(Edit { row, removed, inserted: insert }, cursor);
+        self.modified();
+    }
+
+    fn splice(&mut self, row: usize, remove: usize, insert: &[String]) -> Vec<String> {
+        let row = row.min(self.count());
+        let remove = remove.min(self.count() - row);
+        self.move_gap(row + remove);
+        let mut removed = Vec::with_capacity(remove);
+        for _ in 0..remove {
+            self.gap_start -= 1;
+            self.len -= 1;
+            removed.push(self.buffer[self.gap_start].to_string());
+        }
+        removed.reverse();
+        for text in insert {
+            self.insert_line(LineBuffer::from(text.as_str()));
+        }
+        removed
+    }
+
+    fn insert_line(&mut self, line: LineBuffer) {
+        if self.gap_start >= self.gap_end {
+            self.grow();
+        }
+        self.buffer[self.gap_start] = line;
+        self.len += 1;
+        self.gap_start += 1;
+    }
+
+    fn grow(&mut self) {
+        let after_gap = if self.buffer.len() > self.gap_end {
+            self.buffer.split_off(self.gap_end + 1)
         } else {
-            if let Some(mut line) = self.get_mut_current_line() {
-                println!("INSERTED");
-                line.insert(ch);
-            };
+            Vec::new()
+        };
+        self.buffer.truncate(self.gap_start);
+        self.buffer.append(&mut Buffer::fill(MAXIMUM_GAP_SIZE));
+        self.gap_end = self.buffer.len() - 1;
+        self.buffer.extend(after_gap);
+    }
+
+    // undo
+
+    /// Ends the current undo step: everything changed since the previous
+    /// call is undone and redone together.
+    pub fn commit_change(&mut self) {
+        self.undo.commit();
+    }
+
+    /// The number of the current undo state, 0 being the text as loaded.
+    pub fn undo_state(&self) -> usize {
+        self.undo.current()
+    }
+
+    /// The number of the newest undo state.
+    pub fn newest_undo_state(&self) -> usize {
+        self.undo.newest()
+    }
+
+    /// `u`: goes back to the state before the last change, returning
+    /// `false` when already at the oldest one.
+    pub fn undo(&mut self) -> bool {
+        self.commit_change();
+        match self.undo.undo_target() {
+            Option::Some(target) => self.travel(target),
+            Option::None         => false,
         }
-        self.is_saved = false;
     }
 
-    pub fn apply_to_all(&self, f: &Fn(&LineBuffer) -> LineBuffer) -> Buffer {
-        let mut transformed = Buffer::fill(self.buffer.capacity());
+    /// `Ctrl-R`: redoes the change undone last, returning `false` when
+    /// there is none.
+    pub fn redo(&mut self) -> bool {
+        self.commit_change();
+        match self.undo.redo_target() {
+            Option::Some(target) => self.travel(target),
+            Option::None         => false,
+        }
+    }
+
+    /// `g-` and `g+`: moves `count` states back or forward in the order
+    /// they were created, crossing between branches of the undo tree.
+    pub fn undo_step(&mut self, count: isize) -> bool {
+        self.commit_change();
+        let current = self.undo.current() as isize;
+        let target = (current + count).max(0).min(self.undo.newest() as isize) as usize;
+        self.travel(target)
+    }
+
+    /// `:earlier {time}`: goes back to the text as it was `duration`
+    /// before the current state.
+    pub fn earlier(&mut self, duration: Duration) -> bool {
+        self.commit_change();
+        let target = self.undo.earlier_target(duration);
+        self.travel(target)
+    }
+
+    /// `:later {time}`: the opposite of `earlier`.
+    pub fn later(&mut self, duration: Duration) -> bool {
+        self.commit_change();
+        let target = self.undo.later_target(duration);
+        self.travel(target)
+    }
+
+    fn travel(&mut self, target: usize) -> bool {
+        if target == self.undo.current() {
+            return false;
+        }
+        let steps = self.undo.path_to(target);
+        let mut cursor = self.get_cursor_index().unwrap_or((0, 0));
+        for step in &steps {
+            let edits = self.undo.edits(step.node).to_vec();
+            if step.forward {
+                for edit in &edits {
+                    self.splice(edit.row, edit.removed.len(), &edit.inserted);
+                }
+            } else {
+                for edit in edits.iter().rev() {
+                    self.splice(edit.row, edit.inserted.len(), &edit.removed);
+                }
+            }
+            cursor = self.undo.cursor(step.node);
+        }
+        self.undo.move_to(&steps, target);
+        self.move_cursor(cursor.0, cursor.1);
+        self.changes += 1;
+        self.is_saved = self.saved_state == Option::Some(target);
+        true
+    }
+
+    pub fn apply_to_all(&self, f: &dyn Fn(&LineBuffer) -> LineBuffer) -> Buffer {
+        let mut transformed = Buffer::fill(self.buffer.len());
         let mut index = 0;
         let len = self.buffer.len();
         while index < len {
             if index == self.gap_start {
                 index = self.gap_end;
             } else {
-                unsafe {
-                    let elem = self.buffer.get_unchecked(index);
-                    let telem = f(elem);
-                    transformed.insert(index, telem);
-                }
+                transformed[index] = f(&self.buffer[index]);
             }
             index += 1;
         }
@@ -231,45 +684,83 @@ impl Buffer {
             len: self.len,
             gap_start: self.gap_start,
             gap_end: self.gap_end,
+            missing_eol: self.missing_eol,
+            encoding: self.encoding,
+            line_ending: self.line_ending,
+            keep_backup: self.keep_backup,
+            changes: self.changes + 1,
+            undo: UndoTree::new(),
+            saved_state: Option::None,
         }
     }
 
-    pub fn for_each(&self, f: &mut FnMut(&LineBuffer) -> ()) {
+    pub fn for_each(&self, f: &mut dyn FnMut(&LineBuffer)) {
         let mut index = 0;
         let len = self.buffer.len();
         while index < len {
             if index == self.gap_start {
                 index = self.gap_end;
             } else {
-                unsafe {
-                    let mut elem = self.buffer.get_unchecked(index);
-                    f(elem);
-                }
+                f(&self.buffer[index]);
             }
             index += 1;
         }
     }
 
-    pub fn save_buffer(&mut self, path: &str) {
-        let file = File::create(&Path::new(path)).expect(&format!("Cannot write to the path: {}", path));
-        let mut writer = BufWriter::new(file);
+    /// Writes the buffer to `path` and makes `path` its file name. See
+    /// `write_to` for `force`.
+    pub fn save_buffer(&mut self, path: &str, force: bool) -> io::Result<()> {
+        self.write_to(path, force)?;
+        self.commit_change();
+        self.saved_state = Option::Some(self.undo.current());
+        self.is_saved = true;
+        self.file_name = Option::Some(path.to_string());
+        Ok(())
+    }
+
+    /// Writes a copy of the buffer to `path`, leaving its file name and
+    /// saved state alone. Text that cannot be represented in the buffer's
+    /// encoding is reported as `io::ErrorKind::InvalidData` before the file
+    /// is touched, and the write itself is atomic unless `force`d, see
+    /// `save::write_atomically`.
+    pub fn write_to(&self, path: &str, force: bool) -> io::Result<()> {
+        let bytes = self.encoding.encode(&self.to_text())?;
+        save::write_atomically(Path::new(path), &bytes, self.keep_backup, force)
+    }
+
+    /// A 64-bit FNV-1a hash of the bytes `write_to` would write, used to
+    /// tell whether a file still has the contents an undo file was saved
+    /// for.
+    pub fn content_hash(&self) -> io::Result<u64> {
+        let bytes = self.encoding.encode(&self.to_text())?;
+        Ok(bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        }))
+    }
 
-        let new_line = &['\n' as u8];
+    fn to_text(&self) -> String {
+        let new_line = self.line_ending.as_str();
         let line_num = self.count();
         let mut count = 0;
+        let mut text = String::new();
         self.for_each(&mut |line| {
-            line.for_each(&mut |ch| { writer.write(&[*ch as u8]); });
+            line.for_each(&mut |ch| text.push(*ch));
             count += 1;
-            if count < line_num {
-                writer.write(new_line);
+            if count < line_num || !self.missing_eol {
+                text.push_str(new_line);
             }
         });
-        self.is_saved = true;
-        self.file_name = Option::Some(path.to_string());
+        text
     }
 
 }
 
+impl Default for Buffer {
+    fn default() -> Self {
+        Buffer::new()
+    }
+}
+
 // // Public interface
 // pub fn seek_to_line(buffer: &mut Buffer, row: usize) {
 //     buffer.seek(row);
@@ -301,41 +792,6 @@ impl Buffer {
 //     GapBuffer::new()
 // }
 //
-// pub fn open_file(path: &str) -> Buffer {
-//     let file = File::open(&Path::new(path)).expect(&format!("Cannot open the file: {}", path));
-//     let reader = BufReader::new(file);
-//     let lines = reader.lines();
-//     let (lo, hi) = lines.size_hint();
-//     println!("the size of the file is between {} and {:?}", lo, hi);
-//     let size = hi.unwrap_or(lo);
-//     let mut buffer: Buffer = GapBuffer::with_capacity(if size == 0 { 100 } else { size });
-//     for line in lines {
-//         if let Ok(line) = line {
-//             for ch in line.chars() {
-//                 put_char(&mut buffer, ch);
-//             }
-//             put_char(&mut buffer, '\n');
-//         }
-//     }
-//     buffer
-// }
-//
-// pub fn save_buffer(buffer: &mut Buffer, path: &str) {
-//     let file = File::create(&Path::new(path)).expect(&format!("Cannot write to the path: {}", path));
-//     let mut writer = BufWriter::new(file);
-//
-//     let new_line = &['\n' as u8];
-//     let line_num = buffer.count();
-//     let mut count = 0;
-//     buffer.for_each(&mut |line| {
-//         line.for_each(&mut |ch| { writer.write(&[*ch as u8]); });
-//         count += 1;
-//         if count < line_num {
-//             writer.write(new_line);
-//         }
-//     });
-// }
-//
 // pub fn get_active_line_index(buffer: &mut Buffer) -> usize {
 //     let idx = buffer.get_caret();
 //     idx
@@ -0,0 +1,180 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+#[cfg(unix)]
+use std::os::unix::fs::{fchown, MetadataExt};
+
+/// Symlinks followed before giving up, the same limit the kernel uses.
+const MAXIMUM_SYMLINK_DEPTH: usize = 40;
+
+/// Replaces the contents of `path` with `bytes` without ever leaving a
+/// half-written file behind.
+///
+/// The bytes go to a temporary file next to the target which is synced
+/// and then renamed over it, so a crash leaves either the old or the new
+/// contents. Symlinks are followed and the file they point to is replaced,
+/// and the permissions and ownership of an existing file are carried over.
+/// With `backup` the previous contents are first copied to `path~`.
+///
+/// When the owner of the file cannot be carried over, replacing it would
+/// give the file away, so this fails instead. With `force` the file is
+/// then overwritten in place, after copying it to `path~` whatever
+/// `backup` says, as that write is not atomic.
+pub fn write_atomically(path: &Path, bytes: &[u8], backup: bool, force: bool) -> io::Result<()> {
+    replace(path, bytes, backup, force, true)
+}
+
+/// Like `write_atomically` without a backup, but leaves it to the system
+/// when the new contents reach the disk, which is much cheaper for files
+/// rewritten often. Until they do, a crash may lose them.
+pub fn write_unsynced(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    replace(path, bytes, false, false, false)
+}
+
+fn replace(path: &Path, bytes: &[u8], backup: bool, force: bool, sync: bool) -> io::Result<()> {
+    let target = resolve_symlinks(path)?;
+    let existing = match fs::metadata(&target) {
+        Ok(metadata) => Option::Some(metadata),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Option::None,
+        Err(err) => return Err(err),
+    };
+    if let Some(ref metadata) = existing {
+        if !metadata.is_file() {
+            return Err(io::Error::other("not a regular file"));
+        }
+        if backup {
+            fs::copy(&target, backup_path(&target))?;
+        }
+    }
+
+    let (temp_path, mut temp) = create_temp_file(&target)?;
+    let result = fill_temp_file(&mut temp, bytes, existing.as_ref(), sync);
+    drop(temp);
+    match result {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = fs::remove_file(&temp_path);
+            if !force {
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied,
+                                          "cannot preserve owner, use :w! to overwrite in place"));
+            }
+            if !backup {
+                fs::copy(&target, backup_path(&target))?;
+            }
+            return write_in_place(&target, bytes);
+        }
+        Err(err) => {
+            let _ = fs::remove_file(&temp_path);
+            return Err(err);
+        }
+    }
+    if let Err(err) = fs::rename(&temp_path, &target) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err);
+    }
+    if sync {
+        sync_dir(&target)?;
+    }
+    Ok(())
+}
+
+/// The path `write_atomically` keeps the previous contents of `path` at.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push("~");
+    PathBuf::from(name)
+}
+
+fn resolve_symlinks(path: &Path) -> io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAXIMUM_SYMLINK_DEPTH {
+        match fs::symlink_metadata(&path) {
+            Ok(ref metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&path)?;
+                path = match path.parent() {
+                    Option::Some(dir) => dir.join(link),
+                    Option::None      => link,
+                };
+            }
+            Ok(_) => return Ok(path),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(path),
+            Err(err) => return Err(err),
+        }
+    }
+    Err(io::Error::other("too many levels of symbolic links"))
+}
+
+fn create_temp_file(target: &Path) -> io::Result<(PathBuf, File)> {
+    let dir = match target.parent() {
+        Option::Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = target.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut attempt = 0;
+    loop {
+        let temp_path = dir.join(format!(".{}.{}-{}.tmp", name, process::id(), attempt));
+        match OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Writes the temporary file and with `sync` syncs it, returning `false`
+/// when the file it replaces has an owner the temporary file could not be
+/// given.
+fn fill_temp_file(temp: &mut File, bytes: &[u8], existing: Option<&fs::Metadata>, sync: bool) -> io::Result<bool> {
+    temp.write_all(bytes)?;
+    if let Some(metadata) = existing {
+        temp.set_permissions(metadata.permissions())?;
+        if !copy_owner(temp, metadata) {
+            return Ok(false);
+        }
+    }
+    if sync {
+        temp.sync_all()?;
+    }
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn copy_owner(temp: &File, metadata: &fs::Metadata) -> bool {
+    let current = match temp.metadata() {
+        Ok(current) => current,
+        Err(_)      => return false,
+    };
+    if current.uid() == metadata.uid() && current.gid() == metadata.gid() {
+        return true;
+    }
+    fchown(temp, Option::Some(metadata.uid()), Option::Some(metadata.gid())).is_ok()
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_temp: &File, _metadata: &fs::Metadata) -> bool {
+    true
+}
+
+fn write_in_place(target: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).truncate(true).open(target)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+#[cfg(unix)]
+fn sync_dir(target: &Path) -> io::Result<()> {
+    match target.parent() {
+        Option::Some(dir) if !dir.as_os_str().is_empty() => File::open(dir)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_target: &Path) -> io::Result<()> {
+    Ok(())
+}
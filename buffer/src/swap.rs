@@ -0,0 +1,166 @@
+use encoding::Encoding;
+use gap_buffer::LineBuffer;
+use save;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+use {Buffer, LineEnding, MAXIMUM_GAP_SIZE};
+
+const HEADER: &str = "rustudio swap 1";
+
+/// What an existing swap file says about the session that wrote it.
+#[derive(Debug)]
+pub struct SwapInfo {
+    pub path: PathBuf,
+    pub pid: u32,
+    pub file_name: String,
+    pub modified: Option<SystemTime>,
+}
+
+impl SwapInfo {
+    /// Whether the process that wrote the swap file is still alive, in
+    /// which case another editor is most likely working on the file.
+    #[cfg(unix)]
+    pub fn is_process_running(&self) -> bool {
+        if self.pid == process::id() || self.pid == 0 || self.pid > libc::pid_t::MAX as u32 {
+            return false;
+        }
+        // signal 0 only checks that the process could be signalled; a
+        // process of another user still exists when that is not allowed
+        let signalled = unsafe { libc::kill(self.pid as libc::pid_t, 0) } == 0;
+        signalled || io::Error::last_os_error().raw_os_error() != Option::Some(libc::ESRCH)
+    }
+
+    /// Whether the process that wrote the swap file is still alive. There
+    /// is no telling here, so it is taken to be.
+    #[cfg(not(unix))]
+    pub fn is_process_running(&self) -> bool {
+        self.pid != process::id()
+    }
+}
+
+/// The swap file for `file`, a hidden `.swp` file next to it.
+pub fn swap_path(file: &Path) -> PathBuf {
+    let name = file.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    file.with_file_name(format!(".{}.swp", name))
+}
+
+/// Looks for a swap file left for `file` by a crashed or running session.
+pub fn find(file: &Path) -> io::Result<Option<SwapInfo>> {
+    let path = swap_path(file);
+    let contents = match fs::read(&path) {
+        Ok(contents) => contents,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(Option::None),
+        Err(err) => return Err(err),
+    };
+    let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+    let (header, _) = split_header(&contents)?;
+    Ok(Option::Some(SwapInfo {
+        pid: header.pid,
+        file_name: header.file_name,
+        path,
+        modified,
+    }))
+}
+
+/// Journals the current contents of `buffer` to `path`, replacing what
+/// was journaled before. Only with `sync` is it made sure they reach the
+/// disk, as that is too slow to do while keys keep coming.
+pub fn write(path: &Path, buffer: &Buffer, sync: bool) -> io::Result<()> {
+    let mut contents = format!(
+        "{}\npid {}\nfile {}\nencoding {}\nline_ending {}\nmissing_eol {}\n\n",
+        HEADER,
+        process::id(),
+        buffer.file_name().unwrap_or(""),
+        buffer.encoding(),
+        match buffer.line_ending() {
+            LineEnding::Unix => "unix",
+            LineEnding::Dos  => "dos",
+        },
+        buffer.missing_eol as u8,
+    );
+    buffer.for_each(&mut |line| {
+        line.for_each(&mut |ch| contents.push(*ch));
+        contents.push('\n');
+    });
+    if sync {
+        save::write_atomically(path, contents.as_bytes(), false, false)
+    } else {
+        save::write_unsynced(path, contents.as_bytes())
+    }
+}
+
+/// Rebuilds the journaled buffer from the swap file at `path`. The result
+/// keeps the original file name and is unsaved.
+pub fn recover(path: &Path) -> io::Result<Buffer> {
+    let contents = fs::read(path)?;
+    let (header, text) = split_header(&contents)?;
+    let text = Encoding::Utf8.decode(text)?;
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    lines.pop();
+    let mut buffer = Buffer::with_capacity(lines.len() + MAXIMUM_GAP_SIZE);
+    for line in lines {
+        buffer.insert_line(LineBuffer::from(line));
+    }
+    buffer.move_cursor(0, 0);
+    if !header.file_name.is_empty() {
+        buffer.set_file_name(&header.file_name);
+    }
+    buffer.encoding = header.encoding;
+    buffer.line_ending = header.line_ending;
+    buffer.missing_eol = header.missing_eol;
+    buffer.modified();
+    Ok(buffer)
+}
+
+pub fn remove(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+struct Header {
+    pid: u32,
+    file_name: String,
+    encoding: Encoding,
+    line_ending: LineEnding,
+    missing_eol: bool,
+}
+
+fn split_header(contents: &[u8]) -> io::Result<(Header, &[u8])> {
+    let end = contents.windows(2).position(|pair| pair == b"\n\n").ok_or_else(corrupt)?;
+    let header = ::std::str::from_utf8(&contents[..end]).map_err(|_| corrupt())?;
+    let mut lines = header.lines();
+    if lines.next() != Option::Some(HEADER) {
+        return Err(corrupt());
+    }
+    let mut parsed = Header {
+        pid: 0,
+        file_name: String::new(),
+        encoding: Encoding::default(),
+        line_ending: LineEnding::default(),
+        missing_eol: false,
+    };
+    for line in lines {
+        let (key, value) = match line.find(' ') {
+            Option::Some(space) => (&line[..space], &line[space + 1..]),
+            Option::None        => (line, ""),
+        };
+        match key {
+            "pid"         => parsed.pid = value.parse().map_err(|_| corrupt())?,
+            "file"        => parsed.file_name = value.to_string(),
+            "encoding"    => parsed.encoding = value.parse().map_err(|_| corrupt())?,
+            "line_ending" => parsed.line_ending = if value == "dos" { LineEnding::Dos } else { LineEnding::Unix },
+            "missing_eol" => parsed.missing_eol = value == "1",
+            _             => (),
+        }
+    }
+    Ok((parsed, &contents[end + 2..]))
+}
+
+fn corrupt() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "not a rustudio swap file")
+}
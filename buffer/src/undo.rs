@@ -0,0 +1,351 @@
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// One reversible change: the lines starting at `row` that were `removed`
+/// and the lines put in their place.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edit {
+    pub row: usize,
+    pub removed: Vec<String>,
+    pub inserted: Vec<String>,
+}
+
+impl Edit {
+    pub fn inverse(&self) -> Edit {
+        Edit {
+            row: self.row,
+            removed: self.inserted.clone(),
+            inserted: self.removed.clone(),
+        }
+    }
+}
+
+/// A state of the text, reached from its parent by applying `edits`.
+#[derive(Debug)]
+struct Node {
+    parent: usize,
+    edits: Vec<Edit>,
+    /// Where the cursor was right before the edits.
+    cursor: (usize, usize),
+    time: SystemTime,
+    /// The child `redo` goes to, the one most recently left or created.
+    redo_child: Option<usize>,
+}
+
+/// One step of a walk through the tree: the node whose edits are applied
+/// forwards, or reverted when `forward` is `false`.
+#[derive(Clone, Copy, Debug)]
+pub struct Step {
+    pub node: usize,
+    pub forward: bool,
+}
+
+/// Every state a buffer went through, as a tree: undoing and then changing
+/// the text starts a new branch instead of throwing the undone changes
+/// away. Nodes are numbered in the order they were created, which is the
+/// order `g-` and `g+` walk them in; node 0 is the text as it was loaded.
+#[derive(Debug)]
+pub struct UndoTree {
+    nodes: Vec<Node>,
+    current: usize,
+    pending: Vec<Edit>,
+    pending_cursor: (usize, usize),
+}
+
+impl UndoTree {
+    pub fn new() -> Self {
+        UndoTree {
+            nodes: vec![Node {
+                parent: 0,
+                edits: Vec::new(),
+                cursor: (0, 0),
+                time: SystemTime::now(),
+                redo_child: Option::None,
+            }],
+            current: 0,
+            pending: Vec::new(),
+            pending_cursor: (0, 0),
+        }
+    }
+
+    /// Adds `edit` to the change being built. Typing on one line keeps
+    /// replacing that line, so such edits are folded into one.
+    pub fn record(&mut self, edit: Edit, cursor: (usize, usize)) {
+        if self.pending.is_empty() {
+            self.pending_cursor = cursor;
+        }
+        if let Some(last) = self.pending.last_mut() {
+            if last.row == edit.row && last.inserted == edit.removed {
+                last.inserted = edit.inserted;
+                return;
+            }
+        }
+        self.pending.push(edit);
+    }
+
+    /// Whether the change being built ends with an edit leaving `row` as a
+    /// line of its own, which chars typed there can be folded into.
+    pub fn extends_line(&self, row: usize) -> bool {
+        self.pending.last().is_some_and(|last| last.row == row && last.inserted.len() == 1)
+    }
+
+    /// Adds `ch` typed at `(row, col)` to the change being built. `before`
+    /// is the line as it was, which is only needed when not
+    /// `extends_line(row)`.
+    pub fn record_char(&mut self, row: usize, col: usize, ch: char, before: Option<String>,
+                       cursor: (usize, usize)) {
+        let before = match before {
+            Option::Some(before) => before,
+            Option::None => match self.pending.last_mut() {
+                Option::Some(last) => return insert_char(&mut last.inserted[0], col, ch),
+                Option::None => return,
+            },
+        };
+        let mut after = before.clone();
+        insert_char(&mut after, col, ch);
+        self.record(Edit { row, removed: vec![before], inserted: vec![after] }, cursor);
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Closes the change being built, making it one undo step. Returns
+    /// whether there was anything to close.
+    pub fn commit(&mut self) -> bool {
+        if self.pending.is_empty() {
+            return false;
+        }
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            parent: self.current,
+            edits: ::std::mem::take(&mut self.pending),
+            cursor: self.pending_cursor,
+            time: SystemTime::now(),
+            redo_child: Option::None,
+        });
+        self.nodes[self.current].redo_child = Option::Some(node);
+        self.current = node;
+        true
+    }
+
+    /// The number of the current state, 0 for the text as it was loaded.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// The number of the newest state.
+    pub fn newest(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    pub fn time(&self, node: usize) -> SystemTime {
+        self.nodes[node].time
+    }
+
+    pub fn edits(&self, node: usize) -> &[Edit] {
+        &self.nodes[node].edits
+    }
+
+    pub fn cursor(&self, node: usize) -> (usize, usize) {
+        self.nodes[node].cursor
+    }
+
+    /// The state `u` goes back to.
+    pub fn undo_target(&self) -> Option<usize> {
+        if self.current == 0 { Option::None } else { Option::Some(self.nodes[self.current].parent) }
+    }
+
+    /// The state `Ctrl-R` goes forward to.
+    pub fn redo_target(&self) -> Option<usize> {
+        self.nodes[self.current].redo_child
+    }
+
+    /// The newest state that already existed `duration` before the
+    /// current one, for `:earlier`.
+    pub fn earlier_target(&self, duration: Duration) -> usize {
+        match self.nodes[self.current].time.checked_sub(duration) {
+            Option::Some(time) => self.newest_before(time),
+            Option::None       => 0,
+        }
+    }
+
+    /// The newest state that existed `duration` after the current one,
+    /// for `:later`.
+    pub fn later_target(&self, duration: Duration) -> usize {
+        match self.nodes[self.current].time.checked_add(duration) {
+            Option::Some(time) => self.newest_before(time).max(self.current),
+            Option::None       => self.newest(),
+        }
+    }
+
+    fn newest_before(&self, time: SystemTime) -> usize {
+        (0..self.nodes.len()).rev().find(|&node| self.nodes[node].time <= time).unwrap_or(0)
+    }
+
+    /// The steps from the current state to `target`: back up to the
+    /// closest common ancestor, then down to `target`.
+    pub fn path_to(&self, target: usize) -> Vec<Step> {
+        let down = self.ancestors(target);
+        let mut steps = Vec::new();
+        let mut node = self.current;
+        while !down.contains(&node) {
+            steps.push(Step { node, forward: false });
+            node = self.nodes[node].parent;
+        }
+        let common = down.iter().position(|&ancestor| ancestor == node).unwrap_or(0);
+        for &ancestor in down[..common].iter().rev() {
+            steps.push(Step { node: ancestor, forward: true });
+        }
+        steps
+    }
+
+    /// `node` followed by its parents up to the root.
+    fn ancestors(&self, mut node: usize) -> Vec<usize> {
+        let mut ancestors = vec![node];
+        while node != 0 {
+            node = self.nodes[node].parent;
+            ancestors.push(node);
+        }
+        ancestors
+    }
+
+    /// Makes `target` the current state once the steps to it have been
+    /// applied, pointing `redo` back along the way that was taken.
+    pub fn move_to(&mut self, steps: &[Step], target: usize) {
+        for step in steps {
+            let parent = self.nodes[step.node].parent;
+            self.nodes[parent].redo_child = Option::Some(step.node);
+        }
+        self.current = target;
+    }
+}
+
+impl UndoTree {
+    /// Writes the committed history to `out` as text, one record per line:
+    /// a `node` line per state followed by its `edit` lines, each followed
+    /// by the removed (`-`) and inserted (`+`) lines.
+    pub fn write(&self, out: &mut String) {
+        out.push_str(&format!("current {}\n", self.current));
+        for node in &self.nodes {
+            let time = node.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+            let redo_child = match node.redo_child {
+                Option::Some(child) => child.to_string(),
+                Option::None        => "-".to_string(),
+            };
+            out.push_str(&format!("node {} {} {} {} {}.{:09} {}\n",
+                                  node.parent, redo_child, node.cursor.0, node.cursor.1,
+                                  time.as_secs(), time.subsec_nanos(), node.edits.len()));
+            for edit in &node.edits {
+                out.push_str(&format!("edit {} {} {}\n", edit.row, edit.removed.len(), edit.inserted.len()));
+                for line in &edit.removed {
+                    out.push('-');
+                    out.push_str(line);
+                    out.push('\n');
+                }
+                for line in &edit.inserted {
+                    out.push('+');
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    /// Reads back a history written by `write`.
+    pub fn read(text: &str) -> io::Result<UndoTree> {
+        // not `lines()`, which would also eat a `\r` ending a line
+        let mut lines = text.split('\n');
+        let current = match fields(lines.next(), "current")?.as_slice() {
+            [current] => current.parse().map_err(|_| corrupt())?,
+            _ => return Err(corrupt()),
+        };
+        let mut nodes = Vec::new();
+        while let Some(line) = lines.next() {
+            if line.is_empty() {
+                break;
+            }
+            let (parent, redo_child, cursor, time, edit_count) = match fields(Option::Some(line), "node")?.as_slice() {
+                [parent, redo_child, row, col, time, edit_count] => (
+                    number(parent)?,
+                    if *redo_child == "-" { Option::None } else { Option::Some(number(redo_child)?) },
+                    (number(row)?, number(col)?),
+                    parse_time(time)?,
+                    number(edit_count)?,
+                ),
+                _ => return Err(corrupt()),
+            };
+            let mut edits = Vec::with_capacity(edit_count);
+            for _ in 0..edit_count {
+                let (row, removed, inserted) = match fields(lines.next(), "edit")?.as_slice() {
+                    [row, removed, inserted] => (number(row)?, number(removed)?, number(inserted)?),
+                    _ => return Err(corrupt()),
+                };
+                edits.push(Edit {
+                    row,
+                    removed: text_lines(&mut lines, '-', removed)?,
+                    inserted: text_lines(&mut lines, '+', inserted)?,
+                });
+            }
+            nodes.push(Node { parent, edits, cursor, time, redo_child });
+        }
+        let len = nodes.len();
+        let in_range = |node: usize| node < len;
+        if len == 0 || !in_range(current)
+            || nodes.iter().enumerate().any(|(index, node)| {
+                (index > 0 && node.parent >= index) || node.redo_child.is_some_and(|child| !in_range(child))
+            }) {
+            return Err(corrupt());
+        }
+        Ok(UndoTree { nodes, current, pending: Vec::new(), pending_cursor: (0, 0) })
+    }
+}
+
+/// Puts `ch` into `line` before the char at `col`.
+fn insert_char(line: &mut String, col: usize, ch: char) {
+    let at = line.char_indices().nth(col).map_or(line.len(), |(at, _)| at);
+    line.insert(at, ch);
+}
+
+/// The space separated fields of `line` after its leading `key`.
+fn fields<'a>(line: Option<&'a str>, key: &str) -> io::Result<Vec<&'a str>> {
+    let mut fields: Vec<&str> = line.ok_or_else(corrupt)?.split(' ').collect();
+    if fields.remove(0) != key {
+        return Err(corrupt());
+    }
+    Ok(fields)
+}
+
+fn text_lines<'a, I: Iterator<Item = &'a str>>(lines: &mut I, prefix: char, count: usize) -> io::Result<Vec<String>> {
+    (0..count).map(|_| {
+        match lines.next().and_then(|line| line.strip_prefix(prefix)) {
+            Option::Some(text) => Ok(text.to_string()),
+            Option::None       => Err(corrupt()),
+        }
+    }).collect()
+}
+
+fn number(field: &str) -> io::Result<usize> {
+    field.parse().map_err(|_| corrupt())
+}
+
+fn parse_time(field: &str) -> io::Result<SystemTime> {
+    let (secs, nanos) = field.split_at(field.find('.').ok_or_else(corrupt)?);
+    let secs = secs.parse().map_err(|_| corrupt())?;
+    let nanos = nanos[1..].parse().map_err(|_| corrupt())?;
+    // `Duration::new` would carry them into `secs` and may overflow
+    if nanos >= 1_000_000_000 {
+        return Err(corrupt());
+    }
+    UNIX_EPOCH.checked_add(Duration::new(secs, nanos)).ok_or_else(corrupt)
+}
+
+fn corrupt() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "not a rustudio undo file")
+}
+
+impl Default for UndoTree {
+    fn default() -> Self {
+        UndoTree::new()
+    }
+}
@@ -0,0 +1,73 @@
+use encoding::Encoding;
+use save;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use undo::UndoTree;
+use Buffer;
+
+const HEADER: &str = "rustudio undo 1";
+
+/// The undo file for `file`, a hidden `.un~` file next to it.
+pub fn undo_path(file: &Path) -> PathBuf {
+    let name = file.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    file.with_file_name(format!(".{}.un~", name))
+}
+
+/// Saves the undo history of `buffer` to `path`, tagged with the file it
+/// is for and the hash of the text the buffer has now. Call it right after
+/// the buffer was written so that the tag matches the file.
+pub fn write(path: &Path, buffer: &mut Buffer) -> io::Result<()> {
+    buffer.commit_change();
+    let mut contents = format!(
+        "{}\nfile {}\nhash {:016x}\n",
+        HEADER,
+        file_key(buffer.file_name()),
+        buffer.content_hash()?,
+    );
+    buffer.undo.write(&mut contents);
+    save::write_atomically(path, contents.as_bytes(), false, false)
+}
+
+/// Gives `buffer` the history saved in the undo file at `path`. Returns
+/// `false`, leaving the buffer alone, when there is no undo file. One
+/// written for another file or for other contents, say because the file
+/// changed outside the editor since, is an error and is not read.
+pub fn read(path: &Path, buffer: &mut Buffer) -> io::Result<bool> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    let text = Encoding::Utf8.decode(&contents)?;
+    let mut lines = text.splitn(4, '\n');
+    if lines.next() != Option::Some(HEADER) {
+        return Err(corrupt());
+    }
+    let file_name = lines.next().and_then(|line| line.strip_prefix("file ")).ok_or_else(corrupt)?;
+    let hash = lines.next()
+        .and_then(|line| line.strip_prefix("hash "))
+        .and_then(|hash| u64::from_str_radix(hash, 16).ok())
+        .ok_or_else(corrupt)?;
+    if file_name != file_key(buffer.file_name()) || hash != buffer.content_hash()? {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "undo file does not match, not read"));
+    }
+    let tree = UndoTree::read(lines.next().unwrap_or(""))?;
+    buffer.saved_state = if buffer.is_saved() { Option::Some(tree.current()) } else { Option::None };
+    buffer.undo = tree;
+    Ok(true)
+}
+
+/// The file an undo file is for as it is tagged with: its absolute path
+/// when it exists, so that it is the same however the file was named.
+fn file_key(file_name: Option<&str>) -> String {
+    let file_name = file_name.unwrap_or("");
+    match fs::canonicalize(file_name) {
+        Ok(path) => path.to_string_lossy().into_owned(),
+        Err(_)   => file_name.to_string(),
+    }
+}
+
+fn corrupt() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "not a rustudio undo file")
+}
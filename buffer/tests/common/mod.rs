@@ -0,0 +1,21 @@
+//! What the integration tests share.
+
+// each test file uses its own share of the helpers
+#![allow(dead_code)]
+
+use rustudio_buffer::Buffer;
+use std::fs;
+use std::path::PathBuf;
+
+/// A fresh directory for `test` to write its files in.
+pub fn scratch_dir(test: &str) -> PathBuf {
+    let dir = ::std::env::temp_dir().join(format!("rustudio-buffer-{}-{}", ::std::process::id(), test));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Every line of `buffer`.
+pub fn lines(buffer: &Buffer) -> Vec<String> {
+    (0..buffer.count()).filter_map(|row| buffer.line_text(row)).collect()
+}
@@ -0,0 +1,107 @@
+extern crate rustudio_buffer;
+
+mod common;
+
+use common::{lines, scratch_dir};
+use rustudio_buffer::encoding::Encoding;
+use rustudio_buffer::{Buffer, LineEnding};
+use std::fs;
+use std::io;
+
+/// Loads `bytes`, writes the buffer back out and gives what was written.
+fn round_trip(test: &str, bytes: &[u8]) -> (Buffer, Vec<u8>) {
+    let path = scratch_dir(test).join("file.txt");
+    let buffer = Buffer::from_reader(bytes).unwrap();
+    buffer.write_to(path.to_str().unwrap(), false).unwrap();
+    (buffer, fs::read(&path).unwrap())
+}
+
+#[test]
+fn utf8_comes_back_byte_for_byte() {
+    let bytes = "héllo\nwörld\n".as_bytes();
+    let (buffer, written) = round_trip("utf8", bytes);
+    assert_eq!(buffer.encoding(), Encoding::Utf8);
+    assert_eq!(lines(&buffer), ["héllo", "wörld"]);
+    assert_eq!(written, bytes);
+}
+
+#[test]
+fn a_utf8_bom_is_kept() {
+    let bytes = b"\xEF\xBB\xBFone\n";
+    let (buffer, written) = round_trip("utf8-bom", bytes);
+    assert_eq!(buffer.encoding(), Encoding::Utf8Bom);
+    assert_eq!(lines(&buffer), ["one"]);
+    assert_eq!(written, bytes);
+}
+
+#[test]
+fn utf16_in_either_byte_order_is_kept() {
+    let little = b"\xFF\xFEh\x00\xE9\x00\n\x00";
+    let (buffer, written) = round_trip("utf16le", little);
+    assert_eq!(buffer.encoding(), Encoding::Utf16Le);
+    assert_eq!(lines(&buffer), ["hé"]);
+    assert_eq!(written, little);
+
+    let big = b"\xFE\xFF\x00h\x00\xE9\x00\n";
+    let (buffer, written) = round_trip("utf16be", big);
+    assert_eq!(buffer.encoding(), Encoding::Utf16Be);
+    assert_eq!(lines(&buffer), ["hé"]);
+    assert_eq!(written, big);
+}
+
+#[test]
+fn what_is_not_utf8_is_read_as_latin1() {
+    let bytes = b"caf\xE9\n";
+    let (buffer, written) = round_trip("latin1", bytes);
+    assert_eq!(buffer.encoding(), Encoding::Latin1);
+    assert_eq!(lines(&buffer), ["café"]);
+    assert_eq!(written, bytes);
+}
+
+#[test]
+fn text_latin1_cannot_hold_is_refused_before_writing() {
+    let path = scratch_dir("latin1-refused").join("file.txt");
+    fs::write(&path, b"caf\xE9\n").unwrap();
+    let mut buffer = Buffer::open(&path).unwrap();
+    buffer.insert_text(0, 0, "€");
+    let err = buffer.write_to(path.to_str().unwrap(), false).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(fs::read(&path).unwrap(), b"caf\xE9\n");
+}
+
+#[test]
+fn odd_utf16_is_invalid() {
+    let err = Buffer::from_reader_with_encoding(&b"\xFF\xFEh"[..], Encoding::Utf16Le).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn dos_line_endings_are_kept() {
+    let bytes = b"one\r\ntwo\r\n";
+    let (buffer, written) = round_trip("dos", bytes);
+    assert_eq!(buffer.line_ending(), LineEnding::Dos);
+    assert_eq!(written, bytes);
+}
+
+#[test]
+fn a_stray_carriage_return_stays_in_its_line() {
+    let bytes = b"one\r\ntwo\n";
+    let (buffer, written) = round_trip("stray-cr", bytes);
+    assert_eq!(buffer.line_ending(), LineEnding::Unix);
+    assert_eq!(lines(&buffer), ["one\r", "two"]);
+    assert_eq!(written, bytes);
+}
+
+#[test]
+fn a_missing_newline_at_the_end_stays_missing() {
+    let (_, written) = round_trip("no-eol", b"one\ntwo");
+    assert_eq!(written, b"one\ntwo");
+}
+
+#[test]
+fn encodings_go_by_their_names() {
+    assert_eq!("UTF-8".parse(), Ok(Encoding::Utf8));
+    assert_eq!("iso-8859-1".parse(), Ok(Encoding::Latin1));
+    assert_eq!(Encoding::Utf16Be.to_string(), "utf-16be");
+    assert!("ebcdic".parse::<Encoding>().is_err());
+}
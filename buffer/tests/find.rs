@@ -0,0 +1,35 @@
+extern crate rustudio_buffer;
+
+use rustudio_buffer::{Buffer, Direction};
+
+fn buffer() -> Buffer {
+    Buffer::from_reader("ab ab\nxx\nab\n".as_bytes()).unwrap()
+}
+
+#[test]
+fn find_forward_goes_round_to_the_top() {
+    let buffer = buffer();
+    assert_eq!(buffer.find("ab", (0, 0), Direction::Forward), Option::Some((0, 3)));
+    assert_eq!(buffer.find("ab", (0, 3), Direction::Forward), Option::Some((2, 0)));
+    assert_eq!(buffer.find("ab", (2, 0), Direction::Forward), Option::Some((0, 0)));
+    // the only match is found again from itself
+    assert_eq!(buffer.find("xx", (1, 0), Direction::Forward), Option::Some((1, 0)));
+}
+
+#[test]
+fn find_backward_goes_round_to_the_bottom() {
+    let buffer = buffer();
+    assert_eq!(buffer.find("ab", (2, 0), Direction::Backward), Option::Some((0, 3)));
+    assert_eq!(buffer.find("ab", (0, 3), Direction::Backward), Option::Some((0, 0)));
+    assert_eq!(buffer.find("ab", (0, 0), Direction::Backward), Option::Some((2, 0)));
+    assert_eq!(buffer.find("xx", (1, 0), Direction::Backward), Option::Some((1, 0)));
+}
+
+#[test]
+fn find_does_not_match_over_the_end_of_a_line() {
+    let buffer = buffer();
+    assert_eq!(buffer.find("abx", (0, 0), Direction::Forward), Option::None);
+    assert_eq!(buffer.find("b\nx", (0, 0), Direction::Forward), Option::None);
+    assert_eq!(buffer.find("", (0, 0), Direction::Forward), Option::None);
+    assert_eq!(Buffer::new().find("ab", (0, 0), Direction::Backward), Option::None);
+}
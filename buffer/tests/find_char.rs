@@ -0,0 +1,25 @@
+extern crate rustudio_buffer;
+
+use rustudio_buffer::gap_buffer::LineBuffer;
+
+fn line(text: &str, caret: usize) -> LineBuffer {
+    let mut line = LineBuffer::new();
+    text.chars().for_each(|ch| line.insert(ch));
+    line.seek(caret);
+    line
+}
+
+#[test]
+fn find_char_looks_past_from_either_way() {
+    // the gap sits in the middle, which the indexes look past
+    for caret in [0, 4, 11] {
+        let line = line("a.b.c d.e.f", caret);
+        assert_eq!(line.find_char('.', 0, true), Option::Some(1), "caret {}", caret);
+        assert_eq!(line.find_char('.', 1, true), Option::Some(3));
+        assert_eq!(line.find_char('.', 3, true), Option::Some(7));
+        assert_eq!(line.find_char('.', 9, true), Option::None);
+        assert_eq!(line.find_char('.', 7, false), Option::Some(3));
+        assert_eq!(line.find_char('.', 1, false), Option::None);
+        assert_eq!(line.find_char('f', 99, false), Option::Some(10));
+    }
+}
@@ -0,0 +1,52 @@
+extern crate rustudio_buffer;
+
+mod common;
+
+use common::{lines, scratch_dir};
+use rustudio_buffer::Buffer;
+use std::fs;
+use std::io;
+
+#[test]
+fn from_reader_gives_a_line_per_line() {
+    let buffer = Buffer::from_reader("one\ntwo\n\nfour\n".as_bytes()).unwrap();
+    assert_eq!(lines(&buffer), ["one", "two", "", "four"]);
+    assert_eq!(buffer.get_cursor_index(), Option::Some((0, 0)));
+    assert_eq!(buffer.file_name(), Option::None);
+}
+
+#[test]
+fn from_reader_takes_a_last_line_without_a_newline() {
+    let buffer = Buffer::from_reader("one\ntwo".as_bytes()).unwrap();
+    assert_eq!(lines(&buffer), ["one", "two"]);
+}
+
+#[test]
+fn from_reader_strips_crlf_line_endings() {
+    let buffer = Buffer::from_reader("one\r\ntwo\r\n".as_bytes()).unwrap();
+    assert_eq!(lines(&buffer), ["one", "two"]);
+}
+
+#[test]
+fn from_reader_of_nothing_is_empty() {
+    let buffer = Buffer::from_reader("".as_bytes()).unwrap();
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.count(), 0);
+}
+
+#[test]
+fn open_names_the_buffer_after_the_file_and_starts_saved() {
+    let path = scratch_dir("open").join("file.txt");
+    fs::write(&path, "héllo\nwörld\n").unwrap();
+    let buffer = Buffer::open(&path).unwrap();
+    assert_eq!(lines(&buffer), ["héllo", "wörld"]);
+    assert_eq!(buffer.line_len(0), 5);
+    assert_eq!(buffer.file_name(), path.to_str());
+    assert!(buffer.is_saved());
+}
+
+#[test]
+fn open_of_a_missing_file_is_not_found() {
+    let path = scratch_dir("missing").join("file.txt");
+    assert_eq!(Buffer::open(&path).unwrap_err().kind(), io::ErrorKind::NotFound);
+}
@@ -0,0 +1,53 @@
+extern crate rustudio_buffer;
+
+mod common;
+
+use common::lines;
+use rustudio_buffer::Buffer;
+
+fn buffer() -> Buffer {
+    Buffer::from_reader("one\ntwo\nthree\n".as_bytes()).unwrap()
+}
+
+#[test]
+fn text_range_spans_lines() {
+    let buffer = buffer();
+    assert_eq!(buffer.text_range((0, 1), (0, 3)), "ne");
+    assert_eq!(buffer.text_range((0, 2), (2, 2)), "e\ntwo\nth");
+    // a column past the end of a line stands for its newline
+    assert_eq!(buffer.text_range((0, 0), (0, 4)), "one\n");
+    assert_eq!(buffer.text_range((1, 3), (2, 0)), "\n");
+    assert_eq!(buffer.text_range((1, 0), (9, 0)), "two\nthree");
+}
+
+#[test]
+fn remove_text_joins_what_is_left() {
+    let mut buffer = buffer();
+    assert_eq!(buffer.remove_text((0, 2), (2, 2)), "e\ntwo\nth");
+    assert_eq!(lines(&buffer), ["onree"]);
+    assert_eq!(buffer.get_cursor_index(), Option::Some((0, 2)));
+
+    let mut buffer = self::buffer();
+    assert_eq!(buffer.remove_text((0, 3), (0, 4)), "\n");
+    assert_eq!(lines(&buffer), ["onetwo", "three"]);
+}
+
+#[test]
+fn replace_text_is_one_change() {
+    let mut buffer = buffer();
+    buffer.commit_change();
+    assert_eq!(buffer.replace_text((0, 1), (1, 2), "X\nY"), "ne\ntw");
+    assert_eq!(lines(&buffer), ["oX", "Yo", "three"]);
+    assert_eq!(buffer.get_cursor_index(), Option::Some((0, 1)));
+
+    buffer.commit_change();
+    assert!(buffer.undo());
+    assert_eq!(lines(&buffer), ["one", "two", "three"]);
+}
+
+#[test]
+fn delete_lines_stops_at_the_end() {
+    let mut buffer = buffer();
+    assert_eq!(buffer.delete_lines(1, 5), ["two", "three"]);
+    assert_eq!(lines(&buffer), ["one"]);
+}
@@ -0,0 +1,111 @@
+extern crate rustudio_buffer;
+
+mod common;
+
+use common::scratch_dir;
+use rustudio_buffer::save::{self, backup_path};
+use rustudio_buffer::Buffer;
+use std::fs;
+use std::path::Path;
+
+#[cfg(unix)]
+use std::os::unix::fs::{symlink, MetadataExt, PermissionsExt};
+
+/// The names of the files in `dir`, sorted.
+fn files_in(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn writes_a_new_file_and_leaves_no_temp_file() {
+    let dir = scratch_dir("new");
+    let path = dir.join("file.txt");
+    save::write_atomically(&path, b"one\n", false, false).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"one\n");
+    assert_eq!(files_in(&dir), ["file.txt"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn replaces_the_file_rather_than_writing_into_it() {
+    let dir = scratch_dir("replace");
+    let path = dir.join("file.txt");
+    fs::write(&path, "old\n").unwrap();
+    let before = fs::metadata(&path).unwrap().ino();
+    save::write_atomically(&path, b"new\n", false, false).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"new\n");
+    assert_ne!(fs::metadata(&path).unwrap().ino(), before);
+    assert_eq!(files_in(&dir), ["file.txt"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn keeps_the_permissions_and_owner() {
+    let path = scratch_dir("permissions").join("file.txt");
+    fs::write(&path, "old\n").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    let before = fs::metadata(&path).unwrap();
+    save::write_atomically(&path, b"new\n", false, false).unwrap();
+    let after = fs::metadata(&path).unwrap();
+    assert_eq!(after.permissions().mode() & 0o777, 0o640);
+    assert_eq!((after.uid(), after.gid()), (before.uid(), before.gid()));
+}
+
+#[cfg(unix)]
+#[test]
+fn writes_through_symlinks() {
+    let dir = scratch_dir("symlink");
+    fs::write(dir.join("target.txt"), "old\n").unwrap();
+    symlink("target.txt", dir.join("link.txt")).unwrap();
+    save::write_atomically(&dir.join("link.txt"), b"new\n", false, false).unwrap();
+    assert!(fs::symlink_metadata(dir.join("link.txt")).unwrap().file_type().is_symlink());
+    assert_eq!(fs::read(dir.join("target.txt")).unwrap(), b"new\n");
+}
+
+#[test]
+fn a_backup_keeps_the_old_contents() {
+    let dir = scratch_dir("backup");
+    let path = dir.join("file.txt");
+    fs::write(&path, "old\n").unwrap();
+    save::write_atomically(&path, b"new\n", true, false).unwrap();
+    assert_eq!(fs::read(backup_path(&path)).unwrap(), b"old\n");
+    assert_eq!(files_in(&dir), ["file.txt", "file.txt~"]);
+
+    save::write_atomically(&path, b"newer\n", false, false).unwrap();
+    assert_eq!(fs::read(backup_path(&path)).unwrap(), b"old\n");
+}
+
+#[test]
+fn a_buffer_keeping_backups_saves_one() {
+    let path = scratch_dir("buffer-backup").join("file.txt");
+    fs::write(&path, "old\n").unwrap();
+    let mut buffer = Buffer::open(&path).unwrap();
+    buffer.set_keep_backup(true);
+    buffer.insert_text(0, 0, "not ");
+    buffer.save_buffer(path.to_str().unwrap(), false).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"not old\n");
+    assert_eq!(fs::read(backup_path(&path)).unwrap(), b"old\n");
+    assert!(buffer.is_saved());
+}
+
+#[test]
+fn refuses_to_replace_what_is_not_a_file() {
+    let dir = scratch_dir("directory");
+    fs::create_dir(dir.join("sub")).unwrap();
+    assert!(save::write_atomically(&dir.join("sub"), b"new\n", false, false).is_err());
+    assert!(dir.join("sub").is_dir());
+}
+
+#[test]
+fn write_unsynced_replaces_the_file_too() {
+    let dir = scratch_dir("unsynced");
+    let path = dir.join("file.txt");
+    fs::write(&path, "old\n").unwrap();
+    save::write_unsynced(&path, b"new\n").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"new\n");
+    assert_eq!(files_in(&dir), ["file.txt"]);
+}
@@ -0,0 +1,73 @@
+extern crate rustudio_buffer;
+
+mod common;
+
+use common::{lines, scratch_dir};
+use rustudio_buffer::encoding::Encoding;
+use rustudio_buffer::swap::{self, SwapInfo};
+use rustudio_buffer::{Buffer, LineEnding};
+use std::fs;
+use std::io;
+use std::process;
+
+#[test]
+fn recover_gives_back_the_journaled_buffer() {
+    let path = scratch_dir("recover").join("file.txt");
+    fs::write(&path, b"caf\xE9\r\nend").unwrap();
+    let mut buffer = Buffer::open(&path).unwrap();
+    buffer.insert_text(0, 0, "new ");
+    let swap_path = swap::swap_path(&path);
+    swap::write(&swap_path, &buffer, true).unwrap();
+
+    let recovered = swap::recover(&swap_path).unwrap();
+    assert_eq!(lines(&recovered), ["new café", "end"]);
+    assert_eq!(recovered.file_name(), path.to_str());
+    assert_eq!(recovered.encoding(), Encoding::Latin1);
+    assert_eq!(recovered.line_ending(), LineEnding::Dos);
+    assert!(!recovered.is_saved());
+
+    recovered.write_to(path.to_str().unwrap(), false).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"new caf\xE9\r\nend");
+}
+
+#[test]
+fn find_tells_who_wrote_the_swap_file() {
+    let path = scratch_dir("find").join("file.txt");
+    assert!(swap::find(&path).unwrap().is_none());
+
+    let mut buffer = Buffer::new();
+    buffer.set_file_name(path.to_str().unwrap());
+    swap::write(&swap::swap_path(&path), &buffer, false).unwrap();
+    let info = swap::find(&path).unwrap().unwrap();
+    assert_eq!(info.path, swap::swap_path(&path));
+    assert_eq!(info.pid, process::id());
+    assert_eq!(info.file_name, path.to_str().unwrap());
+    assert!(info.modified.is_some());
+
+    swap::remove(&info.path).unwrap();
+    assert!(swap::find(&path).unwrap().is_none());
+    swap::remove(&info.path).unwrap();
+}
+
+#[test]
+fn what_is_not_a_swap_file_is_invalid() {
+    let path = scratch_dir("corrupt").join("file.txt");
+    fs::write(swap::swap_path(&path), "something else\n\ntext\n").unwrap();
+    assert_eq!(swap::find(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert_eq!(swap::recover(&swap::swap_path(&path)).unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
+
+#[cfg(unix)]
+#[test]
+fn only_other_live_processes_count_as_running() {
+    let info = |pid| SwapInfo { path: Default::default(), pid, file_name: String::new(), modified: Option::None };
+    assert!(!info(process::id()).is_process_running());
+    assert!(!info(0).is_process_running());
+    assert!(!info(u32::MAX).is_process_running());
+    assert!(info(1).is_process_running());
+
+    let mut child = process::Command::new("true").spawn().unwrap();
+    let pid = child.id();
+    child.wait().unwrap();
+    assert!(!info(pid).is_process_running());
+}
@@ -0,0 +1,123 @@
+extern crate rustudio_buffer;
+
+mod common;
+
+use common::{lines, scratch_dir};
+use rustudio_buffer::undofile;
+use rustudio_buffer::Buffer;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Writes `text` to `name` in `dir`, then opens it, appends `!` to the
+/// first line, saves it and writes its undo file.
+fn edit_and_save(dir: &Path, name: &str, text: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, text).unwrap();
+    let mut buffer = Buffer::open(&path).unwrap();
+    let len = buffer.line_len(0);
+    buffer.insert_text(0, len, "!");
+    buffer.save_buffer(path.to_str().unwrap(), false).unwrap();
+    undofile::write(&undofile::undo_path(&path), &mut buffer).unwrap();
+    path
+}
+
+#[test]
+fn read_gives_back_the_written_history() {
+    let dir = scratch_dir("round-trip");
+    let path = edit_and_save(&dir, "file.txt", "one\ntwo\n");
+
+    let mut buffer = Buffer::open(&path).unwrap();
+    assert!(undofile::read(&undofile::undo_path(&path), &mut buffer).unwrap());
+    assert_eq!(lines(&buffer), ["one!", "two"]);
+    assert!(buffer.undo());
+    assert_eq!(lines(&buffer), ["one", "two"]);
+    assert!(buffer.redo());
+    assert_eq!(lines(&buffer), ["one!", "two"]);
+    assert!(buffer.is_saved());
+}
+
+#[test]
+fn read_without_an_undo_file_leaves_the_buffer_alone() {
+    let dir = scratch_dir("missing");
+    let path = dir.join("file.txt");
+    fs::write(&path, "one\n").unwrap();
+
+    let mut buffer = Buffer::open(&path).unwrap();
+    assert!(!undofile::read(&undofile::undo_path(&path), &mut buffer).unwrap());
+    assert!(!buffer.undo());
+}
+
+#[test]
+fn read_refuses_an_undo_file_for_other_contents() {
+    let dir = scratch_dir("changed");
+    let path = edit_and_save(&dir, "file.txt", "one\n");
+    fs::write(&path, "changed outside\n").unwrap();
+
+    let mut buffer = Buffer::open(&path).unwrap();
+    let err = undofile::read(&undofile::undo_path(&path), &mut buffer).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "undo file does not match, not read");
+    assert!(!buffer.undo());
+}
+
+#[test]
+fn read_refuses_an_undo_file_for_another_file() {
+    let dir = scratch_dir("other-file");
+    let path = edit_and_save(&dir, "file.txt", "one\n");
+    let other = dir.join("other.txt");
+    fs::copy(&path, &other).unwrap();
+    fs::copy(undofile::undo_path(&path), undofile::undo_path(&other)).unwrap();
+
+    let mut buffer = Buffer::open(&other).unwrap();
+    let err = undofile::read(&undofile::undo_path(&other), &mut buffer).unwrap_err();
+    assert_eq!(err.to_string(), "undo file does not match, not read");
+    assert!(!buffer.undo());
+}
+
+#[test]
+fn read_matches_the_file_however_it_is_named() {
+    let dir = scratch_dir("relative");
+    edit_and_save(&dir, "file.txt", "one\n");
+    let roundabout = dir.join(".").join("file.txt");
+
+    let mut buffer = Buffer::open(&roundabout).unwrap();
+    assert!(undofile::read(&undofile::undo_path(&roundabout), &mut buffer).unwrap());
+    assert!(buffer.undo());
+    assert_eq!(lines(&buffer), ["one"]);
+}
+
+#[test]
+fn read_rejects_what_is_not_an_undo_file() {
+    let dir = scratch_dir("corrupt");
+    let path = dir.join("file.txt");
+    fs::write(&path, "one\n").unwrap();
+    fs::write(undofile::undo_path(&path), "something else\n").unwrap();
+
+    let mut buffer = Buffer::open(&path).unwrap();
+    let err = undofile::read(&undofile::undo_path(&path), &mut buffer).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn read_rejects_a_time_that_overflows() {
+    let dir = scratch_dir("time");
+    let path = edit_and_save(&dir, "file.txt", "one\n");
+    let undo_path = undofile::undo_path(&path);
+    let contents: Vec<String> = fs::read_to_string(&undo_path).unwrap()
+        .split('\n')
+        .map(|line| {
+            let mut fields: Vec<&str> = line.split(' ').collect();
+            if fields[0] == "node" {
+                fields[5] = "18446744073709551615.1999999999";
+            }
+            fields.join(" ")
+        })
+        .collect();
+    fs::write(&undo_path, contents.join("\n")).unwrap();
+
+    let mut buffer = Buffer::open(&path).unwrap();
+    let err = undofile::read(&undo_path, &mut buffer).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(!buffer.undo());
+}
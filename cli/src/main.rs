@@ -1,84 +1,279 @@
+extern crate libc;
 extern crate ncurses;
-// extern crate rustudio_utils;
-// extern crate rustudio_buffer;
+extern crate rustudio_buffer;
 extern crate rustudio_engine;
+extern crate rustudio_keys;
 
 use ncurses::*;
-//use rustudio_buffer::*;
+use rustudio_buffer::*;
 use rustudio_engine::*;
+use rustudio_engine::config;
+use rustudio_engine::terminal::{Cell, Event, Style, Terminal};
+use rustudio_keys::decode::{self, Decoder};
+use std::env;
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::process;
+use std::time::Duration;
+
+/// Milliseconds to wait for the rest of an escape sequence before taking
+/// an Esc as the Esc key.
+const ESCAPE_TIMEOUT_MS: i32 = 25;
+
+const USAGE: &str = "usage: rustudio_cli [-R] [+N | +] [file | -]";
+
+/// Where the line given with `+` should put the cursor.
+enum StartLine {
+    Line(usize),
+    Last,
+}
+
+struct Options {
+    file: Option<String>,
+    from_stdin: bool,
+    read_only: bool,
+    start_line: Option<StartLine>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options {
+        file: Option::None,
+        from_stdin: false,
+        read_only: false,
+        start_line: Option::None,
+    };
+    let mut only_files = false;
+    for arg in args {
+        if !only_files && arg == "--" {
+            only_files = true;
+        } else if !only_files && arg == "-R" {
+            options.read_only = true;
+        } else if !only_files && arg == "-" {
+            options.from_stdin = true;
+        } else if !only_files && arg == "+" {
+            options.start_line = Option::Some(StartLine::Last);
+        } else if !only_files && arg.starts_with('+') {
+            match arg[1..].parse::<usize>() {
+                Ok(line) => options.start_line = Option::Some(StartLine::Line(line)),
+                Err(_)   => return Err(format!("invalid line number: {}", arg)),
+            }
+        } else if !only_files && arg.starts_with('-') {
+            return Err(format!("unknown option: {}", arg));
+        } else if options.file.is_some() {
+            return Err(format!("too many file names: {}", arg));
+        } else {
+            options.file = Option::Some(arg);
+        }
+    }
+    if options.from_stdin && options.file.is_some() {
+        return Err("cannot read from both stdin and a file".to_string());
+    }
+    Ok(options)
+}
+
+fn create_engine(options: &Options) -> io::Result<Engine> {
+    let mut engine = if options.from_stdin {
+        let buffer = Buffer::from_reader(io::stdin())?;
+        reopen_terminal()?;
+        Engine::with_buffer(buffer)
+    } else if let Some(ref file) = options.file {
+        Engine::open(file)?
+    } else {
+        Engine::new()
+    };
+    engine.set_read_only(options.read_only);
+    match options.start_line {
+        Option::Some(StartLine::Line(line)) => engine.go_to_line(line),
+        Option::Some(StartLine::Last)       => engine.go_to_last_line(),
+        Option::None                        => (),
+    }
+    Ok(engine)
+}
+
+/// Once the text has been read from a pipe, keys have to come from the
+/// controlling terminal instead.
+fn reopen_terminal() -> io::Result<()> {
+    let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+    if unsafe { libc::dup2(tty.as_raw_fd(), libc::STDIN_FILENO) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// The ncurses standard screen. Keys ncurses knows come as key codes, the
+/// rest as bytes that `decoder` puts together.
+struct CursesTerminal {
+    decoder: Decoder,
+}
+
+impl CursesTerminal {
+    fn new() -> Self {
+        CursesTerminal { decoder: Decoder::new() }
+    }
+}
+
+impl Terminal for CursesTerminal {
+    fn read_event(&mut self, wait: Duration) -> Option<Event> {
+        loop {
+            if let Some(key) = self.decoder.next_key() {
+                return Option::Some(Event::Key(key));
+            }
+            let pending = self.decoder.is_pending();
+            timeout(if pending { ESCAPE_TIMEOUT_MS } else { wait.as_millis().min(i32::MAX as u128) as i32 });
+            let input = getch();
+            if input == ERR {
+                if !pending {
+                    return Option::Some(Event::Idle);
+                }
+                if let Some(key) = self.decoder.flush_key() {
+                    return Option::Some(Event::Key(key));
+                }
+            } else if input == KEY_RESIZE {
+                return Option::Some(Event::Resize);
+            } else if (0..=0xff).contains(&input) {
+                self.decoder.push(input as u8);
+            } else if let Option::Some(key) = decode::from_curses(input) {
+                return Option::Some(Event::Key(key));
+            }
+        }
+    }
+
+    fn size(&self) -> (usize, usize) {
+        let (mut height, mut width) = (0, 0);
+        getmaxyx(stdscr(), &mut height, &mut width);
+        (height.max(0) as usize, width.max(0) as usize)
+    }
+
+    fn draw_cells(&mut self, row: usize, cells: &[Cell]) {
+        mv(row as i32, 0);
+        for cell in cells {
+            let attributes = match cell.style {
+                Style::Normal  => A_NORMAL(),
+                Style::NonText => A_BOLD(),
+                Style::LineNumber => A_DIM(),
+                Style::Selected => A_REVERSE(),
+                Style::MatchParen => A_BOLD() | A_UNDERLINE(),
+            };
+            attron(attributes);
+            addstr(cell.ch.encode_utf8(&mut [0; 4]));
+            attroff(attributes);
+        }
+        clrtoeol();
+    }
+
+    fn set_cursor(&mut self, row: usize, col: usize) {
+        mv(row as i32, col as i32);
+    }
+
+    fn flush(&mut self) {
+        refresh();
+    }
+}
 
 fn curses_init() {
+    setlocale(LcCategory::all, "");
     initscr();
     raw();
-    /* Allow for extended keyboard (like F1). */
-    keypad(stdscr(), true);
-    noecho();
-    //noecho();               // Don't echo keystrokes
+    noecho();               // Don't echo keystrokes
     cbreak();               // Disable line buffering
-    keypad(stdscr(), true);   // Enable special keys to be recorded
+    keypad(stdscr(), true); // Enable special keys to be recorded
+    set_escdelay(ESCAPE_TIMEOUT_MS);
 }
 
 fn main() {
-    let str = "";
-    //if argc > 1 {
-    //  fn = argv[1];               // Set the filename
-    //}
-
-    curses_init();                  // Initialize ncurses
-    let mut engine = Engine::new();
-    let mut stay_alive = true;
-    while stay_alive  {
-        let input = getch() as u32;
-        match std::char::from_u32(input) {
-            Option::Some(ch) => {
-                stay_alive = engine.input(ch);
-            }
-            Option::None     =>
-                (),
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err)    => {
+            eprintln!("rustudio_cli: {}\n{}", err, USAGE);
+            process::exit(2);
         }
-        refresh();
-    }
+    };
+    let conf = match config::load() {
+        Ok(conf) => conf,
+        Err(err) => {
+            eprintln!("rustudio_cli: {}", err);
+            process::exit(1);
+        }
+    };
+    let mut engine = match create_engine(&options) {
+        Ok(engine) => engine,
+        Err(err)   => {
+            eprintln!("rustudio_cli: {}", err);
+            process::exit(1);
+        }
+    };
+    engine.set_configuration(conf);
 
-    // let mut buffer = empty_buffer();
-    // put_char(&mut buffer, 'T');
-    // put_char(&mut buffer, 'e');
-    // put_char(&mut buffer, 's');
-    // put_char(&mut buffer, 't');
-    // put_char(&mut buffer, '\n');
-    // put_char(&mut buffer, '\n');
-    // put_char(&mut buffer, '\n');
-    // put_char(&mut buffer, 'T');
-    // put_char(&mut buffer, 'e');
-    // put_char(&mut buffer, 's');
-    // put_char(&mut buffer, 't');
-    // put_char(&mut buffer, '\n');
-    // put_char(&mut buffer, '\n');
-    // put_char(&mut buffer, '\n');
-    // put_char(&mut buffer, '\n');
-    // put_char(&mut buffer, 'T');
-    // put_char(&mut buffer, 'e');
-    // put_char(&mut buffer, 's');
-    // put_char(&mut buffer, 't');
-    // put_char(&mut buffer, '\n');
-    // save_buffer(&mut buffer, "/Users/amanjsherwany/Desktop/test2-1.txt");
-    //
-    // seek_to_line(&mut buffer, 4);
-    // put_char(&mut buffer, '3');
-    // put_char(&mut buffer, '3');
-    // seek_to_col(&mut buffer, 4, 0);
-    // put_char(&mut buffer, '4');
-    // seek_to_col(&mut buffer, 4, 5);
-    // put_char(&mut buffer, '5');
-    // seek_to_col(&mut buffer, 1, 0);
-    // seek_to_col(&mut buffer, 1, 3);
-    // put_char(&mut buffer, '5');
-    //
-    // save_buffer(&mut buffer, "/Users/amanjsherwany/Desktop/test2.txt");
-    //
-    // let mut buffer = open_file("/Users/amanjsherwany/Desktop/test.txt");
-    //
-    // save_buffer(&mut buffer, "/Users/amanjsherwany/Desktop/test3.txt");
+    curses_init();                  // Initialize ncurses
+    engine.run(&mut CursesTerminal::new());
 
     refresh();                      // Refresh display
     endwin();                       // End ncurses mode
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustudio_engine::terminal::HeadlessTerminal;
+    use std::fs;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    /// The screen row the cursor is on after opening `text` with `options`.
+    fn start_row(text: &str, mut options: Options) -> usize {
+        let dir = env::temp_dir().join(format!("rustudio-cli-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("file.txt");
+        fs::write(&path, text).unwrap();
+        options.file = Option::Some(path.to_string_lossy().into_owned());
+        let mut terminal = HeadlessTerminal::new(20, 20);
+        create_engine(&options).unwrap().run(&mut terminal);
+        fs::remove_dir_all(&dir).unwrap();
+        terminal.cursor().0
+    }
+
+    #[test]
+    fn a_file_with_a_start_line_and_read_only() {
+        let options = parse(&["-R", "+12", "notes.txt"]).unwrap();
+        assert_eq!(options.file, Option::Some("notes.txt".to_string()));
+        assert!(options.read_only && !options.from_stdin);
+        assert!(matches!(options.start_line, Option::Some(StartLine::Line(12))));
+    }
+
+    #[test]
+    fn a_bare_plus_is_the_last_line() {
+        assert!(matches!(parse(&["+", "notes.txt"]).unwrap().start_line, Option::Some(StartLine::Last)));
+    }
+
+    #[test]
+    fn a_dash_reads_stdin() {
+        let options = parse(&["-"]).unwrap();
+        assert!(options.from_stdin && options.file.is_none());
+        assert!(parse(&["-", "notes.txt"]).is_err());
+    }
+
+    #[test]
+    fn everything_after_two_dashes_is_a_file() {
+        assert_eq!(parse(&["--", "-R"]).unwrap().file, Option::Some("-R".to_string()));
+        assert_eq!(parse(&["--", "+3"]).unwrap().file, Option::Some("+3".to_string()));
+    }
+
+    #[test]
+    fn bad_arguments_are_errors() {
+        assert_eq!(parse(&["+x"]).err(), Option::Some("invalid line number: +x".to_string()));
+        assert_eq!(parse(&["-q"]).err(), Option::Some("unknown option: -q".to_string()));
+        assert_eq!(parse(&["a", "b"]).err(), Option::Some("too many file names: b".to_string()));
+    }
+
+    #[test]
+    fn the_start_line_is_kept_in_the_file() {
+        let text = "1\n2\n3\n4\n";
+        assert_eq!(start_row(text, parse(&["+3"]).unwrap()), 2);
+        assert_eq!(start_row(text, parse(&["+"]).unwrap()), 3);
+        assert_eq!(start_row(text, parse(&["+99"]).unwrap()), 3);
+        assert_eq!(start_row(text, parse(&["+0"]).unwrap()), 0);
+    }
+}
@@ -0,0 +1,179 @@
+use std::time::Duration;
+use Mode;
+
+/// A parsed command-line (`:`) command.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Write { path: Option<String>, force: bool },
+    SaveAs { path: String, force: bool },
+    WriteQuit { path: Option<String>, force: bool },
+    /// `:x`: as `:wq`, but only writes when the buffer was changed.
+    Exit { path: Option<String>, force: bool },
+    Quit { force: bool },
+    Earlier(Travel),
+    Later(Travel),
+    /// `:map` and its variants. Without `rhs` it lists the mappings of
+    /// `modes` that start with `lhs`, or all of them without `lhs` either.
+    Map {
+        modes: &'static [Mode],
+        noremap: bool,
+        silent: bool,
+        buffer: bool,
+        lhs: Option<String>,
+        rhs: Option<String>,
+    },
+    Unmap { modes: &'static [Mode], buffer: bool, lhs: String },
+}
+
+/// How far `:earlier` and `:later` go through the undo history.
+#[derive(Debug, PartialEq)]
+pub enum Travel {
+    Steps(usize),
+    Time(Duration),
+}
+
+/// Command names with the length of their shortest accepted abbreviation,
+/// so that `:w`, `:wri` and `:write` all name the same command.
+const COMMANDS: &[(&str, usize)] = &[
+    ("write", 1),
+    ("wq", 2),
+    ("xit", 1),
+    ("saveas", 3),
+    ("quit", 1),
+    ("earlier", 2),
+    ("later", 3),
+    ("map", 3),
+    ("nmap", 2),
+    ("vmap", 2),
+    ("imap", 2),
+    ("noremap", 2),
+    ("nnoremap", 2),
+    ("vnoremap", 2),
+    ("inoremap", 3),
+    ("unmap", 3),
+    ("nunmap", 3),
+    ("vunmap", 2),
+    ("iunmap", 2),
+    ("omap", 2),
+    ("onoremap", 3),
+    ("ounmap", 2),
+];
+
+const NORMAL_VISUAL_OPERATOR: &[Mode] = &[Mode::Normal, Mode::Visual, Mode::OperatorPending];
+const NORMAL: &[Mode] = &[Mode::Normal];
+const VISUAL: &[Mode] = &[Mode::Visual];
+const OPERATOR: &[Mode] = &[Mode::OperatorPending];
+const INSERT: &[Mode] = &[Mode::Insert];
+
+fn full_name(name: &str) -> Option<&'static str> {
+    COMMANDS.iter()
+        .find(|&&(full, min)| name.len() >= min && full.starts_with(name))
+        .map(|&(full, _)| full)
+}
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let name_len = line.find(|ch: char| !ch.is_ascii_alphabetic()).unwrap_or(line.len());
+    let (name, rest) = line.split_at(name_len);
+    let (force, rest) = if let Some(rest) = rest.strip_prefix('!') {
+        (true, rest)
+    } else {
+        (false, rest)
+    };
+    let arg = rest.trim();
+    let path = if arg.is_empty() { Option::None } else { Option::Some(arg.to_string()) };
+
+    match full_name(name) {
+        Option::Some("write")  => Ok(Command::Write { path, force }),
+        Option::Some("wq")     => Ok(Command::WriteQuit { path, force }),
+        Option::Some("xit")    => Ok(Command::Exit { path, force }),
+        Option::Some("saveas") => match path {
+            Option::Some(path) => Ok(Command::SaveAs { path, force }),
+            Option::None       => Err("Argument required".to_string()),
+        },
+        Option::Some("quit") if path.is_none() => Ok(Command::Quit { force }),
+        Option::Some("earlier") if !force => parse_travel(arg).map(Command::Earlier),
+        Option::Some("later") if !force => parse_travel(arg).map(Command::Later),
+        Option::Some(name) if name.ends_with("map") => parse_map(name, force, arg),
+        Option::Some(_)        => Err("Trailing characters".to_string()),
+        Option::None           => Err(format!("Not an editor command: {}", line)),
+    }
+}
+
+/// The arguments of one of the map commands `name`: any of `<silent>` and
+/// `<buffer>`, then `{lhs} {rhs}` with both in key notation. As in Vim,
+/// `:map` is for normal, visual and operator-pending mode and `:map!` for
+/// insert mode.
+fn parse_map(name: &str, force: bool, arg: &str) -> Result<Command, String> {
+    let modes = match (name.trim_end_matches("map").trim_end_matches("nore").trim_end_matches("un"), force) {
+        ("", true)   => INSERT,
+        ("", false)  => NORMAL_VISUAL_OPERATOR,
+        ("n", false) => NORMAL,
+        ("v", false) => VISUAL,
+        ("o", false) => OPERATOR,
+        ("i", false) => INSERT,
+        _            => return Err("Trailing characters".to_string()),
+    };
+    let (mut silent, mut buffer) = (false, false);
+    let mut rest = arg;
+    loop {
+        if let Some(after) = strip_prefix_ignore_case(rest, "<silent>") {
+            silent = true;
+            rest = after.trim_start();
+        } else if let Some(after) = strip_prefix_ignore_case(rest, "<buffer>") {
+            buffer = true;
+            rest = after.trim_start();
+        } else {
+            break;
+        }
+    }
+    if name.ends_with("unmap") {
+        return match rest {
+            ""  => Err("Argument required".to_string()),
+            lhs => Ok(Command::Unmap { modes, buffer, lhs: lhs.to_string() }),
+        };
+    }
+    let (lhs, rhs) = match rest.find(char::is_whitespace) {
+        Option::Some(end) => (&rest[..end], rest[end..].trim_start()),
+        Option::None      => (rest, ""),
+    };
+    let non_empty = |text: &str| if text.is_empty() { Option::None } else { Option::Some(text.to_string()) };
+    Ok(Command::Map {
+        modes,
+        noremap: name.ends_with("noremap"),
+        silent,
+        buffer,
+        lhs: non_empty(lhs),
+        rhs: non_empty(rhs),
+    })
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    match text.get(..prefix.len()) {
+        Option::Some(start) if start.eq_ignore_ascii_case(prefix) => Option::Some(&text[prefix.len()..]),
+        _ => Option::None,
+    }
+}
+
+/// `{count}` undo steps, or `{N}s`, `{N}m`, `{N}h` or `{N}d` of time. No
+/// argument means one step.
+fn parse_travel(arg: &str) -> Result<Travel, String> {
+    if arg.is_empty() {
+        return Ok(Travel::Steps(1));
+    }
+    let digits = arg.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(arg.len());
+    let (count, unit) = arg.split_at(digits);
+    let count: u64 = match count.parse() {
+        Ok(count) => count,
+        Err(_) => return Err(format!("Invalid argument: {}", arg)),
+    };
+    let seconds = match unit {
+        ""  => return Ok(Travel::Steps(count as usize)),
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _   => return Err(format!("Invalid argument: {}", arg)),
+    };
+    Ok(Travel::Time(Duration::from_secs(count.saturating_mul(seconds))))
+}
@@ -0,0 +1,425 @@
+//! Loading the `Configuration` from TOML files.
+//!
+//! ```toml
+//! [options]
+//! tab_width = 4
+//! shift_width = 4
+//! expand_tab = true
+//! line_numbers = true
+//! leader = ","
+//! timeoutlen = 500
+//! matchpairs = "(:),{:},[:],<:>"
+//! skip_literals = true
+//!
+//! [keys.normal]
+//! scroll_line_down = ["<C-e>", "<leader>j"]
+//!
+//! [keys.insert]
+//! go_to_normal_mode = ["<Esc>", "jk"]
+//!
+//! [keys.operator]
+//! inner_tag = ["it", "ix"]
+//! ```
+//!
+//! `skip_literals` is a heuristic, not syntax highlighting: it takes `"..."`
+//! on one line for a string and `'x'` or `'\x'` for a char, as in C and
+//! Rust, whatever the file type. Leave it off for text where quotes mean
+//! something else.
+//!
+//! Only the TOML the settings need is understood: tables, bare or quoted
+//! keys, strings, integers, booleans and arrays.
+
+use rustudio_keys::{notation, KeyCombination};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::iter::Peekable;
+use std::path::PathBuf;
+use std::str::Chars;
+use {default_configuration, Configuration};
+
+/// A problem with a configuration file, and the line it is on.
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.path.display(), self.message)
+        } else {
+            write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+        }
+    }
+}
+
+/// The user's configuration file, `$XDG_CONFIG_HOME/rustudio/config.toml`,
+/// which defaults to `~/.config/rustudio/config.toml`.
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Option::Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Option::Some(config_home.join("rustudio").join("config.toml"))
+}
+
+/// The project's configuration file, `.rustudio.toml` in the working
+/// directory.
+pub fn project_config_path() -> PathBuf {
+    PathBuf::from(".rustudio.toml")
+}
+
+/// The default configuration with the user's and then the project's
+/// configuration files applied on top.
+pub fn load() -> Result<Configuration, ConfigError> {
+    let mut paths: Vec<PathBuf> = user_config_path().into_iter().collect();
+    paths.push(project_config_path());
+    load_from(&paths)
+}
+
+/// The default configuration with the files at `paths` applied in order,
+/// so that later files win. Files that do not exist are skipped.
+pub fn load_from(paths: &[PathBuf]) -> Result<Configuration, ConfigError> {
+    let mut conf = default_configuration();
+    for path in paths {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(ConfigError { path: path.clone(), line: 0, message: err.to_string() }),
+        };
+        apply(&mut conf, &text).map_err(|(line, message)| ConfigError { path: path.clone(), line, message })?;
+    }
+    Ok(conf)
+}
+
+/// Applies the settings in the TOML `text` to `conf`. Errors come with the
+/// line they were found on.
+pub fn apply(conf: &mut Configuration, text: &str) -> Result<(), (usize, String)> {
+    let entries = Parser::new(text).parse()?;
+    // the leader has to be known before any binding is read
+    for entry in entries.iter().filter(|entry| entry.table == ["options"]) {
+        apply_option(conf, entry).map_err(|message| (entry.line, message))?;
+    }
+    for entry in entries.iter().filter(|entry| entry.table != ["options"]) {
+        apply_binding(conf, entry).map_err(|message| (entry.line, message))?;
+    }
+    Ok(())
+}
+
+fn apply_option(conf: &mut Configuration, entry: &Entry) -> Result<(), String> {
+    let options = &mut conf.options;
+    match (entry.key.as_str(), &entry.value) {
+        ("tab_width", &Value::Integer(width)) if (1..=32).contains(&width) => options.tab_width = width as usize,
+        ("tab_width", _) => return Err("tab_width must be a number from 1 to 32".to_string()),
+        ("shift_width", &Value::Integer(width)) if (1..=32).contains(&width) => options.shift_width = width as usize,
+        ("shift_width", _) => return Err("shift_width must be a number from 1 to 32".to_string()),
+        ("expand_tab", &Value::Boolean(on)) => options.expand_tab = on,
+        ("expand_tab", _) => return Err("expand_tab must be true or false".to_string()),
+        ("line_numbers", &Value::Boolean(on)) => options.line_numbers = on,
+        ("line_numbers", _) => return Err("line_numbers must be true or false".to_string()),
+        ("leader", Value::String(leader)) => {
+            let keys = notation::parse(leader)?;
+            if keys.is_empty() {
+                return Err("leader must not be empty".to_string());
+            }
+            options.leader = keys;
+        }
+        ("leader", _) => return Err("leader must be a string".to_string()),
+        ("timeoutlen", &Value::Integer(ms)) if ms >= 0 => options.timeout_len = ms as u64,
+        ("timeoutlen", _) => return Err("timeoutlen must be a number of milliseconds".to_string()),
+        ("matchpairs", Value::String(pairs)) => options.match_pairs = parse_match_pairs(pairs)?,
+        ("matchpairs", _) => return Err("matchpairs must be a string".to_string()),
+        ("skip_literals", &Value::Boolean(on)) => options.skip_literals = on,
+        ("skip_literals", _) => return Err("skip_literals must be true or false".to_string()),
+        (key, _) => return Err(format!("unknown option '{}'", key)),
+    }
+    Ok(())
+}
+
+/// Pairs written as in Vim's `matchpairs`, like `(:),[:],<:>`.
+fn parse_match_pairs(text: &str) -> Result<Vec<(char, char)>, String> {
+    text.split(',')
+        .map(|pair| {
+            let chars: Vec<char> = pair.chars().collect();
+            match chars[..] {
+                [open, ':', close] if open != close => Ok((open, close)),
+                _ => Err(format!("invalid pair '{}' in matchpairs, use pairs like (:),[:]", pair)),
+            }
+        })
+        .collect()
+}
+
+fn apply_binding(conf: &mut Configuration, entry: &Entry) -> Result<(), String> {
+    let mode = match entry.table.as_slice() {
+        [keys, mode] if keys == "keys" => mode.as_str(),
+        [keys] if keys == "keys" => return Err("key bindings go in [keys.normal], [keys.insert], [keys.visual] or [keys.operator]".to_string()),
+        [] => return Err(format!("'{}' is not in a table", entry.key)),
+        table => return Err(format!("unknown table [{}]", table.join("."))),
+    };
+    let notations = match entry.value {
+        Value::String(ref notation) => vec![notation],
+        Value::Array(ref values) => values.iter()
+            .map(|value| match *value {
+                Value::String(ref notation) => Ok(notation),
+                _ => Err(format!("the bindings of {} must be strings", entry.key)),
+            })
+            .collect::<Result<_, _>>()?,
+        _ => return Err(format!("{} must be a key binding or a list of them", entry.key)),
+    };
+    let leader = conf.options.leader.clone();
+    let combinations = notations.into_iter()
+        .map(|text| match notation::parse_with_leader(text, &leader) {
+            Ok(ref keys) if keys.is_empty() => Err("a key binding must not be empty".to_string()),
+            result => result,
+        })
+        .collect::<Result<Vec<KeyCombination>, _>>()?;
+    match conf.bindings_mut(mode, &entry.key) {
+        Option::Some(bindings) => *bindings = combinations,
+        Option::None if matches!(mode, "normal" | "insert" | "visual" | "operator") => {
+            return Err(format!("unknown {} mode action '{}'", mode, entry.key));
+        }
+        Option::None => return Err(format!("unknown mode '{}', use normal, insert, visual or operator", mode)),
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+/// One `key = value` line and the table it is in.
+#[derive(Debug)]
+struct Entry {
+    table: Vec<String>,
+    key: String,
+    value: Value,
+    line: usize,
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+type ParseResult<T> = Result<T, (usize, String)>;
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser { chars: text.chars().peekable(), line: 1 }
+    }
+
+    fn parse(mut self) -> ParseResult<Vec<Entry>> {
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut table = Vec::new();
+        let mut tables_seen: Vec<Vec<String>> = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            let line = self.line;
+            match self.chars.peek() {
+                Option::None => return Ok(entries),
+                Option::Some('[') => {
+                    self.chars.next();
+                    if self.chars.peek() == Option::Some(&'[') {
+                        return Err(self.error("arrays of tables are not supported"));
+                    }
+                    table = self.parse_table_name()?;
+                    if tables_seen.contains(&table) {
+                        return Err((line, format!("table [{}] defined twice", table.join("."))));
+                    }
+                    tables_seen.push(table.clone());
+                }
+                Option::Some(_) => {
+                    let key = self.parse_key()?;
+                    self.skip_spaces();
+                    if self.chars.next() != Option::Some('=') {
+                        return Err((line, format!("expected '=' after '{}'", key)));
+                    }
+                    self.skip_spaces();
+                    let value = self.parse_value()?;
+                    if entries.iter().any(|entry| entry.table == table && entry.key == key) {
+                        return Err((line, format!("'{}' set twice", key)));
+                    }
+                    entries.push(Entry { table: table.clone(), key, value, line });
+                }
+            }
+            self.end_of_line()?;
+        }
+    }
+
+    fn error(&self, message: &str) -> (usize, String) {
+        (self.line, message.to_string())
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.chars.next();
+        if ch == Option::Some('\n') {
+            self.line += 1;
+        }
+        ch
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(&(' ' | '\t' | '\r')) = self.chars.peek() {
+            self.chars.next();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.chars.peek() == Option::Some(&'#') {
+            while self.chars.peek().is_some_and(|&ch| ch != '\n') {
+                self.chars.next();
+            }
+        }
+    }
+
+    /// Skips whitespace, comments and newlines.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            if self.chars.peek() != Option::Some(&'\n') {
+                return;
+            }
+            self.next();
+        }
+    }
+
+    fn end_of_line(&mut self) -> ParseResult<()> {
+        self.skip_spaces();
+        self.skip_comment();
+        match self.next() {
+            Option::None | Option::Some('\n') => Ok(()),
+            Option::Some(ch) => Err(self.error(&format!("unexpected '{}' after the value", ch))),
+        }
+    }
+
+    fn parse_table_name(&mut self) -> ParseResult<Vec<String>> {
+        let mut name = Vec::new();
+        loop {
+            self.skip_spaces();
+            name.push(self.parse_key()?);
+            self.skip_spaces();
+            match self.chars.next() {
+                Option::Some('.') => (),
+                Option::Some(']') => return Ok(name),
+                _ => return Err(self.error("expected ']' to close the table name")),
+            }
+        }
+    }
+
+    fn parse_key(&mut self) -> ParseResult<String> {
+        match self.chars.peek() {
+            Option::Some('"') | Option::Some('\'') => {
+                match self.parse_value()? {
+                    Value::String(key) => Ok(key),
+                    _ => unreachable!(),
+                }
+            }
+            _ => {
+                let mut key = String::new();
+                while let Some(&ch) = self.chars.peek() {
+                    if !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '-') {
+                        break;
+                    }
+                    key.push(ch);
+                    self.chars.next();
+                }
+                if key.is_empty() {
+                    Err(self.error("expected a key"))
+                } else {
+                    Ok(key)
+                }
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> ParseResult<Value> {
+        match self.chars.peek() {
+            Option::Some('"') => self.parse_basic_string(),
+            Option::Some('\'') => {
+                self.chars.next();
+                let mut text = String::new();
+                loop {
+                    match self.chars.next() {
+                        Option::Some('\'') => return Ok(Value::String(text)),
+                        Option::Some('\n') | Option::None => return Err(self.error("unterminated string")),
+                        Option::Some(ch) => text.push(ch),
+                    }
+                }
+            }
+            Option::Some('[') => {
+                self.chars.next();
+                let mut values = Vec::new();
+                loop {
+                    self.skip_blank_lines();
+                    if self.chars.peek() == Option::Some(&']') {
+                        self.chars.next();
+                        return Ok(Value::Array(values));
+                    }
+                    values.push(self.parse_value()?);
+                    self.skip_blank_lines();
+                    match self.chars.next() {
+                        Option::Some(',') => (),
+                        Option::Some(']') => return Ok(Value::Array(values)),
+                        _ => return Err(self.error("expected ',' or ']' in the list")),
+                    }
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = self.chars.peek() {
+                    if !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '-' || ch == '+') {
+                        break;
+                    }
+                    word.push(ch);
+                    self.chars.next();
+                }
+                match word.as_str() {
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    "" => Err(self.error("expected a value")),
+                    _ => word.replace('_', "").parse().map(Value::Integer)
+                        .map_err(|_| self.error(&format!("'{}' is not a valid value, strings need quotes", word))),
+                }
+            }
+        }
+    }
+
+    fn parse_basic_string(&mut self) -> ParseResult<Value> {
+        self.chars.next();
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                Option::Some('"') => return Ok(Value::String(text)),
+                Option::Some('\\') => {
+                    let escaped = match self.chars.next() {
+                        Option::Some('n') => '\n',
+                        Option::Some('t') => '\t',
+                        Option::Some('r') => '\r',
+                        Option::Some('"') => '"',
+                        Option::Some('\\') => '\\',
+                        Option::Some(kind @ ('u' | 'U')) => {
+                            let len = if kind == 'u' { 4 } else { 8 };
+                            let hex: String = (0..len).filter_map(|_| self.chars.next()).collect();
+                            u32::from_str_radix(&hex, 16).ok().and_then(::std::char::from_u32)
+                                .ok_or_else(|| self.error(&format!("invalid escape \\{}{}", kind, hex)))?
+                        }
+                        Option::Some(ch) => return Err(self.error(&format!("invalid escape \\{}", ch))),
+                        Option::None => return Err(self.error("unterminated string")),
+                    };
+                    text.push(escaped);
+                }
+                Option::Some('\n') | Option::None => return Err(self.error("unterminated string")),
+                Option::Some(ch) => text.push(ch),
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use rustudio_keys::Key;
+use std::collections::HashMap;
+
+/// What a sequence of keys is to a `Keymap`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lookup<T> {
+    /// Bound to a value and not the start of anything longer.
+    Complete(T),
+    /// Bound to a value, but also the start of longer sequences: whether
+    /// it stands alone depends on whether more keys come in time.
+    Ambiguous(T),
+    /// Not bound itself, only the start of longer sequences.
+    Prefix,
+    /// Neither bound nor the start of anything bound.
+    Invalid,
+}
+
+/// Key sequences and what they are bound to, as a prefix tree so that
+/// any sequence can be told apart from the start of a longer one.
+#[derive(Clone, Debug)]
+pub struct Keymap<T> {
+    value: Option<T>,
+    children: HashMap<Key, Keymap<T>>,
+}
+
+impl<T: Clone> Keymap<T> {
+    pub fn new() -> Self {
+        Keymap { value: Option::None, children: HashMap::new() }
+    }
+
+    /// Binds `keys` to `value`, replacing what it was bound to. Binding
+    /// the empty sequence does nothing.
+    pub fn insert(&mut self, keys: &[Key], value: T) {
+        if keys.is_empty() {
+            return;
+        }
+        let mut node = self;
+        for key in keys {
+            node = node.children.entry(*key).or_default();
+        }
+        node.value = Option::Some(value);
+    }
+
+    /// Unbinds `keys`, returning what it was bound to.
+    pub fn remove(&mut self, keys: &[Key]) -> Option<T> {
+        let (first, rest) = keys.split_first()?;
+        let child = self.children.get_mut(first)?;
+        let removed = if rest.is_empty() { child.value.take() } else { child.remove(rest) };
+        if child.value.is_none() && child.children.is_empty() {
+            self.children.remove(first);
+        }
+        removed
+    }
+
+    /// What `keys` is bound to, and whether more keys could still make it
+    /// into a longer sequence.
+    ///
+    /// ```
+    /// extern crate rustudio_engine;
+    /// extern crate rustudio_keys;
+    ///
+    /// use rustudio_engine::keymap::{Keymap, Lookup};
+    /// use rustudio_keys::keys;
+    ///
+    /// let mut keymap = Keymap::new();
+    /// keymap.insert(&keys("d"), "delete");
+    /// keymap.insert(&keys("dd"), "delete line");
+    /// keymap.insert(&keys("gg"), "go to top");
+    /// assert_eq!(keymap.lookup(&keys("dd")), Lookup::Complete("delete line"));
+    /// assert_eq!(keymap.lookup(&keys("d")), Lookup::Ambiguous("delete"));
+    /// assert_eq!(keymap.lookup(&keys("g")), Lookup::Prefix);
+    /// assert_eq!(keymap.lookup(&keys("gx")), Lookup::Invalid);
+    /// ```
+    pub fn lookup(&self, keys: &[Key]) -> Lookup<T> {
+        let mut node = self;
+        for key in keys {
+            node = match node.children.get(key) {
+                Option::Some(child) => child,
+                Option::None        => return Lookup::Invalid,
+            };
+        }
+        match node.value {
+            Option::Some(ref value) if node.children.is_empty() => Lookup::Complete(value.clone()),
+            Option::Some(ref value) => Lookup::Ambiguous(value.clone()),
+            Option::None if keys.is_empty() || node.children.is_empty() => Lookup::Invalid,
+            Option::None => Lookup::Prefix,
+        }
+    }
+
+    /// The longest start of `keys` that is bound, with its length. This is
+    /// what keys still waiting when `timeout_len` runs out stand for.
+    pub fn longest_match(&self, keys: &[Key]) -> Option<(usize, T)> {
+        let mut node = self;
+        let mut found = Option::None;
+        for (len, key) in keys.iter().enumerate() {
+            node = match node.children.get(key) {
+                Option::Some(child) => child,
+                Option::None        => break,
+            };
+            if let Some(ref value) = node.value {
+                found = Option::Some((len + 1, value.clone()));
+            }
+        }
+        found
+    }
+
+    /// Every bound sequence with its value, in no particular order.
+    pub fn bindings(&self) -> Vec<(Vec<Key>, T)> {
+        let mut bindings = Vec::new();
+        self.collect(&mut Vec::new(), &mut bindings);
+        bindings
+    }
+
+    fn collect(&self, prefix: &mut Vec<Key>, bindings: &mut Vec<(Vec<Key>, T)>) {
+        if let Some(ref value) = self.value {
+            bindings.push((prefix.clone(), value.clone()));
+        }
+        for (key, child) in &self.children {
+            prefix.push(*key);
+            child.collect(prefix, bindings);
+            prefix.pop();
+        }
+    }
+}
+
+impl<T: Clone> Default for Keymap<T> {
+    fn default() -> Self {
+        Keymap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustudio_keys::keys;
+
+    fn keymap(bindings: &[(&str, u32)]) -> Keymap<u32> {
+        let mut keymap = Keymap::new();
+        for &(sequence, value) in bindings {
+            keymap.insert(&keys(sequence), value);
+        }
+        keymap
+    }
+
+    #[test]
+    fn lookup_tells_the_four_states_apart() {
+        let keymap = keymap(&[("x", 1), ("d", 2), ("dd", 3), ("zt", 4)]);
+        assert_eq!(keymap.lookup(&keys("x")), Lookup::Complete(1));
+        assert_eq!(keymap.lookup(&keys("d")), Lookup::Ambiguous(2));
+        assert_eq!(keymap.lookup(&keys("dd")), Lookup::Complete(3));
+        assert_eq!(keymap.lookup(&keys("z")), Lookup::Prefix);
+        assert_eq!(keymap.lookup(&keys("zt")), Lookup::Complete(4));
+        assert_eq!(keymap.lookup(&keys("q")), Lookup::Invalid);
+        assert_eq!(keymap.lookup(&keys("xx")), Lookup::Invalid);
+        assert_eq!(keymap.lookup(&keys("ztx")), Lookup::Invalid);
+    }
+
+    #[test]
+    fn lookup_of_no_keys_is_invalid() {
+        assert_eq!(keymap(&[]).lookup(&[]), Lookup::Invalid);
+        assert_eq!(keymap(&[("x", 1)]).lookup(&[]), Lookup::Invalid);
+    }
+
+    #[test]
+    fn insert_replaces_and_ignores_the_empty_sequence() {
+        let mut keymap = keymap(&[("x", 1)]);
+        keymap.insert(&keys("x"), 2);
+        keymap.insert(&[], 3);
+        assert_eq!(keymap.lookup(&keys("x")), Lookup::Complete(2));
+        assert_eq!(keymap.bindings(), vec![(keys("x"), 2)]);
+    }
+
+    #[test]
+    fn remove_prunes_what_is_left_empty() {
+        let mut keymap = keymap(&[("d", 1), ("dw", 2), ("zt", 3)]);
+        assert_eq!(keymap.remove(&keys("dw")), Option::Some(2));
+        assert_eq!(keymap.lookup(&keys("d")), Lookup::Complete(1));
+        assert_eq!(keymap.remove(&keys("zt")), Option::Some(3));
+        assert_eq!(keymap.lookup(&keys("z")), Lookup::Invalid);
+        assert_eq!(keymap.remove(&keys("zt")), Option::None);
+        assert_eq!(keymap.remove(&[]), Option::None);
+    }
+
+    #[test]
+    fn remove_keeps_longer_sequences() {
+        let mut keymap = keymap(&[("d", 1), ("dd", 2)]);
+        assert_eq!(keymap.remove(&keys("d")), Option::Some(1));
+        assert_eq!(keymap.lookup(&keys("d")), Lookup::Prefix);
+        assert_eq!(keymap.lookup(&keys("dd")), Lookup::Complete(2));
+    }
+
+    #[test]
+    fn longest_match_settles_keys_on_timeout() {
+        let keymap = keymap(&[("g", 1), ("gqq", 2), ("z", 3)]);
+        // `gq` timed out: `g` alone, leaving `q` to be handled again
+        assert_eq!(keymap.longest_match(&keys("gq")), Option::Some((1, 1)));
+        assert_eq!(keymap.longest_match(&keys("gqq")), Option::Some((3, 2)));
+        assert_eq!(keymap.longest_match(&keys("gqqz")), Option::Some((3, 2)));
+        assert_eq!(keymap.longest_match(&keys("qg")), Option::None);
+        assert_eq!(keymap.longest_match(&[]), Option::None);
+    }
+}
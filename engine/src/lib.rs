@@ -1,13 +1,85 @@
 extern crate rustudio_keys;
 extern crate rustudio_buffer;
 
+pub mod command;
+pub mod config;
+pub mod keymap;
+pub mod motion;
+pub mod register;
+pub mod terminal;
+pub mod text_object;
+pub mod view;
+pub mod viewport;
+
+use command::{Command, Travel};
+use keymap::{Keymap, Lookup};
+use register::{Register, RegisterKind, Registers};
 use rustudio_keys::*;
 use rustudio_buffer::*;
-use std::process;
+use rustudio_buffer::swap::{self, SwapInfo};
+use rustudio_buffer::undofile;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use terminal::{Cell, Event, Style, Terminal};
+use view::View;
+use viewport::Viewport;
+
+/// Milliseconds without a key press after which pending changes are
+/// journaled to the swap file, see `Engine::idle`.
+pub const UPDATE_TIME_MS: u64 = 4000;
 
-type KeyCombination = Vec<char>;
+/// How deep mappings may expand into other mappings before the expansion
+/// is taken to be endless.
+const MAX_MAP_DEPTH: usize = 1000;
 
+/// Changes after which the swap file is brought up to date even while
+/// keys keep coming.
+const UPDATE_COUNT: u64 = 200;
+
+/// Settings that are not key bindings.
+pub struct Options {
+    /// Columns between tab stops.
+    pub tab_width: usize,
+    /// Columns `>` and `<` shift lines by.
+    pub shift_width: usize,
+    /// Whether indents are made of spaces only, rather than tabs as far
+    /// as they go.
+    pub expand_tab: bool,
+    /// Whether to show line numbers left of the text.
+    pub line_numbers: bool,
+    /// The keys `<leader>` stands for in bindings.
+    pub leader: KeyCombination,
+    /// Milliseconds to wait for the next key of a sequence when the keys
+    /// so far are bound but also start a longer binding.
+    pub timeout_len: u64,
+    /// The pairs of chars `%` jumps between, opening char first.
+    pub match_pairs: Vec<(char, char)>,
+    /// Whether `%`, `i(` and the like skip brackets in what looks like a
+    /// string or char literal. It is a guess the C and Rust way, not syntax
+    /// info, and misreads text where quotes mean something else.
+    pub skip_literals: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            tab_width: 8,
+            shift_width: 8,
+            expand_tab: false,
+            line_numbers: false,
+            leader: keys(&notation::DEFAULT_LEADER.to_string()),
+            timeout_len: 1000,
+            match_pairs: vec![('(', ')'), ('{', '}'), ('[', ']')],
+            skip_literals: false,
+        }
+    }
+}
+
+#[allow(dead_code)]
 pub struct Configuration {
+    pub options: Options,
     open_line_above: Vec<KeyCombination>,
     open_line_below: Vec<KeyCombination>,
     go_to_start: Vec<KeyCombination>,
@@ -16,124 +88,2539 @@ pub struct Configuration {
     go_to_down: Vec<KeyCombination>,
     go_to_up: Vec<KeyCombination>,
     go_to_right: Vec<KeyCombination>,
+    word_forward: Vec<KeyCombination>,
+    big_word_forward: Vec<KeyCombination>,
+    word_backward: Vec<KeyCombination>,
+    big_word_backward: Vec<KeyCombination>,
+    word_end: Vec<KeyCombination>,
+    big_word_end: Vec<KeyCombination>,
+    word_end_backward: Vec<KeyCombination>,
+    big_word_end_backward: Vec<KeyCombination>,
+    sentence_forward: Vec<KeyCombination>,
+    sentence_backward: Vec<KeyCombination>,
+    paragraph_forward: Vec<KeyCombination>,
+    paragraph_backward: Vec<KeyCombination>,
+    go_to_first_non_blank: Vec<KeyCombination>,
+    go_to_last_non_blank: Vec<KeyCombination>,
+    go_to_first_line: Vec<KeyCombination>,
+    go_to_last_line: Vec<KeyCombination>,
+    go_to_screen_top: Vec<KeyCombination>,
+    go_to_screen_middle: Vec<KeyCombination>,
+    go_to_screen_bottom: Vec<KeyCombination>,
+    find_forward: Vec<KeyCombination>,
+    find_backward: Vec<KeyCombination>,
+    till_forward: Vec<KeyCombination>,
+    till_backward: Vec<KeyCombination>,
+    repeat_find: Vec<KeyCombination>,
+    repeat_find_reverse: Vec<KeyCombination>,
+    match_pair: Vec<KeyCombination>,
+    search_forward: Vec<KeyCombination>,
+    search_backward: Vec<KeyCombination>,
+    search_next: Vec<KeyCombination>,
+    search_previous: Vec<KeyCombination>,
+    search_word_forward: Vec<KeyCombination>,
+    search_word_backward: Vec<KeyCombination>,
+    inner_word: Vec<KeyCombination>,
+    a_word: Vec<KeyCombination>,
+    inner_big_word: Vec<KeyCombination>,
+    a_big_word: Vec<KeyCombination>,
+    inner_sentence: Vec<KeyCombination>,
+    a_sentence: Vec<KeyCombination>,
+    inner_paragraph: Vec<KeyCombination>,
+    a_paragraph: Vec<KeyCombination>,
+    inner_double_quote: Vec<KeyCombination>,
+    a_double_quote: Vec<KeyCombination>,
+    inner_single_quote: Vec<KeyCombination>,
+    a_single_quote: Vec<KeyCombination>,
+    inner_back_quote: Vec<KeyCombination>,
+    a_back_quote: Vec<KeyCombination>,
+    inner_paren: Vec<KeyCombination>,
+    a_paren: Vec<KeyCombination>,
+    inner_bracket: Vec<KeyCombination>,
+    a_bracket: Vec<KeyCombination>,
+    inner_brace: Vec<KeyCombination>,
+    a_brace: Vec<KeyCombination>,
+    inner_angle: Vec<KeyCombination>,
+    a_angle: Vec<KeyCombination>,
+    inner_tag: Vec<KeyCombination>,
+    a_tag: Vec<KeyCombination>,
     go_to_normal_mode: Vec<KeyCombination>,
     insert_char_here: Vec<KeyCombination>,
-    write_buffer: Vec<KeyCombination>,
-    quit_editor: Vec<KeyCombination>,
+    delete_char: Vec<KeyCombination>,
+    delete: Vec<KeyCombination>,
+    change: Vec<KeyCombination>,
+    yank: Vec<KeyCombination>,
+    shift_right: Vec<KeyCombination>,
+    shift_left: Vec<KeyCombination>,
+    reindent: Vec<KeyCombination>,
+    lowercase: Vec<KeyCombination>,
+    uppercase: Vec<KeyCombination>,
+    toggle_case: Vec<KeyCombination>,
+    visual_mode: Vec<KeyCombination>,
+    visual_line_mode: Vec<KeyCombination>,
+    visual_block_mode: Vec<KeyCombination>,
+    leave_visual_mode: Vec<KeyCombination>,
+    swap_ends: Vec<KeyCombination>,
+    block_insert: Vec<KeyCombination>,
+    block_append: Vec<KeyCombination>,
+    put_after: Vec<KeyCombination>,
+    put_before: Vec<KeyCombination>,
+    put_after_move: Vec<KeyCombination>,
+    put_before_move: Vec<KeyCombination>,
+    go_to_command_mode: Vec<KeyCombination>,
+    scroll_line_down: Vec<KeyCombination>,
+    scroll_line_up: Vec<KeyCombination>,
+    scroll_half_page_down: Vec<KeyCombination>,
+    scroll_half_page_up: Vec<KeyCombination>,
+    scroll_page_down: Vec<KeyCombination>,
+    scroll_page_up: Vec<KeyCombination>,
+    scroll_cursor_top: Vec<KeyCombination>,
+    scroll_cursor_center: Vec<KeyCombination>,
+    scroll_cursor_bottom: Vec<KeyCombination>,
+    undo: Vec<KeyCombination>,
+    redo: Vec<KeyCombination>,
+    undo_older: Vec<KeyCombination>,
+    undo_newer: Vec<KeyCombination>,
+    /// Mappings made with `:map` and friends, in the order they were made.
+    pub mappings: Vec<Mapping>,
+}
+
+/// A key sequence that stands for other keys, as made by `:map`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mapping {
+    pub mode: Mode,
+    pub lhs: KeyCombination,
+    pub rhs: KeyCombination,
+    /// Whether the keys of `rhs` run their built-in bindings rather than
+    /// being mapped again.
+    pub noremap: bool,
+    /// Whether messages given while the mapping runs are kept off the
+    /// status line.
+    pub silent: bool,
+    /// Whether the mapping belongs to the buffer rather than the editor.
+    pub buffer: bool,
 }
 
 pub fn default_configuration() -> Configuration {
     Configuration {
-        open_line_above: vec![vec!['o']],
-        open_line_below: vec![vec!['O']],
-        go_to_start: vec![vec!['0']],
-        go_to_end: vec![vec!['$']],
-        go_to_left: vec![vec!['h']],
-        go_to_down: vec![vec!['j']],
-        go_to_up: vec![vec!['k']],
-        go_to_right: vec![vec!['l']],
-        go_to_normal_mode: vec![vec![Ctrl, '['], vec![Esc]],
-        insert_char_here: vec![vec!['i']],
-        write_buffer: vec![vec![':', 'w', '\n']],
-        quit_editor: vec![vec![':', 'q', '\n']],
+        options: Options::default(),
+        open_line_above: bind(&["o"]),
+        open_line_below: bind(&["O"]),
+        go_to_start: bind(&["0", "<Home>"]),
+        go_to_end: bind(&["$", "<End>"]),
+        go_to_left: bind(&["h", "<Left>"]),
+        go_to_down: bind(&["j", "<Down>"]),
+        go_to_up: bind(&["k", "<Up>"]),
+        go_to_right: bind(&["l", "<Right>"]),
+        word_forward: bind(&["w"]),
+        big_word_forward: bind(&["W"]),
+        word_backward: bind(&["b"]),
+        big_word_backward: bind(&["B"]),
+        word_end: bind(&["e"]),
+        big_word_end: bind(&["E"]),
+        word_end_backward: bind(&["ge"]),
+        big_word_end_backward: bind(&["gE"]),
+        sentence_forward: bind(&[")"]),
+        sentence_backward: bind(&["("]),
+        paragraph_forward: bind(&["}"]),
+        paragraph_backward: bind(&["{"]),
+        go_to_first_non_blank: bind(&["^"]),
+        go_to_last_non_blank: bind(&["g_"]),
+        go_to_first_line: bind(&["gg"]),
+        go_to_last_line: bind(&["G"]),
+        go_to_screen_top: bind(&["H"]),
+        go_to_screen_middle: bind(&["M"]),
+        go_to_screen_bottom: bind(&["L"]),
+        find_forward: bind(&["f"]),
+        find_backward: bind(&["F"]),
+        till_forward: bind(&["t"]),
+        till_backward: bind(&["T"]),
+        repeat_find: bind(&[";"]),
+        repeat_find_reverse: bind(&[","]),
+        match_pair: bind(&["%"]),
+        search_forward: bind(&["/"]),
+        search_backward: bind(&["?"]),
+        search_next: bind(&["n"]),
+        search_previous: bind(&["N"]),
+        search_word_forward: bind(&["*"]),
+        search_word_backward: bind(&["#"]),
+        inner_word: bind(&["iw"]),
+        a_word: bind(&["aw"]),
+        inner_big_word: bind(&["iW"]),
+        a_big_word: bind(&["aW"]),
+        inner_sentence: bind(&["is"]),
+        a_sentence: bind(&["as"]),
+        inner_paragraph: bind(&["ip"]),
+        a_paragraph: bind(&["ap"]),
+        inner_double_quote: bind(&["i\""]),
+        a_double_quote: bind(&["a\""]),
+        inner_single_quote: bind(&["i'"]),
+        a_single_quote: bind(&["a'"]),
+        inner_back_quote: bind(&["i`"]),
+        a_back_quote: bind(&["a`"]),
+        inner_paren: bind(&["i(", "i)", "ib"]),
+        a_paren: bind(&["a(", "a)", "ab"]),
+        inner_bracket: bind(&["i[", "i]"]),
+        a_bracket: bind(&["a[", "a]"]),
+        inner_brace: bind(&["i{", "i}", "iB"]),
+        a_brace: bind(&["a{", "a}", "aB"]),
+        inner_angle: bind(&["i<lt>", "i>"]),
+        a_angle: bind(&["a<lt>", "a>"]),
+        inner_tag: bind(&["it"]),
+        a_tag: bind(&["at"]),
+        go_to_normal_mode: bind(&["<C-[>", "<Esc>"]),
+        insert_char_here: bind(&["i"]),
+        delete_char: bind(&["x", "<Del>"]),
+        delete: bind(&["d"]),
+        change: bind(&["c"]),
+        yank: bind(&["y"]),
+        shift_right: bind(&[">"]),
+        shift_left: bind(&["<lt>"]),
+        reindent: bind(&["="]),
+        lowercase: bind(&["gu"]),
+        uppercase: bind(&["gU"]),
+        toggle_case: bind(&["g~"]),
+        visual_mode: bind(&["v"]),
+        visual_line_mode: bind(&["V"]),
+        visual_block_mode: bind(&["<C-v>"]),
+        leave_visual_mode: bind(&["<C-[>", "<Esc>"]),
+        swap_ends: bind(&["o"]),
+        block_insert: bind(&["I"]),
+        block_append: bind(&["A"]),
+        put_after: bind(&["p"]),
+        put_before: bind(&["P"]),
+        put_after_move: bind(&["gp"]),
+        put_before_move: bind(&["gP"]),
+        go_to_command_mode: bind(&[":"]),
+        scroll_line_down: bind(&["<C-e>"]),
+        scroll_line_up: bind(&["<C-y>"]),
+        scroll_half_page_down: bind(&["<C-d>"]),
+        scroll_half_page_up: bind(&["<C-u>"]),
+        scroll_page_down: bind(&["<C-f>", "<PageDown>"]),
+        scroll_page_up: bind(&["<C-b>", "<PageUp>"]),
+        scroll_cursor_top: bind(&["zt"]),
+        scroll_cursor_center: bind(&["zz"]),
+        scroll_cursor_bottom: bind(&["zb"]),
+        undo: bind(&["u"]),
+        redo: bind(&["<C-r>"]),
+        undo_older: bind(&["g-"]),
+        undo_newer: bind(&["g+"]),
+        mappings: Vec::new(),
+    }
+}
+
+/// What a key binding does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    OpenLineAbove,
+    OpenLineBelow,
+    GoToStart,
+    GoToEnd,
+    GoToLeft,
+    GoToDown,
+    GoToUp,
+    GoToRight,
+    WordForward,
+    BigWordForward,
+    WordBackward,
+    BigWordBackward,
+    WordEnd,
+    BigWordEnd,
+    WordEndBackward,
+    BigWordEndBackward,
+    SentenceForward,
+    SentenceBackward,
+    ParagraphForward,
+    ParagraphBackward,
+    GoToFirstNonBlank,
+    GoToLastNonBlank,
+    GoToFirstLine,
+    GoToLastLine,
+    GoToScreenTop,
+    GoToScreenMiddle,
+    GoToScreenBottom,
+    FindForward,
+    FindBackward,
+    TillForward,
+    TillBackward,
+    RepeatFind,
+    RepeatFindReverse,
+    MatchPair,
+    SearchForward,
+    SearchBackward,
+    SearchNext,
+    SearchPrevious,
+    SearchWordForward,
+    SearchWordBackward,
+    InnerWord,
+    AWord,
+    InnerBigWord,
+    ABigWord,
+    InnerSentence,
+    ASentence,
+    InnerParagraph,
+    AParagraph,
+    InnerDoubleQuote,
+    ADoubleQuote,
+    InnerSingleQuote,
+    ASingleQuote,
+    InnerBackQuote,
+    ABackQuote,
+    InnerParen,
+    AParen,
+    InnerBracket,
+    ABracket,
+    InnerBrace,
+    ABrace,
+    InnerAngle,
+    AAngle,
+    InnerTag,
+    ATag,
+    GoToNormalMode,
+    InsertCharHere,
+    DeleteChar,
+    Delete,
+    Change,
+    Yank,
+    ShiftRight,
+    ShiftLeft,
+    Reindent,
+    Lowercase,
+    Uppercase,
+    ToggleCase,
+    VisualMode,
+    VisualLineMode,
+    VisualBlockMode,
+    LeaveVisualMode,
+    SwapEnds,
+    BlockInsert,
+    BlockAppend,
+    PutAfter,
+    PutBefore,
+    PutAfterMove,
+    PutBeforeMove,
+    GoToCommandMode,
+    ScrollLineDown,
+    ScrollLineUp,
+    ScrollHalfPageDown,
+    ScrollHalfPageUp,
+    ScrollPageDown,
+    ScrollPageUp,
+    ScrollCursorTop,
+    ScrollCursorCenter,
+    ScrollCursorBottom,
+    Undo,
+    Redo,
+    UndoOlder,
+    UndoNewer,
+}
+
+impl Action {
+    /// Whether the action moves the cursor, so that an operator can work
+    /// on the text it moves over.
+    pub fn is_motion(&self) -> bool {
+        matches!(*self, Action::GoToStart | Action::GoToEnd | Action::GoToLeft | Action::GoToDown
+                        | Action::GoToUp | Action::GoToRight | Action::WordForward | Action::BigWordForward
+                        | Action::WordBackward | Action::BigWordBackward | Action::WordEnd
+                        | Action::BigWordEnd | Action::WordEndBackward | Action::BigWordEndBackward
+                        | Action::SentenceForward | Action::SentenceBackward | Action::ParagraphForward
+                        | Action::ParagraphBackward | Action::GoToFirstNonBlank | Action::GoToLastNonBlank
+                        | Action::GoToFirstLine | Action::GoToLastLine | Action::GoToScreenTop
+                        | Action::GoToScreenMiddle | Action::GoToScreenBottom | Action::MatchPair
+                        | Action::SearchForward | Action::SearchBackward | Action::SearchNext
+                        | Action::SearchPrevious | Action::SearchWordForward | Action::SearchWordBackward)
+            || self.finds_char()
+    }
+
+    /// Whether the action finds a char in the line: `f`, `F`, `t` and `T`,
+    /// or `;` and `,` repeating them.
+    pub fn finds_char(&self) -> bool {
+        self.takes_char() || matches!(*self, Action::RepeatFind | Action::RepeatFindReverse)
+    }
+
+    /// Whether the action takes the char it finds from the next key.
+    pub fn takes_char(&self) -> bool {
+        matches!(*self, Action::FindForward | Action::FindBackward | Action::TillForward | Action::TillBackward)
+    }
+
+    /// Whether the action works in visual mode as it does in normal mode,
+    /// or on the selection for operators.
+    fn in_visual(&self) -> bool {
+        self.is_motion() || self.is_operator() || self.is_text_object()
+            || matches!(*self, Action::DeleteChar | Action::VisualMode | Action::VisualLineMode
+                               | Action::VisualBlockMode)
+    }
+
+    /// Whether the action is an operator, waiting for a motion to say what
+    /// text it works on.
+    pub fn is_operator(&self) -> bool {
+        matches!(*self, Action::Delete | Action::Change | Action::Yank | Action::ShiftRight
+                        | Action::ShiftLeft | Action::Reindent | Action::Lowercase
+                        | Action::Uppercase | Action::ToggleCase)
+    }
+
+    /// Whether the action selects a text object, like `iw` or `a(`, for
+    /// an operator or visual mode.
+    pub fn is_text_object(&self) -> bool {
+        matches!(*self, Action::InnerWord | Action::AWord | Action::InnerBigWord | Action::ABigWord
+                        | Action::InnerSentence | Action::ASentence | Action::InnerParagraph
+                        | Action::AParagraph | Action::InnerDoubleQuote | Action::ADoubleQuote
+                        | Action::InnerSingleQuote | Action::ASingleQuote | Action::InnerBackQuote
+                        | Action::ABackQuote | Action::InnerParen | Action::AParen | Action::InnerBracket
+                        | Action::ABracket | Action::InnerBrace | Action::ABrace | Action::InnerAngle
+                        | Action::AAngle | Action::InnerTag | Action::ATag)
     }
 }
 
+/// What a key sequence is bound to in a `Keymap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Action(Action),
+    /// The mapping at this index of `Configuration::mappings`.
+    Mapping(usize),
+}
+
+/// Every action with the mode it is bound in and its name in
+/// configuration files.
+const ACTIONS: &[(Mode, &str, Action)] = &[
+    (Mode::Insert, "go_to_normal_mode", Action::GoToNormalMode),
+    (Mode::Normal, "open_line_above", Action::OpenLineAbove),
+    (Mode::Normal, "open_line_below", Action::OpenLineBelow),
+    (Mode::Normal, "go_to_start", Action::GoToStart),
+    (Mode::Normal, "go_to_end", Action::GoToEnd),
+    (Mode::Normal, "go_to_left", Action::GoToLeft),
+    (Mode::Normal, "go_to_down", Action::GoToDown),
+    (Mode::Normal, "go_to_up", Action::GoToUp),
+    (Mode::Normal, "go_to_right", Action::GoToRight),
+    (Mode::Normal, "word_forward", Action::WordForward),
+    (Mode::Normal, "big_word_forward", Action::BigWordForward),
+    (Mode::Normal, "word_backward", Action::WordBackward),
+    (Mode::Normal, "big_word_backward", Action::BigWordBackward),
+    (Mode::Normal, "word_end", Action::WordEnd),
+    (Mode::Normal, "big_word_end", Action::BigWordEnd),
+    (Mode::Normal, "word_end_backward", Action::WordEndBackward),
+    (Mode::Normal, "big_word_end_backward", Action::BigWordEndBackward),
+    (Mode::Normal, "sentence_forward", Action::SentenceForward),
+    (Mode::Normal, "sentence_backward", Action::SentenceBackward),
+    (Mode::Normal, "paragraph_forward", Action::ParagraphForward),
+    (Mode::Normal, "paragraph_backward", Action::ParagraphBackward),
+    (Mode::Normal, "go_to_first_non_blank", Action::GoToFirstNonBlank),
+    (Mode::Normal, "go_to_last_non_blank", Action::GoToLastNonBlank),
+    (Mode::Normal, "go_to_first_line", Action::GoToFirstLine),
+    (Mode::Normal, "go_to_last_line", Action::GoToLastLine),
+    (Mode::Normal, "go_to_screen_top", Action::GoToScreenTop),
+    (Mode::Normal, "go_to_screen_middle", Action::GoToScreenMiddle),
+    (Mode::Normal, "go_to_screen_bottom", Action::GoToScreenBottom),
+    (Mode::Normal, "find_forward", Action::FindForward),
+    (Mode::Normal, "find_backward", Action::FindBackward),
+    (Mode::Normal, "till_forward", Action::TillForward),
+    (Mode::Normal, "till_backward", Action::TillBackward),
+    (Mode::Normal, "repeat_find", Action::RepeatFind),
+    (Mode::Normal, "repeat_find_reverse", Action::RepeatFindReverse),
+    (Mode::Normal, "match_pair", Action::MatchPair),
+    (Mode::Normal, "search_forward", Action::SearchForward),
+    (Mode::Normal, "search_backward", Action::SearchBackward),
+    (Mode::Normal, "search_next", Action::SearchNext),
+    (Mode::Normal, "search_previous", Action::SearchPrevious),
+    (Mode::Normal, "search_word_forward", Action::SearchWordForward),
+    (Mode::Normal, "search_word_backward", Action::SearchWordBackward),
+    (Mode::OperatorPending, "inner_word", Action::InnerWord),
+    (Mode::OperatorPending, "a_word", Action::AWord),
+    (Mode::OperatorPending, "inner_big_word", Action::InnerBigWord),
+    (Mode::OperatorPending, "a_big_word", Action::ABigWord),
+    (Mode::OperatorPending, "inner_sentence", Action::InnerSentence),
+    (Mode::OperatorPending, "a_sentence", Action::ASentence),
+    (Mode::OperatorPending, "inner_paragraph", Action::InnerParagraph),
+    (Mode::OperatorPending, "a_paragraph", Action::AParagraph),
+    (Mode::OperatorPending, "inner_double_quote", Action::InnerDoubleQuote),
+    (Mode::OperatorPending, "a_double_quote", Action::ADoubleQuote),
+    (Mode::OperatorPending, "inner_single_quote", Action::InnerSingleQuote),
+    (Mode::OperatorPending, "a_single_quote", Action::ASingleQuote),
+    (Mode::OperatorPending, "inner_back_quote", Action::InnerBackQuote),
+    (Mode::OperatorPending, "a_back_quote", Action::ABackQuote),
+    (Mode::OperatorPending, "inner_paren", Action::InnerParen),
+    (Mode::OperatorPending, "a_paren", Action::AParen),
+    (Mode::OperatorPending, "inner_bracket", Action::InnerBracket),
+    (Mode::OperatorPending, "a_bracket", Action::ABracket),
+    (Mode::OperatorPending, "inner_brace", Action::InnerBrace),
+    (Mode::OperatorPending, "a_brace", Action::ABrace),
+    (Mode::OperatorPending, "inner_angle", Action::InnerAngle),
+    (Mode::OperatorPending, "a_angle", Action::AAngle),
+    (Mode::OperatorPending, "inner_tag", Action::InnerTag),
+    (Mode::OperatorPending, "a_tag", Action::ATag),
+    (Mode::Normal, "insert_char_here", Action::InsertCharHere),
+    (Mode::Normal, "delete_char", Action::DeleteChar),
+    (Mode::Normal, "delete", Action::Delete),
+    (Mode::Normal, "change", Action::Change),
+    (Mode::Normal, "yank", Action::Yank),
+    (Mode::Normal, "shift_right", Action::ShiftRight),
+    (Mode::Normal, "shift_left", Action::ShiftLeft),
+    (Mode::Normal, "reindent", Action::Reindent),
+    (Mode::Normal, "lowercase", Action::Lowercase),
+    (Mode::Normal, "uppercase", Action::Uppercase),
+    (Mode::Normal, "toggle_case", Action::ToggleCase),
+    (Mode::Normal, "visual_mode", Action::VisualMode),
+    (Mode::Normal, "visual_line_mode", Action::VisualLineMode),
+    (Mode::Normal, "visual_block_mode", Action::VisualBlockMode),
+    (Mode::Visual, "leave_visual_mode", Action::LeaveVisualMode),
+    (Mode::Visual, "swap_ends", Action::SwapEnds),
+    (Mode::Visual, "block_insert", Action::BlockInsert),
+    (Mode::Visual, "block_append", Action::BlockAppend),
+    (Mode::Normal, "put_after", Action::PutAfter),
+    (Mode::Normal, "put_before", Action::PutBefore),
+    (Mode::Normal, "put_after_move", Action::PutAfterMove),
+    (Mode::Normal, "put_before_move", Action::PutBeforeMove),
+    (Mode::Normal, "go_to_command_mode", Action::GoToCommandMode),
+    (Mode::Normal, "scroll_line_down", Action::ScrollLineDown),
+    (Mode::Normal, "scroll_line_up", Action::ScrollLineUp),
+    (Mode::Normal, "scroll_half_page_down", Action::ScrollHalfPageDown),
+    (Mode::Normal, "scroll_half_page_up", Action::ScrollHalfPageUp),
+    (Mode::Normal, "scroll_page_down", Action::ScrollPageDown),
+    (Mode::Normal, "scroll_page_up", Action::ScrollPageUp),
+    (Mode::Normal, "scroll_cursor_top", Action::ScrollCursorTop),
+    (Mode::Normal, "scroll_cursor_center", Action::ScrollCursorCenter),
+    (Mode::Normal, "scroll_cursor_bottom", Action::ScrollCursorBottom),
+    (Mode::Normal, "undo", Action::Undo),
+    (Mode::Normal, "redo", Action::Redo),
+    (Mode::Normal, "undo_older", Action::UndoOlder),
+    (Mode::Normal, "undo_newer", Action::UndoNewer),
+];
+
+impl Configuration {
+    pub fn bindings(&self, action: Action) -> &[KeyCombination] {
+        match action {
+            Action::OpenLineAbove      => &self.open_line_above,
+            Action::OpenLineBelow      => &self.open_line_below,
+            Action::GoToStart          => &self.go_to_start,
+            Action::GoToEnd            => &self.go_to_end,
+            Action::GoToLeft           => &self.go_to_left,
+            Action::GoToDown           => &self.go_to_down,
+            Action::GoToUp             => &self.go_to_up,
+            Action::GoToRight          => &self.go_to_right,
+            Action::WordForward        => &self.word_forward,
+            Action::BigWordForward     => &self.big_word_forward,
+            Action::WordBackward       => &self.word_backward,
+            Action::BigWordBackward    => &self.big_word_backward,
+            Action::WordEnd            => &self.word_end,
+            Action::BigWordEnd         => &self.big_word_end,
+            Action::WordEndBackward    => &self.word_end_backward,
+            Action::BigWordEndBackward => &self.big_word_end_backward,
+            Action::SentenceForward    => &self.sentence_forward,
+            Action::SentenceBackward   => &self.sentence_backward,
+            Action::ParagraphForward   => &self.paragraph_forward,
+            Action::ParagraphBackward  => &self.paragraph_backward,
+            Action::GoToFirstNonBlank  => &self.go_to_first_non_blank,
+            Action::GoToLastNonBlank   => &self.go_to_last_non_blank,
+            Action::GoToFirstLine      => &self.go_to_first_line,
+            Action::GoToLastLine       => &self.go_to_last_line,
+            Action::GoToScreenTop      => &self.go_to_screen_top,
+            Action::GoToScreenMiddle   => &self.go_to_screen_middle,
+            Action::GoToScreenBottom   => &self.go_to_screen_bottom,
+            Action::FindForward        => &self.find_forward,
+            Action::FindBackward       => &self.find_backward,
+            Action::TillForward        => &self.till_forward,
+            Action::TillBackward       => &self.till_backward,
+            Action::RepeatFind         => &self.repeat_find,
+            Action::RepeatFindReverse  => &self.repeat_find_reverse,
+            Action::MatchPair          => &self.match_pair,
+            Action::SearchForward      => &self.search_forward,
+            Action::SearchBackward     => &self.search_backward,
+            Action::SearchNext         => &self.search_next,
+            Action::SearchPrevious     => &self.search_previous,
+            Action::SearchWordForward  => &self.search_word_forward,
+            Action::SearchWordBackward => &self.search_word_backward,
+            Action::InnerWord          => &self.inner_word,
+            Action::AWord              => &self.a_word,
+            Action::InnerBigWord       => &self.inner_big_word,
+            Action::ABigWord           => &self.a_big_word,
+            Action::InnerSentence      => &self.inner_sentence,
+            Action::ASentence          => &self.a_sentence,
+            Action::InnerParagraph     => &self.inner_paragraph,
+            Action::AParagraph         => &self.a_paragraph,
+            Action::InnerDoubleQuote   => &self.inner_double_quote,
+            Action::ADoubleQuote       => &self.a_double_quote,
+            Action::InnerSingleQuote   => &self.inner_single_quote,
+            Action::ASingleQuote       => &self.a_single_quote,
+            Action::InnerBackQuote     => &self.inner_back_quote,
+            Action::ABackQuote         => &self.a_back_quote,
+            Action::InnerParen         => &self.inner_paren,
+            Action::AParen             => &self.a_paren,
+            Action::InnerBracket       => &self.inner_bracket,
+            Action::ABracket           => &self.a_bracket,
+            Action::InnerBrace         => &self.inner_brace,
+            Action::ABrace             => &self.a_brace,
+            Action::InnerAngle         => &self.inner_angle,
+            Action::AAngle             => &self.a_angle,
+            Action::InnerTag           => &self.inner_tag,
+            Action::ATag               => &self.a_tag,
+            Action::GoToNormalMode     => &self.go_to_normal_mode,
+            Action::InsertCharHere     => &self.insert_char_here,
+            Action::DeleteChar         => &self.delete_char,
+            Action::Delete             => &self.delete,
+            Action::Change             => &self.change,
+            Action::Yank               => &self.yank,
+            Action::ShiftRight         => &self.shift_right,
+            Action::ShiftLeft          => &self.shift_left,
+            Action::Reindent           => &self.reindent,
+            Action::Lowercase          => &self.lowercase,
+            Action::Uppercase          => &self.uppercase,
+            Action::ToggleCase         => &self.toggle_case,
+            Action::VisualMode         => &self.visual_mode,
+            Action::VisualLineMode     => &self.visual_line_mode,
+            Action::VisualBlockMode    => &self.visual_block_mode,
+            Action::LeaveVisualMode    => &self.leave_visual_mode,
+            Action::SwapEnds           => &self.swap_ends,
+            Action::BlockInsert        => &self.block_insert,
+            Action::BlockAppend        => &self.block_append,
+            Action::PutAfter           => &self.put_after,
+            Action::PutBefore          => &self.put_before,
+            Action::PutAfterMove       => &self.put_after_move,
+            Action::PutBeforeMove      => &self.put_before_move,
+            Action::GoToCommandMode    => &self.go_to_command_mode,
+            Action::ScrollLineDown     => &self.scroll_line_down,
+            Action::ScrollLineUp       => &self.scroll_line_up,
+            Action::ScrollHalfPageDown => &self.scroll_half_page_down,
+            Action::ScrollHalfPageUp   => &self.scroll_half_page_up,
+            Action::ScrollPageDown     => &self.scroll_page_down,
+            Action::ScrollPageUp       => &self.scroll_page_up,
+            Action::ScrollCursorTop    => &self.scroll_cursor_top,
+            Action::ScrollCursorCenter => &self.scroll_cursor_center,
+            Action::ScrollCursorBottom => &self.scroll_cursor_bottom,
+            Action::Undo               => &self.undo,
+            Action::Redo               => &self.redo,
+            Action::UndoOlder          => &self.undo_older,
+            Action::UndoNewer          => &self.undo_newer,
+        }
+    }
+
+    /// The bindings of the action named `action` in `mode`, `"normal"`,
+    /// `"insert"`, `"visual"` or `"operator"`, as it is called in
+    /// configuration files.
+    pub fn bindings_mut(&mut self, mode: &str, action: &str) -> Option<&mut Vec<KeyCombination>> {
+        let &(_, _, action) = ACTIONS.iter()
+            .find(|&&(action_mode, name, _)| name == action && action_mode.name() == mode)?;
+        Option::Some(match action {
+            Action::OpenLineAbove      => &mut self.open_line_above,
+            Action::OpenLineBelow      => &mut self.open_line_below,
+            Action::GoToStart          => &mut self.go_to_start,
+            Action::GoToEnd            => &mut self.go_to_end,
+            Action::GoToLeft           => &mut self.go_to_left,
+            Action::GoToDown           => &mut self.go_to_down,
+            Action::GoToUp             => &mut self.go_to_up,
+            Action::GoToRight          => &mut self.go_to_right,
+            Action::WordForward        => &mut self.word_forward,
+            Action::BigWordForward     => &mut self.big_word_forward,
+            Action::WordBackward       => &mut self.word_backward,
+            Action::BigWordBackward    => &mut self.big_word_backward,
+            Action::WordEnd            => &mut self.word_end,
+            Action::BigWordEnd         => &mut self.big_word_end,
+            Action::WordEndBackward    => &mut self.word_end_backward,
+            Action::BigWordEndBackward => &mut self.big_word_end_backward,
+            Action::SentenceForward    => &mut self.sentence_forward,
+            Action::SentenceBackward   => &mut self.sentence_backward,
+            Action::ParagraphForward   => &mut self.paragraph_forward,
+            Action::ParagraphBackward  => &mut self.paragraph_backward,
+            Action::GoToFirstNonBlank  => &mut self.go_to_first_non_blank,
+            Action::GoToLastNonBlank   => &mut self.go_to_last_non_blank,
+            Action::GoToFirstLine      => &mut self.go_to_first_line,
+            Action::GoToLastLine       => &mut self.go_to_last_line,
+            Action::GoToScreenTop      => &mut self.go_to_screen_top,
+            Action::GoToScreenMiddle   => &mut self.go_to_screen_middle,
+            Action::GoToScreenBottom   => &mut self.go_to_screen_bottom,
+            Action::FindForward        => &mut self.find_forward,
+            Action::FindBackward       => &mut self.find_backward,
+            Action::TillForward        => &mut self.till_forward,
+            Action::TillBackward       => &mut self.till_backward,
+            Action::RepeatFind         => &mut self.repeat_find,
+            Action::RepeatFindReverse  => &mut self.repeat_find_reverse,
+            Action::MatchPair          => &mut self.match_pair,
+            Action::SearchForward      => &mut self.search_forward,
+            Action::SearchBackward     => &mut self.search_backward,
+            Action::SearchNext         => &mut self.search_next,
+            Action::SearchPrevious     => &mut self.search_previous,
+            Action::SearchWordForward  => &mut self.search_word_forward,
+            Action::SearchWordBackward => &mut self.search_word_backward,
+            Action::InnerWord          => &mut self.inner_word,
+            Action::AWord              => &mut self.a_word,
+            Action::InnerBigWord       => &mut self.inner_big_word,
+            Action::ABigWord           => &mut self.a_big_word,
+            Action::InnerSentence      => &mut self.inner_sentence,
+            Action::ASentence          => &mut self.a_sentence,
+            Action::InnerParagraph     => &mut self.inner_paragraph,
+            Action::AParagraph         => &mut self.a_paragraph,
+            Action::InnerDoubleQuote   => &mut self.inner_double_quote,
+            Action::ADoubleQuote       => &mut self.a_double_quote,
+            Action::InnerSingleQuote   => &mut self.inner_single_quote,
+            Action::ASingleQuote       => &mut self.a_single_quote,
+            Action::InnerBackQuote     => &mut self.inner_back_quote,
+            Action::ABackQuote         => &mut self.a_back_quote,
+            Action::InnerParen         => &mut self.inner_paren,
+            Action::AParen             => &mut self.a_paren,
+            Action::InnerBracket       => &mut self.inner_bracket,
+            Action::ABracket           => &mut self.a_bracket,
+            Action::InnerBrace         => &mut self.inner_brace,
+            Action::ABrace             => &mut self.a_brace,
+            Action::InnerAngle         => &mut self.inner_angle,
+            Action::AAngle             => &mut self.a_angle,
+            Action::InnerTag           => &mut self.inner_tag,
+            Action::ATag               => &mut self.a_tag,
+            Action::GoToNormalMode     => &mut self.go_to_normal_mode,
+            Action::InsertCharHere     => &mut self.insert_char_here,
+            Action::DeleteChar         => &mut self.delete_char,
+            Action::Delete             => &mut self.delete,
+            Action::Change             => &mut self.change,
+            Action::Yank               => &mut self.yank,
+            Action::ShiftRight         => &mut self.shift_right,
+            Action::ShiftLeft          => &mut self.shift_left,
+            Action::Reindent           => &mut self.reindent,
+            Action::Lowercase          => &mut self.lowercase,
+            Action::Uppercase          => &mut self.uppercase,
+            Action::ToggleCase         => &mut self.toggle_case,
+            Action::VisualMode         => &mut self.visual_mode,
+            Action::VisualLineMode     => &mut self.visual_line_mode,
+            Action::VisualBlockMode    => &mut self.visual_block_mode,
+            Action::LeaveVisualMode    => &mut self.leave_visual_mode,
+            Action::SwapEnds           => &mut self.swap_ends,
+            Action::BlockInsert        => &mut self.block_insert,
+            Action::BlockAppend        => &mut self.block_append,
+            Action::PutAfter           => &mut self.put_after,
+            Action::PutBefore          => &mut self.put_before,
+            Action::PutAfterMove       => &mut self.put_after_move,
+            Action::PutBeforeMove      => &mut self.put_before_move,
+            Action::GoToCommandMode    => &mut self.go_to_command_mode,
+            Action::ScrollLineDown     => &mut self.scroll_line_down,
+            Action::ScrollLineUp       => &mut self.scroll_line_up,
+            Action::ScrollHalfPageDown => &mut self.scroll_half_page_down,
+            Action::ScrollHalfPageUp   => &mut self.scroll_half_page_up,
+            Action::ScrollPageDown     => &mut self.scroll_page_down,
+            Action::ScrollPageUp       => &mut self.scroll_page_up,
+            Action::ScrollCursorTop    => &mut self.scroll_cursor_top,
+            Action::ScrollCursorCenter => &mut self.scroll_cursor_center,
+            Action::ScrollCursorBottom => &mut self.scroll_cursor_bottom,
+            Action::Undo               => &mut self.undo,
+            Action::Redo               => &mut self.redo,
+            Action::UndoOlder          => &mut self.undo_older,
+            Action::UndoNewer          => &mut self.undo_newer,
+        })
+    }
+
+    /// The bindings of the actions of `mode` as a `Keymap`, with the
+    /// mappings of `mode` over them when `remap` is set.
+    ///
+    /// Visual mode also takes the motions and operators of normal mode,
+    /// and so does operator-pending mode, and the text objects of
+    /// operator-pending mode. An operator in operator-pending
+    /// mode, or just the last key of it, makes the pending operator work
+    /// on lines, as in `dd` and `guu`.
+    pub fn keymap(&self, mode: Mode, remap: bool) -> Keymap<Binding> {
+        let mut keymap = Keymap::new();
+        for &(action_mode, _, action) in ACTIONS {
+            if action_mode == mode || (mode == Mode::Visual && action_mode != Mode::Insert && action.in_visual()) {
+                for keys in self.bindings(action) {
+                    keymap.insert(keys, Binding::Action(action));
+                }
+            } else if mode == Mode::OperatorPending && action_mode == Mode::Normal {
+                for keys in self.bindings(action) {
+                    if action.is_operator() {
+                        keymap.insert(&keys[keys.len() - 1..], Binding::Action(action));
+                    }
+                }
+                for keys in self.bindings(action) {
+                    if action.is_motion() || action.is_operator() {
+                        keymap.insert(keys, Binding::Action(action));
+                    }
+                }
+            }
+        }
+        if remap {
+            for (index, mapping) in self.mappings.iter().enumerate() {
+                if mapping.mode == mode {
+                    keymap.insert(&mapping.lhs, Binding::Mapping(index));
+                }
+            }
+        }
+        keymap
+    }
+}
+
+/// The key combinations written in key notation in `notations`.
+fn bind(notations: &[&str]) -> Vec<KeyCombination> {
+    notations.iter()
+        .map(|text| notation::parse(text).unwrap_or_else(|err| panic!("bad default binding {}: {}", text, err)))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Mode {
     Normal,
-    Insert
+    Insert,
+    Visual,
+    /// After an operator, waiting for the motion it works on.
+    OperatorPending,
+    Command,
+}
+
+impl Mode {
+    /// The name of the mode in configuration files.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Mode::Normal  => "normal",
+            Mode::Insert  => "insert",
+            Mode::Visual  => "visual",
+            Mode::OperatorPending => "operator",
+            Mode::Command => "command",
+        }
+    }
 }
 
+/// The text from one position to another, `end` being where the cursor
+/// went and possibly before `start`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Selection {
-    start_line: usize,
-    start_col: usize,
-    end_line: usize,
-    end_col: usize,
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+impl Selection {
+    pub fn new((start_line, start_col): (usize, usize), (end_line, end_col): (usize, usize)) -> Self {
+        Selection { start_line, start_col, end_line, end_col }
+    }
+
+    /// The two ends as `(row, col)`, the one that comes first first.
+    pub fn ordered(&self) -> ((usize, usize), (usize, usize)) {
+        let start = (self.start_line, self.start_col);
+        let end = (self.end_line, self.end_col);
+        if end < start { (end, start) } else { (start, end) }
+    }
+}
+
+/// What visual mode selects between its two ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VisualKind {
+    /// `v`: the chars from one end to the other.
+    Char,
+    /// `V`: the whole lines.
+    Line,
+    /// `Ctrl-V`: the columns between the ends on each line.
+    Block,
+}
+
+impl VisualKind {
+    /// Whether `(row, col)` is selected when the ends are `start` and `end`
+    /// in order.
+    fn contains(self, (start, end): ((usize, usize), (usize, usize)), row: usize, col: usize) -> bool {
+        match self {
+            VisualKind::Char  => (row, col) >= start && (row, col) <= end,
+            VisualKind::Line  => row >= start.0 && row <= end.0,
+            VisualKind::Block => {
+                row >= start.0 && row <= end.0 && col >= start.1.min(end.1) && col <= start.1.max(end.1)
+            }
+        }
+    }
+}
+
+/// Text typed in visual-block mode with `I` or `A`, to go into the other
+/// lines of the block once insert mode is left.
+struct BlockInsert {
+    first: usize,
+    last: usize,
+    col: usize,
+    /// Whether lines too short to reach `col` are padded with spaces
+    /// rather than skipped.
+    pad: bool,
+}
+
+/// How an operator takes the text a motion moves over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MotionKind {
+    /// Up to but not including the end, like `h` and `0`.
+    Exclusive,
+    /// Up to and including the char at the end, like `$`.
+    Inclusive,
+    /// The whole lines from start to end, like `j` and `k`.
+    Linewise,
+}
+
+/// The keymaps of one mode: `remap` for typed keys and the keys of
+/// recursive mappings, `noremap` for the keys of `:noremap` mappings.
+struct ModeKeymaps {
+    remap: Keymap<Binding>,
+    noremap: Keymap<Binding>,
+}
+
+impl ModeKeymaps {
+    fn new(conf: &Configuration, mode: Mode) -> Self {
+        ModeKeymaps { remap: conf.keymap(mode, true), noremap: conf.keymap(mode, false) }
+    }
 }
 
 pub struct Engine {
     mode: Mode,
     buffer: Buffer,
     conf: Configuration,
-    command_buffer: Vec<char>,
+    command_buffer: Vec<Key>,
+    /// The count typed in front of the pending command, as in `5j`.
+    count: Option<usize>,
+    /// How many times the text typed since entering insert mode goes in
+    /// when leaving it, as in `3ihello<Esc>`.
+    insert_count: usize,
+    /// The text typed since entering insert mode.
+    inserted: String,
+    /// The operator waiting for its motion in operator-pending mode, with
+    /// the count typed before it.
+    operator: Option<(Action, Option<usize>)>,
+    registers: Registers,
+    /// The register selected with `"` for the next command.
+    register_name: Option<char>,
+    /// Set after a `"`, while the name of the register is still to come.
+    awaiting_register: bool,
+    /// Set after `f`, `F`, `t` or `T`, while the char to find is still to
+    /// come.
+    awaiting_find: Option<Action>,
+    /// The last `f`, `F`, `t` or `T` with the char it found, for `;` and
+    /// `,` to repeat.
+    last_find: Option<(Action, char)>,
+    /// In visual mode, what is selected. The end of the selection is kept
+    /// at the cursor.
+    visual: Option<(VisualKind, Selection)>,
+    block_insert: Option<BlockInsert>,
+    /// Whether the keys in `command_buffer` may run mappings.
+    remap_pending: bool,
+    /// How many mappings are expanding inside each other right now.
+    map_depth: usize,
+    /// Set when a mapping expanded too deep, to drop the rest of the
+    /// expansion.
+    map_aborted: bool,
+    normal_keys: ModeKeymaps,
+    insert_keys: ModeKeymaps,
+    visual_keys: ModeKeymaps,
+    operator_keys: ModeKeymaps,
+    command_line: String,
+    /// What the command line starts with: `:` for commands, `/` or `?`
+    /// for searches.
+    command_prompt: char,
+    /// The mode a search was started from, to go back to.
+    search_from: Mode,
+    /// The last pattern searched for and whether forward, for `n` and `N`.
+    last_search: Option<(String, bool)>,
+    /// The patterns searched for, the latest last.
+    search_history: Vec<String>,
+    /// The entry of `search_history` on the command line, if one is.
+    history_index: Option<usize>,
+    /// What was typed on the command line before going through the
+    /// history.
+    history_typed: String,
+    message: Option<String>,
+    read_only: bool,
+    swap_path: Option<PathBuf>,
+    swap_conflict: Option<SwapInfo>,
+    swap_changes: u64,
+    swap_written_at: Instant,
+    view: View,
+    viewport: Viewport,
 }
 
 impl Engine where {
     pub fn new() -> Self {
+        Engine::with_buffer(Buffer::new())
+    }
+
+    pub fn with_buffer(buffer: Buffer) -> Self {
+        let conf = default_configuration();
         Engine {
             mode: Mode::Normal,
-            buffer: Buffer::new(),
-            conf: default_configuration(),
+            buffer,
+            normal_keys: ModeKeymaps::new(&conf, Mode::Normal),
+            insert_keys: ModeKeymaps::new(&conf, Mode::Insert),
+            visual_keys: ModeKeymaps::new(&conf, Mode::Visual),
+            operator_keys: ModeKeymaps::new(&conf, Mode::OperatorPending),
+            conf,
             command_buffer: Vec::new(),
+            count: Option::None,
+            insert_count: 1,
+            inserted: String::new(),
+            operator: Option::None,
+            registers: Registers::new(),
+            register_name: Option::None,
+            awaiting_register: false,
+            awaiting_find: Option::None,
+            last_find: Option::None,
+            visual: Option::None,
+            block_insert: Option::None,
+            remap_pending: true,
+            map_depth: 0,
+            map_aborted: false,
+            command_line: String::new(),
+            command_prompt: ':',
+            search_from: Mode::Normal,
+            last_search: Option::None,
+            search_history: Vec::new(),
+            history_index: Option::None,
+            history_typed: String::new(),
+            message: Option::None,
+            read_only: false,
+            swap_path: Option::None,
+            swap_conflict: Option::None,
+            swap_changes: 0,
+            swap_written_at: Instant::now(),
+            view: View::new(),
+            viewport: Viewport::new(),
+        }
+    }
+
+    /// Starts editing the file at `path`. A path that does not exist yet
+    /// gives an empty buffer that will be created on the first write. The
+    /// undo history saved with the file is picked up, unless the file was
+    /// changed since.
+    ///
+    /// If a swap file for `path` is found, the engine first asks whether
+    /// to recover it, open the file read-only or delete the swap file; the
+    /// question is the engine's `message` and the next key answers it.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut buffer = match Buffer::open(path) {
+            Ok(buffer) => buffer,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                let mut buffer = Buffer::new();
+                buffer.set_file_name(&path.to_string_lossy());
+                buffer
+            }
+            Err(err) => return Err(err),
+        };
+        let undo_path = undofile::undo_path(path);
+        let undo_error = undofile::read(&undo_path, &mut buffer).err()
+            .map(|err| format!("\"{}\" {}", undo_path.display(), err));
+        let mut engine = Engine::with_buffer(buffer);
+        engine.message = undo_error;
+        match swap::find(path) {
+            Ok(Option::Some(info)) => {
+                engine.message = Option::Some(Engine::swap_prompt(&info));
+                engine.swap_conflict = Option::Some(info);
+            }
+            Ok(Option::None) => engine.take_swap(),
+            Err(err) => {
+                let swap_path = swap::swap_path(path);
+                engine.message = Option::Some(format!("\"{}\" {}", swap_path.display(), err));
+            }
+        }
+        Ok(engine)
+    }
+
+    /// Runs the editor on `terminal` until it quits or the terminal runs
+    /// out of input.
+    pub fn run(&mut self, terminal: &mut dyn Terminal) {
+        self.render(terminal);
+        while let Some(event) = terminal.read_event(self.input_timeout()) {
+            let stay_alive = match event {
+                Event::Key(key) => self.input(key),
+                Event::Resize => {
+                    self.view.invalidate();
+                    true
+                }
+                Event::Idle => self.idle(),
+            };
+            if !stay_alive {
+                return;
+            }
+            self.render(terminal);
+        }
+    }
+
+    /// How long to wait for a key before calling `idle`: `timeout_len`
+    /// while the keys typed so far may still grow into a longer binding,
+    /// `UPDATE_TIME_MS` otherwise.
+    pub fn input_timeout(&self) -> Duration {
+        if self.command_buffer.is_empty() {
+            Duration::from_millis(UPDATE_TIME_MS)
+        } else {
+            Duration::from_millis(self.conf.options.timeout_len)
+        }
+    }
+
+    /// Draws the part of the buffer that fits on the screen above a status
+    /// line holding the command line, the last message or the mode.
+    pub fn render(&mut self, terminal: &mut dyn Terminal) {
+        let (height, width) = terminal.size();
+        if height == 0 {
+            return;
+        }
+        let text_height = height - 1;
+        let tab_width = self.conf.options.tab_width;
+        let gutter = if self.conf.options.line_numbers {
+            self.buffer.count().to_string().len().max(3) + 1
+        } else {
+            0
+        };
+        let text_width = width.saturating_sub(gutter);
+        self.viewport.resize(text_height, text_width);
+        let cursor_index = self.buffer.get_cursor_index().map(|(row, col)| {
+            let line = self.buffer.get_line_at(row).map(|line| line.to_string()).unwrap_or_default();
+            (row, display_col(&line, col, tab_width))
+        });
+        if let Some((row, col)) = cursor_index {
+            self.viewport.follow(row, col);
+        }
+
+        let left = self.viewport.left;
+        let selected = self.visual.map(|(kind, selection)| (kind, selection.ordered()));
+        let matching = self.matching_pair(self.viewport.top..self.viewport.top + text_height);
+        let mut lines = Vec::with_capacity(height);
+        for row in self.viewport.top..self.viewport.top + text_height {
+            lines.push(match self.buffer.get_line_at(row) {
+                Option::Some(line) => {
+                    let mut cells: Vec<Cell> = format!("{:>1$} ", row + 1, gutter.saturating_sub(1))
+                        .chars()
+                        .take(gutter)
+                        .map(|ch| Cell::new(ch, Style::LineNumber))
+                        .collect();
+                    let mut col = 0;
+                    let mut index = 0;
+                    line.for_each(&mut |&ch| {
+                        let (ch, cell_width) = if ch == '\t' {
+                            (' ', tab_width - col % tab_width)
+                        } else {
+                            (ch, 1)
+                        };
+                        let style = match selected {
+                            Option::Some((kind, ends)) if kind.contains(ends, row, index) => Style::Selected,
+                            _ if matching == Option::Some((row, index)) => Style::MatchParen,
+                            _ => Style::Normal,
+                        };
+                        for _ in 0..cell_width {
+                            if col >= left && col < left + text_width {
+                                cells.push(Cell::new(ch, style));
+                            }
+                            col += 1;
+                        }
+                        index += 1;
+                    });
+                    cells
+                }
+                Option::None => vec![Cell::new('~', Style::NonText)],
+            });
+        }
+
+        let status = match self.mode {
+            Mode::Command => format!("{}{}", self.command_prompt, self.command_line),
+            _ => match self.message {
+                Option::Some(ref message) => message.clone(),
+                Option::None => match self.mode {
+                    Mode::Insert => "-- INSERT --".to_string(),
+                    Mode::Visual => match self.visual {
+                        Option::Some((VisualKind::Line, _))  => "-- VISUAL LINE --".to_string(),
+                        Option::Some((VisualKind::Block, _)) => "-- VISUAL BLOCK --".to_string(),
+                        _                                    => "-- VISUAL --".to_string(),
+                    },
+                    _            => String::new(),
+                },
+            },
+        };
+        // a message of several lines covers the bottom of the text until
+        // the next key
+        let status_lines: Vec<&str> = status.split('\n').collect();
+        let status_lines = &status_lines[status_lines.len().saturating_sub(height)..];
+        lines.truncate(height - status_lines.len());
+        for line in status_lines {
+            lines.push(line.chars().take(width).map(|ch| Cell::new(ch, Style::Normal)).collect());
+        }
+        let status_len = status_lines[status_lines.len() - 1].chars().count();
+
+        let cursor = match self.mode {
+            Mode::Command => (text_height, status_len),
+            _ => cursor_index
+                .and_then(|(row, col)| self.viewport.to_screen(row, col))
+                .map(|(row, col)| (row, col + gutter))
+                .unwrap_or((0, gutter)),
+        };
+        let cursor = (cursor.0.min(text_height), cursor.1.min(width.saturating_sub(1)));
+        self.view.update(lines, cursor, terminal);
+    }
+
+    /// Where the bracket matching the one under the cursor is, for `render`
+    /// to highlight it. Only `rows`, the ones on screen, are searched.
+    fn matching_pair(&self, rows: Range<usize>) -> Option<(usize, usize)> {
+        let (row, col) = self.buffer.get_cursor_index()?;
+        let ch = *self.buffer.get_line_at(row)?.get_char_at(col)?;
+        let options = &self.conf.options;
+        let (open, close) = *options.match_pairs.iter().find(|&&(open, close)| ch == open || ch == close)?;
+        motion::find_unmatched_in(&self.buffer, (row, col), open, close, ch == open, options.skip_literals, rows)
+    }
+
+    /// Replaces the key bindings and options, say with ones from
+    /// `config::load`.
+    pub fn set_configuration(&mut self, conf: Configuration) {
+        self.conf = conf;
+        self.update_keymaps();
+        self.view.invalidate();
+    }
+
+    fn update_keymaps(&mut self) {
+        self.normal_keys = ModeKeymaps::new(&self.conf, Mode::Normal);
+        self.insert_keys = ModeKeymaps::new(&self.conf, Mode::Insert);
+        self.visual_keys = ModeKeymaps::new(&self.conf, Mode::Visual);
+        self.operator_keys = ModeKeymaps::new(&self.conf, Mode::OperatorPending);
+    }
+
+    /// Journals any pending changes to the swap file. Front ends call this
+    /// once no key has come in for `UPDATE_TIME_MS`.
+    ///
+    /// Keys still waiting for the rest of a binding are settled, as no more
+    /// came within `input_timeout`. Returns `false` when they quit the
+    /// editor.
+    pub fn idle(&mut self) -> bool {
+        let stay_alive = self.command_buffer.is_empty() || self.flush_keys();
+        if self.mode == Mode::Normal && self.command_buffer.is_empty() {
+            self.buffer.commit_change();
+        }
+        if stay_alive && self.buffer.changes() != self.swap_changes {
+            self.write_swap(true);
         }
+        stay_alive
+    }
+
+    /// What is in the register `name`, as selected with `"`.
+    pub fn register(&self, name: char) -> Option<Register> {
+        match name {
+            '%' => self.buffer.file_name().map(|name| Register::chars(name.to_string())),
+            '/' => self.last_search.as_ref().map(|(pattern, _)| Register::chars(pattern.clone())),
+            _   => self.registers.get(name),
+        }
+    }
+
+    /// The last error or notice produced by a command, if any.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Keeps a `file~` copy of the previous contents whenever a file is
+    /// overwritten.
+    pub fn set_backup(&mut self, backup: bool) {
+        self.buffer.set_keep_backup(backup);
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Moves the cursor to the start of `line`, counting from 1. Lines past
+    /// the end of the buffer land on the last one.
+    pub fn go_to_line(&mut self, line: usize) {
+        let last = self.buffer.count().saturating_sub(1);
+        self.buffer.move_cursor(line.saturating_sub(1).min(last), 0);
+    }
+
+    /// Moves the cursor to the start of the last line, as `+` does.
+    pub fn go_to_last_line(&mut self) {
+        self.go_to_line(self.buffer.count());
     }
 
-    pub fn input(&mut self, ch: char) -> bool {
-        let mut stay_alive: bool = true;
+    pub fn input(&mut self, key: Key) -> bool {
+        if self.swap_conflict.is_some() {
+            return self.answer_swap_prompt(key);
+        }
+        if self.message.as_ref().is_some_and(|message| message.contains('\n')) {
+            self.message = Option::None;
+        }
+        let stay_alive = self.handle_key(key, true);
+        if stay_alive {
+            self.update_swap();
+        }
+        stay_alive
+    }
+
+    /// Takes one key, typed or coming from a mapping. With `remap` unset
+    /// it only runs built-in bindings.
+    fn handle_key(&mut self, key: Key, remap: bool) -> bool {
+        if self.awaiting_register {
+            self.awaiting_register = false;
+            match key.to_char() {
+                Option::Some(name) if Registers::is_name(name) => self.register_name = Option::Some(name),
+                _ => self.count = Option::None,
+            }
+            return true;
+        }
+        if let Some(action) = self.awaiting_find.take() {
+            match key.to_char() {
+                Option::Some(ch) => {
+                    self.last_find = Option::Some((action, ch));
+                    self.perform(action);
+                }
+                Option::None if self.mode == Mode::OperatorPending => self.cancel_operator(),
+                Option::None => {
+                    self.count = Option::None;
+                    self.register_name = Option::None;
+                }
+            }
+            if self.mode == Mode::Normal {
+                self.buffer.commit_change();
+            }
+            return true;
+        }
+        if matches!(self.mode, Mode::Normal | Mode::Visual) && self.command_buffer.is_empty() && key.is_char('"') {
+            self.awaiting_register = true;
+            return true;
+        }
+        let takes_count = matches!(self.mode, Mode::Normal | Mode::Visual | Mode::OperatorPending);
+        if takes_count && self.command_buffer.is_empty() {
+            if let Some(digit) = self.count_digit(key) {
+                self.count = Option::Some(self.count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                return true;
+            }
+        }
         match self.mode {
-            Mode::Insert => {
-              self.command_buffer.push(ch);
-              if self.conf.go_to_normal_mode.contains(&self.command_buffer) {
-                  self.switch_mode(Mode::Normal);
-              } else {
-                  let len = self.command_buffer.len() - 1;
-                  self.command_buffer.truncate(len);
-                  self.buffer.put_char(ch);
-              }
-              self.buffer.for_each(&mut |line| {
-                  line.for_each(&mut |ch| { print!("{}", ch) })
-              });
-              println!("{:?}", self.command_buffer);
-            }
-            Mode::Normal => {
-              self.command_buffer.push(ch);
-              println!("{:?}", self.command_buffer);
-              if ch == Backspace {
-                  let len = if self.command_buffer.len() > 1 {
-                      self.command_buffer.len() - 2
-                  } else {
-                      self.command_buffer.len() - 1
-                  };
-                  self.command_buffer.truncate(len);
-                  println!("{:?}", self.command_buffer);
-              } else if self.conf.open_line_above.contains(&self.command_buffer) {
-                  self.open_line_above();
-              } else if self.conf.open_line_below.contains(&self.command_buffer) {
-                  self.open_line_below();
-              // else if ch == self.conf.go_to_start {
-              //     self.go_to_end();
-              } else if self.conf.go_to_left.contains(&self.command_buffer) {
-                  self.left();
-                  self.clear_command_buffer();
-              } else if self.conf.go_to_right.contains(&self.command_buffer) {
-                  self.right();
-                  self.clear_command_buffer();
-              } else if self.conf.go_to_up.contains(&self.command_buffer) {
-                  self.up();
-                  self.clear_command_buffer();
-              } else if self.conf.go_to_down.contains(&self.command_buffer) {
-                  self.down();
-                  self.clear_command_buffer();
-              } else if self.conf.insert_char_here.contains(&self.command_buffer) {
-                  if self.buffer.get_current_line_index().is_none() {
-                      self.buffer.new_line();
-                  }
-                  self.switch_mode(Mode::Insert);
-              } else if self.conf.write_buffer.contains(&self.command_buffer) {
-                  self.buffer.save_buffer("/Users/amanjsherwany/Desktop/test2-1.txt");
-                  self.clear_command_buffer();
-              } else if self.conf.quit_editor.contains(&self.command_buffer) {
-                  stay_alive = false;
-                  self.clear_command_buffer();
-              }
-          }
+            Mode::Command => return self.command_line_key(key),
+            Mode::Normal if key.code == KeyCode::Backspace && !self.command_buffer.is_empty() => {
+                self.command_buffer.pop();
+                return true;
+            }
+            Mode::Normal if key.code == KeyCode::Esc && self.count.is_some() => {
+                self.count = Option::None;
+                return true;
+            }
+            Mode::OperatorPending if key.code == KeyCode::Esc => {
+                self.cancel_operator();
+                return true;
+            }
+            _ => {
+                if self.command_buffer.is_empty() {
+                    self.remap_pending = remap;
+                }
+                self.command_buffer.push(key);
+            }
+        }
+        let mut stay_alive = true;
+        match self.keymap().lookup(&self.command_buffer) {
+            Lookup::Complete(binding) => {
+                self.clear_command_buffer();
+                stay_alive = self.run_binding(binding);
+            }
+            // wait for the next key, or for `timeout_len` to run out
+            Lookup::Ambiguous(_) | Lookup::Prefix => (),
+            Lookup::Invalid => stay_alive = self.flush_keys(),
+        }
+        if self.mode == Mode::Normal && self.command_buffer.is_empty() {
+            self.buffer.commit_change();
+        }
+        stay_alive
+    }
+
+    /// The digit `key` adds to the count, if any. A `0` only adds to a
+    /// count already started, as `0` alone is a motion.
+    fn count_digit(&self, key: Key) -> Option<usize> {
+        match key.to_char().and_then(|ch| ch.to_digit(10)) {
+            Option::Some(0) if self.count.is_none() => Option::None,
+            digit => digit.map(|digit| digit as usize),
+        }
+    }
+
+    /// The bindings of the current mode for the pending keys.
+    fn keymap(&self) -> &Keymap<Binding> {
+        let keymaps = match self.mode {
+            Mode::Insert => &self.insert_keys,
+            Mode::Visual => &self.visual_keys,
+            Mode::OperatorPending => &self.operator_keys,
+            _            => &self.normal_keys,
+        };
+        if self.remap_pending { &keymaps.remap } else { &keymaps.noremap }
+    }
+
+    fn run_binding(&mut self, binding: Binding) -> bool {
+        match binding {
+            Binding::Action(action) if action.takes_char() => {
+                self.awaiting_find = Option::Some(action);
+                true
+            }
+            Binding::Action(action) => {
+                self.perform(action);
+                true
+            }
+            Binding::Mapping(index) => match self.conf.mappings.get(index).cloned() {
+                Option::Some(mapping) => self.run_mapping(&mapping),
+                Option::None          => true,
+            },
+        }
+    }
+
+    /// Takes the keys `mapping` stands for as if they were typed. As in
+    /// Vim, when they start with the mapped keys themselves, those are not
+    /// mapped again, so that `:nmap j jzz` does not expand forever.
+    fn run_mapping(&mut self, mapping: &Mapping) -> bool {
+        if self.map_depth >= MAX_MAP_DEPTH {
+            self.map_aborted = true;
+            self.clear_command_buffer();
+            self.message = Option::Some("Recursive mapping".to_string());
+            return true;
+        }
+        let message = self.message.clone();
+        let own_keys = if mapping.rhs.starts_with(&mapping.lhs) { mapping.lhs.len() } else { 0 };
+        self.map_depth += 1;
+        let mut stay_alive = true;
+        for (index, key) in mapping.rhs.iter().enumerate() {
+            if self.map_aborted {
+                break;
+            }
+            stay_alive = self.handle_key(*key, !mapping.noremap && index >= own_keys);
+            if !stay_alive {
+                break;
+            }
+        }
+        self.map_depth -= 1;
+        if self.map_depth == 0 && self.map_aborted {
+            self.map_aborted = false;
+        } else if mapping.silent {
+            self.message = message;
         }
         stay_alive
     }
 
+    /// Settles the pending keys once no longer binding can come of them:
+    /// the longest bound start runs and the keys after it are taken again.
+    /// When nothing is bound, insert mode types the first key and normal
+    /// mode drops them all with a message.
+    fn flush_keys(&mut self) -> bool {
+        let remap = self.remap_pending;
+        let keys = ::std::mem::take(&mut self.command_buffer);
+        let rest = match self.keymap().longest_match(&keys) {
+            Option::Some((len, binding)) => {
+                if !self.run_binding(binding) {
+                    return false;
+                }
+                &keys[len..]
+            }
+            Option::None if self.mode == Mode::Insert => {
+                if let Some(ch) = keys[0].to_char() {
+                    self.buffer.put_char(ch);
+                    self.inserted.push(ch);
+                }
+                &keys[1..]
+            }
+            Option::None if self.mode == Mode::OperatorPending => {
+                self.cancel_operator();
+                &[]
+            }
+            Option::None => {
+                self.count = Option::None;
+                self.message = Option::Some(format!("Unknown key sequence: {}", notation::to_string(&keys)));
+                &[]
+            }
+        };
+        for key in rest {
+            if !self.handle_key(*key, remap) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Runs `action` with the pending count, if any.
+    fn perform(&mut self, action: Action) {
+        // the pattern of `/` and `?` comes first, on the command line
+        if matches!(action, Action::SearchForward | Action::SearchBackward) {
+            return self.start_search(action == Action::SearchForward);
+        }
+        match self.mode {
+            Mode::OperatorPending => return self.perform_pending(action),
+            Mode::Visual          => return self.perform_visual(action),
+            _                     => (),
+        }
+        let given = self.count.take();
+        let count = given.unwrap_or(1).max(1);
+        match action {
+            Action::GoToNormalMode => {
+                let text = self.inserted.clone();
+                self.repeat_insert();
+                self.finish_block_insert(&text);
+                if !text.is_empty() {
+                    self.registers.set_last_inserted(text);
+                }
+                self.switch_mode(Mode::Normal);
+                self.buffer.commit_change();
+            }
+            Action::OpenLineAbove   => (0..count).for_each(|_| self.open_line_above()),
+            Action::OpenLineBelow   => (0..count).for_each(|_| self.open_line_below()),
+            Action::GoToStart | Action::GoToEnd | Action::GoToLeft | Action::GoToRight | Action::GoToUp
+            | Action::GoToDown | Action::WordForward | Action::BigWordForward | Action::WordBackward
+            | Action::BigWordBackward | Action::WordEnd | Action::BigWordEnd | Action::WordEndBackward
+            | Action::BigWordEndBackward | Action::SentenceForward | Action::SentenceBackward
+            | Action::ParagraphForward | Action::ParagraphBackward | Action::GoToFirstNonBlank
+            | Action::GoToLastNonBlank | Action::GoToFirstLine | Action::GoToLastLine | Action::GoToScreenTop
+            | Action::GoToScreenMiddle | Action::GoToScreenBottom | Action::FindForward
+            | Action::FindBackward | Action::TillForward | Action::TillBackward | Action::RepeatFind
+            | Action::RepeatFindReverse | Action::MatchPair | Action::SearchForward | Action::SearchBackward
+            | Action::SearchNext | Action::SearchPrevious | Action::SearchWordForward
+            | Action::SearchWordBackward => {
+                if let Some((selection, _)) = self.motion(action, given) {
+                    self.move_to(selection.end_line, selection.end_col);
+                }
+            }
+            Action::InsertCharHere  => {
+                if self.buffer.get_current_line_index().is_none() {
+                    self.buffer.new_line();
+                }
+                self.start_insert(count);
+            }
+            Action::DeleteChar      => self.delete_chars(count),
+            Action::Delete | Action::Change | Action::Yank | Action::ShiftRight | Action::ShiftLeft
+            | Action::Reindent | Action::Lowercase | Action::Uppercase | Action::ToggleCase => {
+                self.operator = Option::Some((action, given));
+                self.switch_mode(Mode::OperatorPending);
+            }
+            Action::VisualMode      => self.start_visual(VisualKind::Char),
+            Action::VisualLineMode  => self.start_visual(VisualKind::Line),
+            Action::VisualBlockMode => self.start_visual(VisualKind::Block),
+            // text objects are for operators and visual mode only
+            Action::InnerWord | Action::AWord | Action::InnerBigWord | Action::ABigWord | Action::InnerSentence
+            | Action::ASentence | Action::InnerParagraph | Action::AParagraph | Action::InnerDoubleQuote
+            | Action::ADoubleQuote | Action::InnerSingleQuote | Action::ASingleQuote | Action::InnerBackQuote
+            | Action::ABackQuote | Action::InnerParen | Action::AParen | Action::InnerBracket | Action::ABracket
+            | Action::InnerBrace | Action::ABrace | Action::InnerAngle | Action::AAngle | Action::InnerTag
+            | Action::ATag => (),
+            Action::LeaveVisualMode | Action::SwapEnds | Action::BlockInsert | Action::BlockAppend => (),
+            Action::PutAfter        => self.put(true, false, count),
+            Action::PutBefore       => self.put(false, false, count),
+            Action::PutAfterMove    => self.put(true, true, count),
+            Action::PutBeforeMove   => self.put(false, true, count),
+            Action::GoToCommandMode => {
+                self.command_prompt = ':';
+                self.command_line.clear();
+                self.switch_mode(Mode::Command);
+            }
+            Action::ScrollLineDown     => self.scroll_down(count),
+            Action::ScrollLineUp       => self.scroll_up(count),
+            Action::ScrollHalfPageDown => self.scroll_half_page_down(given),
+            Action::ScrollHalfPageUp   => self.scroll_half_page_up(given),
+            Action::ScrollPageDown     => (0..count).for_each(|_| self.scroll_page_down()),
+            Action::ScrollPageUp       => (0..count).for_each(|_| self.scroll_page_up()),
+            Action::ScrollCursorTop => {
+                if let Some(row) = self.count_row(given) {
+                    self.viewport.show_at_top(row);
+                }
+            }
+            Action::ScrollCursorCenter => {
+                if let Some(row) = self.count_row(given) {
+                    self.viewport.show_at_center(row);
+                }
+            }
+            Action::ScrollCursorBottom => {
+                if let Some(row) = self.count_row(given) {
+                    self.viewport.show_at_bottom(row);
+                }
+            }
+            Action::Undo      => self.undo(count),
+            Action::Redo      => self.redo(count),
+            Action::UndoOlder => self.undo_step(-(count as isize)),
+            Action::UndoNewer => self.undo_step(count as isize),
+        }
+        // a register selected with `"` is only for the command right after
+        if !matches!(self.mode, Mode::OperatorPending | Mode::Visual) {
+            self.register_name = Option::None;
+        }
+    }
+
+    /// Finishes the pending operator with `action`: a motion gives the text
+    /// it works on, the operator itself its count of lines. Counts before
+    /// the operator and before the motion multiply, so `2d3j` is `d6j`.
+    fn perform_pending(&mut self, action: Action) {
+        let (operator, operator_count) = match self.operator.take() {
+            Option::Some(pending) => pending,
+            Option::None => return self.cancel_operator(),
+        };
+        self.switch_mode(Mode::Normal);
+        let given = match (operator_count, self.count.take()) {
+            (Option::None, Option::None) => Option::None,
+            (before, after) => Option::Some(before.unwrap_or(1).saturating_mul(after.unwrap_or(1)).max(1)),
+        };
+        let count = given.unwrap_or(1);
+        let target = if action == operator {
+            self.buffer.get_cursor_index().map(|(row, col)| {
+                let last = row.saturating_add(count - 1).min(self.buffer.count().saturating_sub(1));
+                (Selection::new((row, col), (last, col)), MotionKind::Linewise)
+            })
+        } else if action.is_motion() {
+            self.operator_motion(operator, action, given)
+        } else if action.is_text_object() {
+            self.text_object(action, count)
+        } else {
+            Option::None
+        };
+        if let Some((selection, kind)) = target {
+            self.apply_operator(operator, selection, kind);
+        }
+        self.register_name = Option::None;
+    }
+
+    fn start_visual(&mut self, kind: VisualKind) {
+        if let Some(cursor) = self.buffer.get_cursor_index() {
+            self.switch_mode(Mode::Visual);
+            self.visual = Option::Some((kind, Selection::new(cursor, cursor)));
+        }
+    }
+
+    fn leave_visual(&mut self) {
+        self.visual = Option::None;
+        self.switch_mode(Mode::Normal);
+    }
+
+    /// Runs `action` in visual mode: motions move the end of the selection,
+    /// operators work on it.
+    fn perform_visual(&mut self, action: Action) {
+        let given = self.count.take();
+        let count = given.unwrap_or(1).max(1);
+        let (kind, selection) = match self.visual {
+            Option::Some(visual) => visual,
+            Option::None => return self.leave_visual(),
+        };
+        let switch_to = match action {
+            Action::VisualMode      => Option::Some(VisualKind::Char),
+            Action::VisualLineMode  => Option::Some(VisualKind::Line),
+            Action::VisualBlockMode => Option::Some(VisualKind::Block),
+            _                       => Option::None,
+        };
+        match action {
+            _ if action.is_motion() => {
+                if let Some((motion, _)) = self.motion(action, given) {
+                    self.move_to(motion.end_line, motion.end_col);
+                }
+            }
+            _ if switch_to == Option::Some(kind) => {
+                self.register_name = Option::None;
+                return self.leave_visual();
+            }
+            _ if switch_to.is_some() => {
+                self.visual = switch_to.map(|kind| (kind, selection));
+            }
+            Action::LeaveVisualMode => {
+                self.register_name = Option::None;
+                return self.leave_visual();
+            }
+            Action::SwapEnds => {
+                self.buffer.move_cursor(selection.start_line, selection.start_col);
+                self.visual = Option::Some((kind, Selection::new(
+                    (selection.end_line, selection.end_col), (selection.start_line, selection.start_col))));
+            }
+            _ if action.is_text_object() => {
+                let (object, object_kind) = match self.text_object(action, count) {
+                    Option::Some(object) => object,
+                    Option::None => return,
+                };
+                let (start, end) = object.ordered();
+                // the selection takes the last char of the object
+                let end = match (object_kind, end) {
+                    (MotionKind::Exclusive, _) if end <= start => return,
+                    (MotionKind::Exclusive, (row, 0)) => (row - 1, self.buffer.line_len(row - 1)),
+                    (MotionKind::Exclusive, (row, col)) => (row, col - 1),
+                    _ => end,
+                };
+                let kind = if object_kind == MotionKind::Linewise { VisualKind::Line } else { kind };
+                self.visual = Option::Some((kind, Selection::new(start, end)));
+                self.move_to(end.0, end.1);
+            }
+            Action::BlockInsert | Action::BlockAppend => return self.visual_insert(action, kind, selection),
+            Action::DeleteChar => return self.apply_visual(Action::Delete, kind, selection),
+            _ if action.is_operator() => return self.apply_visual(action, kind, selection),
+            _ => (),
+        }
+        if let (Some((_, ref mut selection)), Some((row, col))) = (self.visual.as_mut(), self.buffer.get_cursor_index()) {
+            selection.end_line = row;
+            selection.end_col = col;
+        }
+    }
+
+    /// Runs `operator` on what visual mode selected and leaves it.
+    fn apply_visual(&mut self, operator: Action, kind: VisualKind, selection: Selection) {
+        self.leave_visual();
+        match kind {
+            VisualKind::Char => self.apply_operator(operator, selection, MotionKind::Inclusive),
+            VisualKind::Line => self.apply_operator(operator, selection, MotionKind::Linewise),
+            VisualKind::Block if matches!(operator, Action::ShiftRight | Action::ShiftLeft | Action::Reindent) => {
+                self.apply_operator(operator, selection, MotionKind::Linewise)
+            }
+            VisualKind::Block => self.apply_block(operator, selection),
+        }
+        self.register_name = Option::None;
+    }
+
+    /// Runs `operator` on the columns of a visual block, line by line.
+    /// Changing the block inserts the text typed into each of its lines.
+    fn apply_block(&mut self, operator: Action, selection: Selection) {
+        let ((first, _), (last, _)) = selection.ordered();
+        let left = selection.start_col.min(selection.end_col);
+        let right = selection.start_col.max(selection.end_col) + 1;
+        let lines: Vec<Vec<char>> = (first..=last)
+            .map(|row| self.buffer.line_text(row).unwrap_or_default().chars().collect())
+            .collect();
+        let pieces: Vec<String> = lines.iter()
+            .map(|line| line[left.min(line.len())..right.min(line.len())].iter().collect())
+            .collect();
+        if operator == Action::Yank {
+            self.store(true, Register::block(&pieces));
+            self.buffer.move_cursor(first, left);
+            return;
+        }
+        let replaced: Vec<String> = lines.iter().zip(&pieces).map(|(line, piece)| {
+            let head: String = line[..left.min(line.len())].iter().collect();
+            let tail: String = line[right.min(line.len())..].iter().collect();
+            match operator {
+                Action::Delete | Action::Change => head + &tail,
+                _ => head + &change_case(operator, piece) + &tail,
+            }
+        }).collect();
+        self.buffer.replace_lines(first, last + 1 - first, replaced);
+        match operator {
+            Action::Delete => {
+                self.store(false, Register::block(&pieces));
+                let len = self.buffer.line_len(first);
+                self.buffer.move_cursor(first, left.min(len.saturating_sub(1)));
+            }
+            Action::Change => {
+                self.store(false, Register::block(&pieces));
+                self.block_insert = Option::Some(BlockInsert { first, last, col: left, pad: false });
+                self.buffer.move_cursor(first, left);
+                self.start_insert(1);
+            }
+            _ => self.buffer.move_cursor(first, left),
+        }
+    }
+
+    /// `I` and `A` in visual mode: inserts before or appends after the
+    /// selection. In a block the text typed goes into every line of it,
+    /// `A` padding short lines to reach the end of the block.
+    fn visual_insert(&mut self, action: Action, kind: VisualKind, selection: Selection) {
+        self.leave_visual();
+        let ((first, _), (last, _)) = selection.ordered();
+        let col = match (kind, action) {
+            (VisualKind::Block, Action::BlockInsert) => selection.start_col.min(selection.end_col),
+            (VisualKind::Block, _) => selection.start_col.max(selection.end_col) + 1,
+            (_, Action::BlockInsert) => selection.ordered().0 .1,
+            (_, _) => {
+                let (row, col) = selection.ordered().1;
+                self.buffer.move_cursor(row, (col + 1).min(self.buffer.line_len(row)));
+                return self.start_insert(1);
+            }
+        };
+        if kind == VisualKind::Block {
+            let pad = action == Action::BlockAppend;
+            let len = self.buffer.line_len(first);
+            if pad && len < col {
+                self.buffer.insert_text(first, len, &" ".repeat(col - len));
+            }
+            self.block_insert = Option::Some(BlockInsert { first, last, col, pad });
+        }
+        self.buffer.move_cursor(first, col);
+        self.start_insert(1);
+    }
+
+    /// Puts `text`, typed into the first line of a visual block, into the
+    /// rest of its lines. Text that broke the line goes nowhere else.
+    fn finish_block_insert(&mut self, text: &str) {
+        let block = match self.block_insert.take() {
+            Option::Some(block) => block,
+            Option::None => return,
+        };
+        if text.is_empty() || text.contains('\n') {
+            return;
+        }
+        for row in block.first + 1..=block.last {
+            let len = self.buffer.line_len(row);
+            if len < block.col {
+                if !block.pad {
+                    continue;
+                }
+                self.buffer.insert_text(row, len, &" ".repeat(block.col - len));
+            }
+            self.buffer.insert_text(row, block.col, text);
+        }
+        self.buffer.move_cursor(block.first, block.col);
+    }
+
+    fn cancel_operator(&mut self) {
+        self.operator = Option::None;
+        self.count = Option::None;
+        self.register_name = Option::None;
+        self.switch_mode(Mode::Normal);
+    }
+
+    /// Where `action` moves the cursor `count` times, from the cursor, or
+    /// `None` when it cannot move at all. `gg` and `G` take the count as a
+    /// line number, `H` and `L` as lines from the top and bottom of the
+    /// screen, `%` as how far into the file in percent. Finding a char
+    /// looks for the one in `last_find`, searching may leave a message.
+    fn motion(&mut self, action: Action, given: Option<usize>) -> Option<(Selection, MotionKind)> {
+        if matches!(action, Action::SearchNext | Action::SearchPrevious | Action::SearchWordForward
+                            | Action::SearchWordBackward) {
+            return self.search(action, given.unwrap_or(1).max(1));
+        }
+        let (row, col) = self.buffer.get_cursor_index()?;
+        let buffer = &self.buffer;
+        let count = given.unwrap_or(1).max(1);
+        let last_row = buffer.count().saturating_sub(1);
+        let len = buffer.line_len(row);
+        let big = matches!(action, Action::BigWordForward | Action::BigWordBackward | Action::BigWordEnd
+                                   | Action::BigWordEndBackward);
+        let repeat = |step: &dyn Fn((usize, usize)) -> (usize, usize)| {
+            (0..count).fold((row, col), |pos, _| step(pos))
+        };
+        let line = |row: usize| ((row, motion::first_non_blank(buffer, row)), MotionKind::Linewise);
+        let screen_bottom = self.viewport.bottom().min(last_row);
+        let (end, kind) = match action {
+            Action::GoToLeft if col > 0 => ((row, col.saturating_sub(count)), MotionKind::Exclusive),
+            Action::GoToRight if col < len => ((row, col.saturating_add(count).min(len)), MotionKind::Exclusive),
+            Action::GoToUp if row > 0 => ((row.saturating_sub(count), col), MotionKind::Linewise),
+            Action::GoToDown if row < last_row => ((row.saturating_add(count).min(last_row), col), MotionKind::Linewise),
+            Action::GoToStart => ((row, 0), MotionKind::Exclusive),
+            Action::GoToEnd => {
+                let end_row = row.saturating_add(count - 1).min(last_row);
+                ((end_row, buffer.line_len(end_row).saturating_sub(1)), MotionKind::Inclusive)
+            }
+            Action::WordForward | Action::BigWordForward => {
+                (repeat(&|pos| motion::word_forward(buffer, pos, big)), MotionKind::Exclusive)
+            }
+            Action::WordBackward | Action::BigWordBackward => {
+                (repeat(&|pos| motion::word_backward(buffer, pos, big)), MotionKind::Exclusive)
+            }
+            Action::WordEnd | Action::BigWordEnd => {
+                (repeat(&|pos| motion::word_end(buffer, pos, big)), MotionKind::Inclusive)
+            }
+            Action::WordEndBackward | Action::BigWordEndBackward => {
+                (repeat(&|pos| motion::word_end_backward(buffer, pos, big)), MotionKind::Inclusive)
+            }
+            Action::SentenceForward => (repeat(&|pos| motion::sentence_forward(buffer, pos)), MotionKind::Exclusive),
+            Action::SentenceBackward => (repeat(&|pos| motion::sentence_backward(buffer, pos)), MotionKind::Exclusive),
+            Action::ParagraphForward => {
+                (repeat(&|(row, _)| motion::paragraph_forward(buffer, row)), MotionKind::Exclusive)
+            }
+            Action::ParagraphBackward => {
+                (repeat(&|(row, _)| motion::paragraph_backward(buffer, row)), MotionKind::Exclusive)
+            }
+            Action::GoToFirstNonBlank => ((row, motion::first_non_blank(buffer, row)), MotionKind::Exclusive),
+            Action::GoToLastNonBlank => {
+                let end_row = row.saturating_add(count - 1).min(last_row);
+                ((end_row, motion::last_non_blank(buffer, end_row)), MotionKind::Inclusive)
+            }
+            Action::GoToFirstLine => line((count - 1).min(last_row)),
+            Action::GoToLastLine => line(given.map_or(last_row, |line| line.max(1) - 1).min(last_row)),
+            Action::GoToScreenTop => line(self.viewport.top.saturating_add(count - 1).min(screen_bottom)),
+            Action::GoToScreenMiddle => line((self.viewport.top + screen_bottom) / 2),
+            Action::GoToScreenBottom => line(screen_bottom.saturating_sub(count - 1).max(self.viewport.top)),
+            // as in Vim, `{count}%` goes that far into the file
+            Action::MatchPair => match given {
+                Option::Some(percent) if percent <= 100 => {
+                    line(percent.saturating_mul(last_row + 1).div_ceil(100) - 1)
+                }
+                Option::Some(_) => return Option::None,
+                Option::None => {
+                    let options = &self.conf.options;
+                    let end = motion::match_pair(buffer, (row, col), &options.match_pairs, options.skip_literals)?;
+                    (end, MotionKind::Inclusive)
+                }
+            },
+            _ if action.finds_char() => {
+                let (find, ch) = self.last_find?;
+                let forward = matches!(find, Action::FindForward | Action::TillForward)
+                    != (action == Action::RepeatFindReverse);
+                let till = matches!(find, Action::TillForward | Action::TillBackward);
+                // as in Vim, repeating `t` or `T` does not stay stuck in
+                // front of the char it found last time
+                let from = match (till && !action.takes_char(), forward) {
+                    (true, true)  => col + 1,
+                    (true, false) => col.checked_sub(1)?,
+                    (false, _)    => col,
+                };
+                let end = motion::find_char(buffer, (row, from), ch, forward, till, count)?;
+                ((row, end), if forward { MotionKind::Inclusive } else { MotionKind::Exclusive })
+            }
+            _ => return Option::None,
+        };
+        Option::Some((Selection::new((row, col), end), kind))
+    }
+
+    /// `motion` for the text an operator works on. As in Vim, `cw` on a
+    /// word changes only up to its end, like `ce`, and `dw` on the last
+    /// word of a line stops at the end of the line.
+    fn operator_motion(&mut self, operator: Action, action: Action, given: Option<usize>)
+                       -> Option<(Selection, MotionKind)> {
+        let (row, col) = self.buffer.get_cursor_index()?;
+        let on_word = self.buffer.get_line_at(row)
+            .and_then(|line| line.get_char_at(col))
+            .is_some_and(|&ch| ch != ' ' && ch != '\t');
+        let (mut selection, kind) = match action {
+            Action::WordForward | Action::BigWordForward if operator == Action::Change && on_word => {
+                let big = action == Action::BigWordForward;
+                let end = (1..given.unwrap_or(1).max(1))
+                    .fold(motion::end_of_word_at(&self.buffer, (row, col), big),
+                          |pos, _| motion::word_end(&self.buffer, pos, big));
+                return Option::Some((Selection::new((row, col), end), MotionKind::Inclusive));
+            }
+            _ => self.motion(action, given)?,
+        };
+        let end_row = selection.end_line;
+        if matches!(action, Action::WordForward | Action::BigWordForward) && end_row > row
+           && selection.end_col <= motion::first_non_blank(&self.buffer, end_row) {
+            selection.end_line = end_row - 1;
+            selection.end_col = self.buffer.line_len(end_row - 1);
+        }
+        // as in Vim, an exclusive motion to the start of a line from at or
+        // before the first non-blank takes whole lines
+        let (start, end) = selection.ordered();
+        if kind == MotionKind::Exclusive && end.1 == 0 && end.0 > start.0
+           && start.1 <= motion::first_non_blank(&self.buffer, start.0) {
+            return Option::Some((Selection::new(start, (end.0 - 1, 0)), MotionKind::Linewise));
+        }
+        Option::Some((selection, kind))
+    }
+
+    /// The text object `action` around the cursor, `count` of them or
+    /// `count` levels out.
+    fn text_object(&self, action: Action, count: usize) -> Option<(Selection, MotionKind)> {
+        let cursor = self.buffer.get_cursor_index()?;
+        let buffer = &self.buffer;
+        let around = matches!(action, Action::AWord | Action::ABigWord | Action::ASentence | Action::AParagraph
+                                      | Action::ADoubleQuote | Action::ASingleQuote | Action::ABackQuote
+                                      | Action::AParen | Action::ABracket | Action::ABrace | Action::AAngle
+                                      | Action::ATag);
+        let skip_literals = self.conf.options.skip_literals;
+        let bracket = |open, close| text_object::bracket(buffer, cursor, open, close, around, count, skip_literals);
+        match action {
+            Action::InnerWord | Action::AWord       => text_object::word(buffer, cursor, around, false, count),
+            Action::InnerBigWord | Action::ABigWord => text_object::word(buffer, cursor, around, true, count),
+            Action::InnerSentence | Action::ASentence => text_object::sentence(buffer, cursor, around, count),
+            Action::InnerParagraph | Action::AParagraph => text_object::paragraph(buffer, cursor.0, around, count),
+            Action::InnerDoubleQuote | Action::ADoubleQuote => text_object::quote(buffer, cursor, '"', around, count),
+            Action::InnerSingleQuote | Action::ASingleQuote => text_object::quote(buffer, cursor, '\'', around, count),
+            Action::InnerBackQuote | Action::ABackQuote => text_object::quote(buffer, cursor, '`', around, count),
+            Action::InnerParen | Action::AParen     => bracket('(', ')'),
+            Action::InnerBracket | Action::ABracket => bracket('[', ']'),
+            Action::InnerBrace | Action::ABrace     => bracket('{', '}'),
+            Action::InnerAngle | Action::AAngle     => bracket('<', '>'),
+            Action::InnerTag | Action::ATag         => text_object::tag(buffer, cursor, around, count),
+            _ => Option::None,
+        }
+    }
+
+    /// Runs `operator` on the text of `selection`. Shifting and reindenting
+    /// always take whole lines.
+    fn apply_operator(&mut self, operator: Action, selection: Selection, kind: MotionKind) {
+        let (start, mut end) = selection.ordered();
+        let linewise = kind == MotionKind::Linewise
+            || matches!(operator, Action::ShiftRight | Action::ShiftLeft | Action::Reindent);
+        if linewise {
+            return self.apply_linewise(operator, start, end.0);
+        }
+        if kind == MotionKind::Inclusive {
+            end.1 = (end.1 + 1).min(self.buffer.line_len(end.0));
+        } else if end.1 == 0 && end.0 > start.0 {
+            // as in Vim, an exclusive motion to the start of a line stops
+            // at the end of the line before
+            end = (end.0 - 1, self.buffer.line_len(end.0 - 1));
+        }
+        match operator {
+            Action::Delete => {
+                let removed = self.buffer.remove_text(start, end);
+                self.store(false, Register::chars(removed));
+                let len = self.buffer.line_len(start.0);
+                self.buffer.move_cursor(start.0, start.1.min(len.saturating_sub(1)));
+            }
+            Action::Change => {
+                let removed = self.buffer.remove_text(start, end);
+                self.store(false, Register::chars(removed));
+                self.start_insert(1);
+            }
+            Action::Yank => {
+                self.store(true, Register::chars(self.buffer.text_range(start, end)));
+                self.buffer.move_cursor(start.0, start.1);
+            }
+            _ => {
+                let text = change_case(operator, &self.buffer.text_range(start, end));
+                self.buffer.replace_text(start, end, &text);
+            }
+        }
+    }
+
+    /// Runs `operator` on the lines from `start` to `last`, the cursor
+    /// having been at `start`.
+    fn apply_linewise(&mut self, operator: Action, start: (usize, usize), last: usize) {
+        let row = start.0;
+        let count = last + 1 - row;
+        let lines: Vec<String> = (row..=last).filter_map(|row| self.buffer.line_text(row)).collect();
+        let report = match operator {
+            Action::Delete => {
+                let removed = self.buffer.delete_lines(row, count);
+                self.store(false, Register::lines(&removed));
+                let row = row.min(self.buffer.count().saturating_sub(1));
+                let col = motion::first_non_blank(&self.buffer, row);
+                self.buffer.move_cursor(row, col);
+                format!("{} fewer lines", count)
+            }
+            Action::Change => {
+                self.store(false, Register::lines(&lines));
+                self.buffer.replace_lines(row, count, vec![String::new()]);
+                self.buffer.move_cursor(row, 0);
+                self.start_insert(1);
+                return;
+            }
+            Action::Yank => {
+                self.store(true, Register::lines(&lines));
+                self.buffer.move_cursor(start.0, start.1);
+                format!("{} lines yanked", count)
+            }
+            Action::ShiftRight | Action::ShiftLeft | Action::Reindent => {
+                let (tab_width, shift_width) = (self.conf.options.tab_width, self.conf.options.shift_width);
+                let above = (0..row).rev()
+                    .filter_map(|row| self.buffer.line_text(row))
+                    .find(|line| !line.trim().is_empty())
+                    .map(|line| indent_of(&line, tab_width).1)
+                    .unwrap_or(0);
+                let indented = lines.iter().map(|line| {
+                    let (chars, width) = indent_of(line, tab_width);
+                    let width = match operator {
+                        _ if line.trim().is_empty() => return String::new(),
+                        Action::ShiftRight => width + shift_width,
+                        Action::ShiftLeft  => width.saturating_sub(shift_width),
+                        _                  => above,
+                    };
+                    self.make_indent(width) + &line.chars().skip(chars).collect::<String>()
+                }).collect();
+                self.buffer.replace_lines(row, count, indented);
+                let col = motion::first_non_blank(&self.buffer, row);
+                self.buffer.move_cursor(row, col);
+                match operator {
+                    Action::ShiftRight => format!("{} lines >ed 1 time", count),
+                    Action::ShiftLeft  => format!("{} lines <ed 1 time", count),
+                    _                  => format!("{} lines indented ", count),
+                }
+            }
+            _ => {
+                let end = (last, self.buffer.line_len(last));
+                let text = change_case(operator, &self.buffer.text_range((row, 0), end));
+                self.buffer.replace_text((row, 0), end, &text);
+                self.buffer.move_cursor(start.0, start.1);
+                format!("{} lines changed", count)
+            }
+        };
+        // as Vim's 'report' does, only tell about more than two lines
+        if count > 2 {
+            self.message = Option::Some(report);
+        }
+    }
+
+    /// Leading whitespace `width` columns wide: tabs and then spaces, or
+    /// only spaces with `expand_tab`.
+    fn make_indent(&self, width: usize) -> String {
+        let tab_width = self.conf.options.tab_width;
+        if self.conf.options.expand_tab {
+            " ".repeat(width)
+        } else {
+            "\t".repeat(width / tab_width) + &" ".repeat(width % tab_width)
+        }
+    }
+
+    /// Keeps deleted or yanked text in the register selected for the
+    /// command.
+    fn store(&mut self, yanked: bool, register: Register) {
+        let name = self.register_name.take();
+        let result = if yanked {
+            self.registers.yank(name, register)
+        } else {
+            self.registers.delete(name, register)
+        };
+        if let Err(err) = result {
+            self.message = Option::Some(err);
+        }
+    }
+
+    fn start_insert(&mut self, count: usize) {
+        self.switch_mode(Mode::Insert);
+        self.insert_count = count;
+        self.inserted.clear();
+    }
+
+    fn command_line_key(&mut self, key: Key) -> bool {
+        let searching = self.command_prompt != ':';
+        if key.code == KeyCode::Enter {
+            let line = self.command_line.clone();
+            if searching {
+                self.search_entered(line);
+                return true;
+            }
+            self.switch_mode(Mode::Normal);
+            if !line.trim().is_empty() {
+                self.registers.set_last_command(line.clone());
+            }
+            return self.execute(&line);
+        } else if key.code == KeyCode::Esc || (key.code == KeyCode::Backspace && self.command_line.is_empty()) {
+            self.leave_command_line();
+        } else if key.code == KeyCode::Backspace {
+            self.command_line.pop();
+        } else if searching && matches!(key.code, KeyCode::Up | KeyCode::Down) {
+            self.browse_history(key.code == KeyCode::Up);
+        } else if let Some(ch) = key.to_char() {
+            self.command_line.push(ch);
+        }
+        true
+    }
+
+    /// Leaves the command line without running it, for the mode a search
+    /// was started from.
+    fn leave_command_line(&mut self) {
+        match self.command_prompt {
+            ':' => self.switch_mode(Mode::Normal),
+            _ if self.search_from == Mode::OperatorPending => self.cancel_operator(),
+            _ => {
+                self.count = Option::None;
+                self.switch_mode(self.search_from);
+            }
+        }
+    }
+
+    /// `/` and `?`: takes the pattern to search for on the command line.
+    fn start_search(&mut self, forward: bool) {
+        self.search_from = self.mode;
+        self.command_prompt = if forward { '/' } else { '?' };
+        self.command_line.clear();
+        self.history_index = Option::None;
+        self.switch_mode(Mode::Command);
+    }
+
+    /// Searches for `pattern` as entered after `/` or `?`, or for the last
+    /// pattern again when it is empty, moving as `n` does in the mode the
+    /// search was started from. The pattern is literal text, see `find`.
+    fn search_entered(&mut self, pattern: String) {
+        let forward = self.command_prompt == '/';
+        self.switch_mode(self.search_from);
+        let pattern = match self.last_search {
+            _ if !pattern.is_empty() => pattern,
+            Option::Some((ref last, _)) => last.clone(),
+            Option::None => {
+                self.message = Option::Some("No previous regular expression".to_string());
+                return self.leave_command_line();
+            }
+        };
+        self.remember_search(pattern, forward);
+        self.perform(Action::SearchNext);
+    }
+
+    /// Makes `pattern` the one `n` and `N` search for, and the latest one
+    /// in the history.
+    fn remember_search(&mut self, pattern: String, forward: bool) {
+        self.search_history.retain(|old| *old != pattern);
+        self.search_history.push(pattern.clone());
+        self.last_search = Option::Some((pattern, forward));
+    }
+
+    /// Up and Down on the search command line: puts older or newer
+    /// patterns from the history there, and what was typed back after the
+    /// newest.
+    fn browse_history(&mut self, older: bool) {
+        let len = self.search_history.len();
+        let index = match (self.history_index, older) {
+            (Option::None, true) if len > 0 => {
+                self.history_typed = self.command_line.clone();
+                Option::Some(len - 1)
+            }
+            (Option::Some(index), true) => Option::Some(index.saturating_sub(1)),
+            (Option::Some(index), false) if index + 1 < len => Option::Some(index + 1),
+            (Option::Some(_), false) => Option::None,
+            (Option::None, _) => return,
+        };
+        self.history_index = index;
+        self.command_line = match index {
+            Option::Some(index) => self.search_history[index].clone(),
+            Option::None => self.history_typed.clone(),
+        };
+    }
+
+    /// `n`, `N`, `*` and `#`: where the `count`th match of the last pattern
+    /// from the cursor is, `*` and `#` first making the word under the
+    /// cursor the pattern. Tells when the search went round the end of the
+    /// buffer, as Vim does.
+    fn search(&mut self, action: Action, count: usize) -> Option<(Selection, MotionKind)> {
+        let cursor = self.buffer.get_cursor_index()?;
+        let mut from = cursor;
+        if matches!(action, Action::SearchWordForward | Action::SearchWordBackward) {
+            let (start, word) = match self.word_at_cursor() {
+                Option::Some(word) => word,
+                Option::None => {
+                    self.message = Option::Some("No string under cursor".to_string());
+                    return Option::None;
+                }
+            };
+            // from the start of the word, so that `#` does not find it
+            from = (cursor.0, start);
+            self.remember_search(format!("\\<{}\\>", word), action == Action::SearchWordForward);
+        }
+        let (pattern, forward) = match self.last_search {
+            Option::Some((ref pattern, forward)) => (pattern.clone(), forward != (action == Action::SearchPrevious)),
+            Option::None => {
+                self.message = Option::Some("No previous regular expression".to_string());
+                return Option::None;
+            }
+        };
+        let direction = if forward { Direction::Forward } else { Direction::Backward };
+        let mut wrapped = false;
+        for _ in 0..count {
+            match self.find(&pattern, from, direction) {
+                Option::Some(found) => {
+                    wrapped |= if forward { found <= from } else { found >= from };
+                    from = found;
+                }
+                Option::None if has_regex_chars(&pattern) => {
+                    self.message = Option::Some(format!("Pattern not found (search is literal, not a regex): {}",
+                                                        pattern));
+                    return Option::None;
+                }
+                Option::None => {
+                    self.message = Option::Some(format!("Pattern not found: {}", pattern));
+                    return Option::None;
+                }
+            }
+        }
+        self.message = Option::Some(match (wrapped, forward) {
+            (true, true)   => "search hit BOTTOM, continuing at TOP".to_string(),
+            (true, false)  => "search hit TOP, continuing at BOTTOM".to_string(),
+            (false, true)  => format!("/{}", pattern),
+            (false, false) => format!("?{}", pattern),
+        });
+        Option::Some((Selection::new(cursor, from), MotionKind::Exclusive))
+    }
+
+    /// `Buffer::find` for `pattern`, where `\<` at its start and `\>` at
+    /// its end only match at the start and end of a word. Nothing else is
+    /// special: this is not Vim's regular expressions, `.` and `*` are
+    /// just themselves.
+    fn find(&self, pattern: &str, from: (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let (word_start, rest) = match pattern.strip_prefix("\\<") {
+            Option::Some(rest) => (true, rest),
+            Option::None => (false, pattern),
+        };
+        let (word_end, text) = match rest.strip_suffix("\\>") {
+            Option::Some(text) => (true, text),
+            Option::None => (false, rest),
+        };
+        let len = text.chars().count();
+        let is_keyword = |row: usize, col: Option<usize>| {
+            col.and_then(|col| self.buffer.get_line_at(row)?.get_char_at(col).copied())
+                .is_some_and(|ch| ch.is_alphanumeric() || ch == '_')
+        };
+        let first = self.buffer.find(text, from, direction)?;
+        let mut found = first;
+        loop {
+            let (row, col) = found;
+            if (!word_start || !is_keyword(row, col.checked_sub(1))) && (!word_end || !is_keyword(row, Option::Some(col + len))) {
+                return Option::Some(found);
+            }
+            found = self.buffer.find(text, found, direction)?;
+            if found == first {
+                return Option::None;
+            }
+        }
+    }
+
+    /// The word under or after the cursor on its line and the column it
+    /// starts at, for `*` and `#`.
+    fn word_at_cursor(&self) -> Option<(usize, String)> {
+        let (row, col) = self.buffer.get_cursor_index()?;
+        let line: Vec<char> = self.buffer.line_text(row)?.chars().collect();
+        let is_keyword = |ch: &char| ch.is_alphanumeric() || *ch == '_';
+        let at = (col..line.len()).find(|&at| is_keyword(&line[at]))?;
+        let start = at - line[..at].iter().rev().take_while(|&ch| is_keyword(ch)).count();
+        let end = at + line[at..].iter().take_while(|&ch| is_keyword(ch)).count();
+        Option::Some((start, line[start..end].iter().collect()))
+    }
+
+    /// Runs a command-line command, returning `false` when the editor
+    /// should quit.
+    fn execute(&mut self, line: &str) -> bool {
+        self.message = Option::None;
+        let result = match command::parse(line) {
+            Ok(Command::Write { path, force }) => self.write(path, force),
+            Ok(Command::SaveAs { path, force }) => self.save_as(&path, force),
+            Ok(Command::WriteQuit { path, force }) => {
+                match self.write(path, force) {
+                    Ok(()) => return self.quit(),
+                    Err(err) => Err(err),
+                }
+            }
+            Ok(Command::Exit { path, force }) => {
+                if self.buffer.is_saved() {
+                    return self.quit();
+                }
+                match self.write(path, force) {
+                    Ok(()) => return self.quit(),
+                    Err(err) => Err(err),
+                }
+            }
+            Ok(Command::Quit { force }) => {
+                if force || self.buffer.is_saved() {
+                    return self.quit();
+                }
+                Err("No write since last change (add ! to override)".to_string())
+            }
+            Ok(Command::Earlier(Travel::Steps(count))) => {
+                self.undo_step(-(count as isize));
+                Ok(())
+            }
+            Ok(Command::Earlier(Travel::Time(duration))) => {
+                let moved = self.buffer.earlier(duration);
+                self.report_undo(moved, "Already at oldest change");
+                Ok(())
+            }
+            Ok(Command::Later(Travel::Steps(count))) => {
+                self.undo_step(count as isize);
+                Ok(())
+            }
+            Ok(Command::Later(Travel::Time(duration))) => {
+                let moved = self.buffer.later(duration);
+                self.report_undo(moved, "Already at newest change");
+                Ok(())
+            }
+            Ok(Command::Map { modes, buffer, lhs, rhs: Option::None, .. }) => self.list_mappings(modes, buffer, lhs),
+            Ok(Command::Map { modes, noremap, silent, buffer, lhs, rhs: Option::Some(rhs) }) => {
+                self.map(modes, noremap, silent, buffer, &lhs.unwrap_or_default(), &rhs)
+            }
+            Ok(Command::Unmap { modes, buffer, lhs }) => self.unmap(modes, buffer, &lhs),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            self.message = Option::Some(err);
+        }
+        true
+    }
+
+    /// `:map {lhs} {rhs}` and its variants: makes `lhs` stand for `rhs` in
+    /// each of `modes`, replacing what `lhs` was mapped to there. An `rhs`
+    /// of `<Nop>` maps `lhs` to nothing.
+    fn map(&mut self, modes: &[Mode], noremap: bool, silent: bool, buffer: bool, lhs: &str, rhs: &str)
+           -> Result<(), String> {
+        let leader = &self.conf.options.leader;
+        let lhs = notation::parse_with_leader(lhs, leader)?;
+        if lhs.is_empty() {
+            return Err("Argument required".to_string());
+        }
+        let rhs = if rhs.eq_ignore_ascii_case("<Nop>") {
+            Vec::new()
+        } else {
+            notation::parse_with_leader(rhs, leader)?
+        };
+        for &mode in modes {
+            self.conf.mappings.retain(|mapping| !(mapping.mode == mode && mapping.lhs == lhs && mapping.buffer == buffer));
+            self.conf.mappings.push(Mapping { mode, lhs: lhs.clone(), rhs: rhs.clone(), noremap, silent, buffer });
+        }
+        self.update_keymaps();
+        Ok(())
+    }
+
+    /// `:unmap {lhs}` and its variants.
+    fn unmap(&mut self, modes: &[Mode], buffer: bool, lhs: &str) -> Result<(), String> {
+        let lhs = notation::parse_with_leader(lhs, &self.conf.options.leader)?;
+        let count = self.conf.mappings.len();
+        self.conf.mappings.retain(|mapping| {
+            !(modes.contains(&mapping.mode) && mapping.lhs == lhs && mapping.buffer == buffer)
+        });
+        if self.conf.mappings.len() == count {
+            return Err("No such mapping".to_string());
+        }
+        self.update_keymaps();
+        Ok(())
+    }
+
+    /// `:map` without `{rhs}`: shows the mappings of `modes` that start with
+    /// `lhs`, one per line as in Vim, `*` marking those that are not
+    /// remapped and `@` those of the buffer.
+    fn list_mappings(&mut self, modes: &[Mode], buffer: bool, lhs: Option<String>) -> Result<(), String> {
+        let prefix = match lhs {
+            Option::Some(lhs) => notation::parse_with_leader(&lhs, &self.conf.options.leader)?,
+            Option::None      => Vec::new(),
+        };
+        let lines: Vec<String> = self.conf.mappings.iter()
+            .filter(|mapping| modes.contains(&mapping.mode) && mapping.lhs.starts_with(&prefix))
+            .filter(|mapping| mapping.buffer || !buffer)
+            .map(|mapping| {
+                let mode = match mapping.mode {
+                    Mode::Insert          => 'i',
+                    Mode::Visual          => 'v',
+                    Mode::OperatorPending => 'o',
+                    _                     => 'n',
+                };
+                let rhs = if mapping.rhs.is_empty() { "<Nop>".to_string() } else { notation::to_string(&mapping.rhs) };
+                format!("{}  {:<11} {}{}{}", mode, notation::to_string(&mapping.lhs),
+                        if mapping.noremap { '*' } else { ' ' }, if mapping.buffer { '@' } else { ' ' }, rhs)
+            })
+            .collect();
+        if lines.is_empty() {
+            return Err("No mapping found".to_string());
+        }
+        self.message = Option::Some(lines.join("\n"));
+        Ok(())
+    }
+
+    /// `:w` and `:w {path}`: without a path the buffer goes to its own file,
+    /// with one it goes there and only the unnamed buffer takes the name.
+    fn write(&mut self, path: Option<String>, force: bool) -> Result<(), String> {
+        self.check_read_only(force)?;
+        let own_name = self.buffer.file_name().map(|name| name.to_string());
+        match (path, own_name) {
+            (Option::None, Option::None) => Err("No file name".to_string()),
+            (Option::None, Option::Some(name)) => self.save_to(&name, force),
+            (Option::Some(path), Option::Some(ref name)) if path == *name => self.save_to(&path, force),
+            (Option::Some(path), Option::Some(_)) => {
+                Engine::check_overwrite(&path, force)?;
+                self.buffer.write_to(&path, force).map_err(|err| format!("\"{}\" {}", path, err))?;
+                self.report_written(&path);
+                Ok(())
+            }
+            (Option::Some(path), Option::None) => {
+                Engine::check_overwrite(&path, force)?;
+                self.save_to(&path, force)
+            }
+        }
+    }
+
+    /// `:saveas {path}`: writes the buffer to `path` and keeps editing it
+    /// under that name.
+    fn save_as(&mut self, path: &str, force: bool) -> Result<(), String> {
+        self.check_read_only(force)?;
+        if self.buffer.file_name() != Option::Some(path) {
+            Engine::check_overwrite(path, force)?;
+        }
+        self.save_to(path, force)
+    }
+
+    /// Refuses to write a buffer opened with `-R` unless `force`d.
+    fn check_read_only(&self, force: bool) -> Result<(), String> {
+        if self.read_only && !force {
+            return Err("'readonly' option is set (add ! to override)".to_string());
+        }
+        Ok(())
+    }
+
+    fn save_to(&mut self, path: &str, force: bool) -> Result<(), String> {
+        let renamed = self.buffer.file_name() != Option::Some(path);
+        self.buffer.save_buffer(path, force).map_err(|err| format!("\"{}\" {}", path, err))?;
+        self.report_written(path);
+        let undo_path = undofile::undo_path(Path::new(path));
+        if let Err(err) = undofile::write(&undo_path, &mut self.buffer) {
+            self.message = Option::Some(format!("\"{}\" {}", undo_path.display(), err));
+        }
+        if renamed {
+            self.release_swap();
+            self.take_swap();
+        }
+        Ok(())
+    }
+
+    fn quit(&mut self) -> bool {
+        self.release_swap();
+        false
+    }
+
+    fn swap_prompt(info: &SwapInfo) -> String {
+        let owner = if info.is_process_running() {
+            format!("in use by process {}", info.pid)
+        } else {
+            "left by a session that did not exit cleanly".to_string()
+        };
+        format!("Found swap file \"{}\" {}: [R]ecover, [O]pen read-only, [D]elete it, [Q]uit",
+                info.path.display(), owner)
+    }
+
+    fn answer_swap_prompt(&mut self, key: Key) -> bool {
+        let info = match self.swap_conflict.take() {
+            Option::Some(info) => info,
+            Option::None       => return true,
+        };
+        self.message = Option::None;
+        match key.to_char().unwrap_or('\0') {
+            'r' | 'R' => match swap::recover(&info.path) {
+                Ok(mut buffer) => {
+                    if let Some(name) = self.buffer.file_name() {
+                        buffer.set_file_name(name);
+                    }
+                    buffer.set_keep_backup(self.buffer.keep_backup());
+                    self.buffer = buffer;
+                    self.take_swap();
+                    self.message = Option::Some("Recovered from the swap file, write the buffer to keep the changes".to_string());
+                }
+                Err(err) => {
+                    self.message = Option::Some(format!("\"{}\" {}", info.path.display(), err));
+                    self.swap_conflict = Option::Some(info);
+                }
+            },
+            'o' | 'O' => self.read_only = true,
+            'd' | 'D' => match swap::remove(&info.path) {
+                Ok(()) => self.take_swap(),
+                Err(err) => {
+                    self.message = Option::Some(format!("\"{}\" {}", info.path.display(), err));
+                    self.swap_conflict = Option::Some(info);
+                }
+            },
+            'q' | 'Q' => return false,
+            _ => {
+                self.message = Option::Some(Engine::swap_prompt(&info));
+                self.swap_conflict = Option::Some(info);
+            }
+        }
+        true
+    }
+
+    /// Starts journaling the buffer to the swap file next to its file.
+    fn take_swap(&mut self) {
+        let path = match self.buffer.file_name() {
+            Option::Some(name) => swap::swap_path(Path::new(name)),
+            Option::None       => return,
+        };
+        self.swap_path = Option::Some(path);
+        self.write_swap(true);
+    }
+
+    fn release_swap(&mut self) {
+        if let Some(path) = self.swap_path.take() {
+            if let Err(err) = swap::remove(&path) {
+                self.message = Option::Some(format!("\"{}\" {}", path.display(), err));
+            }
+        }
+    }
+
+    fn update_swap(&mut self) {
+        let pending = self.buffer.changes() - self.swap_changes;
+        let elapsed = self.swap_written_at.elapsed();
+        let idle_long = elapsed >= Duration::from_millis(UPDATE_TIME_MS);
+        if pending >= UPDATE_COUNT || (pending > 0 && idle_long) {
+            self.write_swap(idle_long);
+        }
+    }
+
+    /// Brings the swap file up to date, with `sync` making sure it reaches
+    /// the disk.
+    fn write_swap(&mut self, sync: bool) {
+        if let Some(ref path) = self.swap_path {
+            if let Err(err) = swap::write(path, &self.buffer, sync) {
+                self.message = Option::Some(format!("\"{}\" {}", path.display(), err));
+            }
+        }
+        self.swap_changes = self.buffer.changes();
+        self.swap_written_at = Instant::now();
+    }
+
+    fn check_overwrite(path: &str, force: bool) -> Result<(), String> {
+        if !force && Path::new(path).exists() {
+            Err("File exists (add ! to override)".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn report_written(&mut self, path: &str) {
+        self.message = Option::Some(format!("\"{}\" {}L written", path, self.buffer.count()));
+    }
 
     fn open_line_above(&mut self) {
-        self.up();
+        self.up(1);
         self.buffer.new_line();
     }
 
@@ -141,59 +2628,240 @@ impl Engine where {
         self.buffer.new_line();
     }
 
-    // fn go_to_start(&mut self) {
-    //     let buffer = &mut self.buffer;
-    //     seek_to_col(buffer, selection.start_line, selection.start_col);
-    // }
-    //
-    // fn go_to_end(&mut self) {
-    //     let buffer = &mut self.buffer;
-    //     seek_to_col(buffer, selection.end_line, selection.end_col);
-    // }
+    /// Types the text inserted since entering insert mode again, until it
+    /// went in as often as the count of the insert command asked.
+    fn repeat_insert(&mut self) {
+        let text = ::std::mem::take(&mut self.inserted);
+        for _ in 1..self.insert_count {
+            text.chars().for_each(|ch| self.buffer.put_char(ch));
+        }
+        self.insert_count = 1;
+    }
 
-    // pub fn end_word(buffer: &mut Buffer) -> Selection {
-    //     let start_line = get_active_line_index(buffer);
-    //     let start_col = get_current_col(buffer);
-    //
-    // }
+    /// `x`: deletes `count` chars from the cursor on, without going past
+    /// the end of the line.
+    fn delete_chars(&mut self, count: usize) {
+        if let Some((row, col)) = self.buffer.get_cursor_index() {
+            let len = self.buffer.line_len(row);
+            if col < len {
+                let removed = self.buffer.remove_text((row, col), (row, (col + count).min(len)));
+                self.store(false, Register::chars(removed));
+                let len = self.buffer.line_len(row);
+                self.buffer.move_cursor(row, col.min(len.saturating_sub(1)));
+            }
+        }
+    }
+
+    /// `p`, `P`, `gp` and `gP`: puts the text of the selected register
+    /// `count` times after or before the cursor: chars into the line, lines
+    /// below or above it, and a block into the lines from the cursor down.
+    /// With `move_past` the cursor ends up just after the new text.
+    fn put(&mut self, after: bool, move_past: bool, count: usize) {
+        let name = self.register_name.take().unwrap_or('"');
+        let register = match self.register(name) {
+            Option::Some(register) => register,
+            Option::None => {
+                self.message = Option::Some(format!("Nothing in register {}", name));
+                return;
+            }
+        };
+        let (row, col) = self.buffer.get_cursor_index().unwrap_or((0, 0));
+        let len = self.buffer.line_len(row);
+        let col = if after && len > 0 { (col + 1).min(len) } else { col.min(len) };
+        match register.kind {
+            RegisterKind::Chars => {
+                let text = register.text.repeat(count);
+                let end = self.buffer.insert_text(row, col, &text);
+                if move_past {
+                    self.buffer.move_cursor(end.0, end.1);
+                } else if text.contains('\n') {
+                    self.buffer.move_cursor(row, col);
+                } else {
+                    self.buffer.move_cursor(end.0, end.1.saturating_sub(1));
+                }
+            }
+            RegisterKind::Lines => {
+                let text = register.text.strip_suffix('\n').unwrap_or(&register.text);
+                let lines: Vec<String> = (0..count)
+                    .flat_map(|_| text.split('\n').map(|line| line.to_string()))
+                    .collect();
+                let at = if after && !self.buffer.is_empty() { row + 1 } else { row };
+                let added = lines.len();
+                self.buffer.replace_lines(at, 0, lines);
+                if move_past {
+                    self.buffer.move_cursor(at + added, 0);
+                } else {
+                    let col = motion::first_non_blank(&self.buffer, at);
+                    self.buffer.move_cursor(at, col);
+                }
+            }
+            RegisterKind::Block => {
+                let pieces: Vec<String> = register.text.split('\n').map(|piece| piece.repeat(count)).collect();
+                let width = pieces.iter().map(|piece| piece.chars().count()).max().unwrap_or(0);
+                for (offset, piece) in pieces.iter().enumerate() {
+                    let row = row + offset;
+                    if row >= self.buffer.count() {
+                        self.buffer.replace_lines(row, 0, vec![String::new()]);
+                    }
+                    let len = self.buffer.line_len(row);
+                    if len < col {
+                        self.buffer.insert_text(row, len, &" ".repeat(col - len));
+                    }
+                    // keep the text after the block lined up
+                    let mut piece = piece.clone();
+                    if self.buffer.line_len(row) > col {
+                        piece.push_str(&" ".repeat(width - piece.chars().count()));
+                    }
+                    self.buffer.insert_text(row, col, &piece);
+                }
+                if move_past {
+                    self.buffer.move_cursor(row + pieces.len() - 1, col + width);
+                } else {
+                    self.buffer.move_cursor(row, col);
+                }
+            }
+        }
+    }
 
-    // pub fn start_word(buffer: &mut Buffer) -> Selection {
+    /// The row a `z` command works on: line `count` when one was given,
+    /// after moving the cursor there, or the cursor's row.
+    fn count_row(&mut self, count: Option<usize>) -> Option<usize> {
+        if let Some(line) = count {
+            self.go_to_line(line);
+        }
+        self.buffer.get_cursor_index().map(|(row, _)| row)
+    }
 
-    // pub fn end_of_line(buffer: &mut Buffer) -> Selection {
+    /// `u`: reverts the last `count` changes.
+    fn undo(&mut self, count: usize) {
+        let mut undone = Option::None;
+        for _ in 0..count {
+            let state = self.buffer.undo_state();
+            if !self.buffer.undo() {
+                break;
+            }
+            undone = Option::Some(state);
+        }
+        self.message = Option::Some(match undone {
+            Option::Some(state) => format!("before #{}", state),
+            Option::None        => "Already at oldest change".to_string(),
+        });
+    }
 
-    // pub fn start_of_line(buffer: &mut Buffer) -> Selection {
+    /// `Ctrl-R`: redoes the last `count` changes that were undone.
+    fn redo(&mut self, count: usize) {
+        let mut moved = false;
+        for _ in 0..count {
+            if !self.buffer.redo() {
+                break;
+            }
+            moved = true;
+        }
+        self.report_undo(moved, "Already at newest change");
+    }
 
-    // pub fn start_of_text(buffer: &mut Buffer) -> Selection {
+    /// `g-` and `g+`: moves through the undo states in the order they were
+    /// created, going into branches `u` and `Ctrl-R` cannot reach.
+    fn undo_step(&mut self, count: isize) {
+        let moved = self.buffer.undo_step(count);
+        let at_end = if count < 0 { "Already at oldest change" } else { "Already at newest change" };
+        self.report_undo(moved, at_end);
+    }
 
-    // pub fn end_of_file(buffer: &mut Buffer) -> Selection {
+    fn report_undo(&mut self, moved: bool, at_end: &str) {
+        self.message = Option::Some(if moved {
+            format!("after #{}", self.buffer.undo_state())
+        } else {
+            at_end.to_string()
+        });
+    }
 
-    // pub fn start_of_file(buffer: &mut Buffer) -> Selection {
+    /// Moves the cursor to where a motion ended, onto the last char of the
+    /// line when it ended past it.
+    fn move_to(&mut self, row: usize, col: usize) {
+        let len = self.buffer.line_len(row);
+        self.buffer.move_cursor(row, col.min(len.saturating_sub(1)));
+    }
 
-    fn down(&mut self) {
+    fn up(&mut self, count: usize) {
         if let Some((row, col)) = self.buffer.get_cursor_index() {
-            self.buffer.move_cursor(row + 1, col)
+            self.buffer.move_cursor(row.saturating_sub(count), col)
         }
     }
 
-    fn up(&mut self) {
+    /// Lines scrolled by `Ctrl-F` and `Ctrl-B`, a screen minus two lines of
+    /// overlap.
+    fn page_size(&self) -> usize {
+        self.viewport.height.saturating_sub(2).max(1)
+    }
+
+    /// `Ctrl-F`: scrolls a page down and puts the cursor on the top line.
+    fn scroll_page_down(&mut self) {
+        if let Some((_, col)) = self.buffer.get_cursor_index() {
+            let count = self.page_size();
+            self.viewport.scroll_down(count, self.buffer.count());
+            self.buffer.move_cursor(self.viewport.top, col);
+        }
+    }
+
+    /// `Ctrl-B`: scrolls a page up and puts the cursor on the bottom line.
+    fn scroll_page_up(&mut self) {
+        if let Some((_, col)) = self.buffer.get_cursor_index() {
+            let count = self.page_size();
+            self.viewport.scroll_up(count);
+            self.buffer.move_cursor(self.viewport.bottom(), col);
+        }
+    }
+
+    /// `Ctrl-E`: scrolls the view down, dragging the cursor along only if
+    /// it would fall off the top.
+    fn scroll_down(&mut self, count: usize) {
+        self.viewport.scroll_down(count, self.buffer.count());
+        self.keep_cursor_in_view();
+    }
+
+    /// `Ctrl-Y`: scrolls the view up, dragging the cursor along only if it
+    /// would fall off the bottom.
+    fn scroll_up(&mut self, count: usize) {
+        self.viewport.scroll_up(count);
+        self.keep_cursor_in_view();
+    }
+
+    /// `Ctrl-D`: scrolls the view and the cursor down by half a screen, or
+    /// by `count` lines when one is given.
+    fn scroll_half_page_down(&mut self, count: Option<usize>) {
         if let Some((row, col)) = self.buffer.get_cursor_index() {
-            self.buffer.move_cursor(row - 1, col)
+            let count = count.unwrap_or(self.viewport.height / 2).max(1);
+            self.viewport.scroll_down(count, self.buffer.count());
+            self.buffer.move_cursor(row + count, col);
+            self.keep_cursor_in_view();
         }
     }
 
-    fn left(&mut self) {
+    /// `Ctrl-U`: scrolls the view and the cursor up by half a screen, or
+    /// by `count` lines when one is given.
+    fn scroll_half_page_up(&mut self, count: Option<usize>) {
         if let Some((row, col)) = self.buffer.get_cursor_index() {
-            self.buffer.move_cursor(row, col - 1)
+            let count = count.unwrap_or(self.viewport.height / 2).max(1);
+            self.viewport.scroll_up(count);
+            self.buffer.move_cursor(row.saturating_sub(count), col);
+            self.keep_cursor_in_view();
         }
     }
 
-    fn right(&mut self) {
+    fn keep_cursor_in_view(&mut self) {
         if let Some((row, col)) = self.buffer.get_cursor_index() {
-            self.buffer.move_cursor(row, col + 1)
+            let visible = self.viewport.clamp_row(row);
+            if visible != row {
+                self.buffer.move_cursor(visible, col);
+            }
         }
     }
 
     fn switch_mode(&mut self, mode: Mode) {
+        if let Mode::Insert = mode {
+            self.message = Option::None;
+        }
         self.clear_command_buffer();
         self.mode = mode;
     }
@@ -201,19 +2869,44 @@ impl Engine where {
     fn clear_command_buffer(&mut self) {
         self.command_buffer.clear();
     }
+}
+
+/// Whether `pattern` has chars a Vim regular expression would read
+/// differently from `Engine::find`, which takes them literally.
+fn has_regex_chars(pattern: &str) -> bool {
+    let pattern = pattern.strip_prefix("\\<").unwrap_or(pattern);
+    let pattern = pattern.strip_suffix("\\>").unwrap_or(pattern);
+    pattern.contains(['.', '*', '[', ']', '~', '^', '$', '\\'])
+}
+
+/// The leading whitespace of `line` as its length in chars and its width
+/// in columns.
+fn indent_of(line: &str, tab_width: usize) -> (usize, usize) {
+    let chars = line.chars().take_while(|&ch| ch == ' ' || ch == '\t').count();
+    (chars, display_col(line, chars, tab_width))
+}
+
+/// `text` with its case changed as `gu`, `gU` or `g~` do.
+fn change_case(operator: Action, text: &str) -> String {
+    match operator {
+        Action::Lowercase => text.to_lowercase(),
+        Action::Uppercase => text.to_uppercase(),
+        _ => text.chars().flat_map(|ch| {
+            if ch.is_uppercase() { ch.to_lowercase().collect::<Vec<_>>() } else { ch.to_uppercase().collect() }
+        }).collect(),
+    }
+}
 
-    //pub fn scrap(buffer: &mut Buffer, selection: Select) {
-    //}
-
-    // fn right(self: &mut Self) -> Selection {
-    //     let buffer = &mut self.buffer;
-    //     let line = get_active_line_index(buffer);
-    //     let col = get_active_col_index(buffer);
-    //     Selection {
-    //         start_line: line,
-    //         start_col: col,
-    //         end_line: line,
-    //         end_col: col + 1,
-    //     }
-    // }
+/// The screen column of text column `col` in `line`, tabs taking up to
+/// `tab_width` columns.
+fn display_col(line: &str, col: usize, tab_width: usize) -> usize {
+    line.chars().take(col).fold(0, |display, ch| {
+        if ch == '\t' { display + tab_width - display % tab_width } else { display + 1 }
+    })
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
 }
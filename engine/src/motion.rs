@@ -0,0 +1,390 @@
+//! Where word, sentence, paragraph and char finding motions land, worked
+//! out on the text of a `Buffer`.
+//!
+//! Positions are `(row, col)`. Each line also has a position at its end,
+//! `(row, len)`, standing for its newline, so that motions can treat line
+//! breaks as the blanks between words.
+
+use rustudio_buffer::Buffer;
+use std::ops::Range;
+
+/// What Vim tells words apart by. Empty lines count as words of their own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Class {
+    Blank,
+    Empty,
+    Punctuation,
+    Word,
+}
+
+/// A position in `buffer` that steps through its chars and newlines.
+struct Walker<'a> {
+    buffer: &'a Buffer,
+    row: usize,
+    col: usize,
+}
+
+impl<'a> Walker<'a> {
+    fn new(buffer: &'a Buffer, (row, col): (usize, usize)) -> Self {
+        let row = row.min(buffer.count().saturating_sub(1));
+        Walker { buffer, row, col: col.min(buffer.line_len(row)) }
+    }
+
+    fn pos(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    /// The char here, `None` at the end of a line.
+    fn ch(&self) -> Option<char> {
+        self.buffer.get_line_at(self.row).and_then(|line| line.get_char_at(self.col)).copied()
+    }
+
+    /// `big` is for WORDs, which only blanks separate.
+    fn class(&self, big: bool) -> Class {
+        match self.ch() {
+            Option::None if self.buffer.line_len(self.row) == 0 => Class::Empty,
+            Option::None => Class::Blank,
+            Option::Some(' ') | Option::Some('\t') => Class::Blank,
+            Option::Some(_) if big => Class::Word,
+            Option::Some(ch) if ch.is_alphanumeric() || ch == '_' => Class::Word,
+            Option::Some(_) => Class::Punctuation,
+        }
+    }
+
+    /// Steps to the next position, returning `false` at the end of the
+    /// buffer.
+    fn next(&mut self) -> bool {
+        if self.col < self.buffer.line_len(self.row) {
+            self.col += 1;
+        } else if self.row + 1 < self.buffer.count() {
+            self.row += 1;
+            self.col = 0;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Steps to the previous position, returning `false` at the start of
+    /// the buffer.
+    fn prev(&mut self) -> bool {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.buffer.line_len(self.row);
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+/// `w` and `W`: the start of the next word, an empty line or the end of
+/// the buffer.
+pub fn word_forward(buffer: &Buffer, pos: (usize, usize), big: bool) -> (usize, usize) {
+    let mut walker = Walker::new(buffer, pos);
+    let class = walker.class(big);
+    if class == Class::Empty {
+        walker.next();
+    } else if class != Class::Blank {
+        while walker.class(big) == class {
+            if !walker.next() {
+                return walker.pos();
+            }
+        }
+    }
+    while walker.class(big) == Class::Blank {
+        if !walker.next() {
+            break;
+        }
+    }
+    walker.pos()
+}
+
+/// `b` and `B`: the start of this word or the one before, or an empty
+/// line.
+pub fn word_backward(buffer: &Buffer, pos: (usize, usize), big: bool) -> (usize, usize) {
+    let mut walker = Walker::new(buffer, pos);
+    if !walker.prev() {
+        return walker.pos();
+    }
+    while walker.class(big) == Class::Blank {
+        if !walker.prev() {
+            return walker.pos();
+        }
+    }
+    let class = walker.class(big);
+    if class == Class::Empty {
+        return walker.pos();
+    }
+    while walker.prev() {
+        if walker.class(big) != class {
+            walker.next();
+            break;
+        }
+    }
+    walker.pos()
+}
+
+/// `e` and `E`: the end of this word, or of the next one when already at
+/// the end.
+pub fn word_end(buffer: &Buffer, pos: (usize, usize), big: bool) -> (usize, usize) {
+    let mut walker = Walker::new(buffer, pos);
+    if !walker.next() {
+        return pos;
+    }
+    while matches!(walker.class(big), Class::Blank | Class::Empty) {
+        if !walker.next() {
+            return walker.pos();
+        }
+    }
+    current_word_end(walker, big)
+}
+
+/// The end of the word at `pos`, which is `pos` itself at its last char.
+/// `cw` changes up to here.
+pub fn end_of_word_at(buffer: &Buffer, pos: (usize, usize), big: bool) -> (usize, usize) {
+    current_word_end(Walker::new(buffer, pos), big)
+}
+
+fn current_word_end(mut walker: Walker, big: bool) -> (usize, usize) {
+    let class = walker.class(big);
+    while walker.next() {
+        if walker.class(big) != class {
+            walker.prev();
+            break;
+        }
+    }
+    walker.pos()
+}
+
+/// `ge` and `gE`: the end of the word before, or an empty line.
+pub fn word_end_backward(buffer: &Buffer, pos: (usize, usize), big: bool) -> (usize, usize) {
+    let mut walker = Walker::new(buffer, pos);
+    let class = walker.class(big);
+    if class == Class::Empty {
+        if !walker.prev() {
+            return walker.pos();
+        }
+    } else if class != Class::Blank {
+        while walker.class(big) == class {
+            if !walker.prev() {
+                return walker.pos();
+            }
+        }
+    }
+    while walker.class(big) == Class::Blank {
+        if !walker.prev() {
+            break;
+        }
+    }
+    walker.pos()
+}
+
+/// Whether a sentence starts at `pos`: the first char after a `.`, `!` or
+/// `?` (and any closing `)`, `]`, `"` or `'`) followed by blanks or a line
+/// break, the first char after an empty line, or an empty line after text.
+pub fn is_sentence_start(buffer: &Buffer, pos: (usize, usize)) -> bool {
+    let mut walker = Walker::new(buffer, pos);
+    match walker.class(false) {
+        Class::Empty => return pos.0 == 0 || buffer.line_len(pos.0 - 1) > 0,
+        Class::Blank => return false,
+        _ => (),
+    }
+    let mut blanks = 0;
+    while walker.prev() {
+        match walker.class(false) {
+            Class::Empty => return true,
+            Class::Blank => blanks += 1,
+            _ if blanks == 0 => return false,
+            _ => {
+                while matches!(walker.ch(), Option::Some(')') | Option::Some(']') | Option::Some('"') | Option::Some('\'')) {
+                    if !walker.prev() {
+                        return false;
+                    }
+                }
+                return matches!(walker.ch(), Option::Some('.') | Option::Some('!') | Option::Some('?'));
+            }
+        }
+    }
+    true
+}
+
+/// `)`: the start of the next sentence, or the end of the buffer.
+pub fn sentence_forward(buffer: &Buffer, pos: (usize, usize)) -> (usize, usize) {
+    let mut walker = Walker::new(buffer, pos);
+    while walker.next() {
+        if is_sentence_start(buffer, walker.pos()) {
+            break;
+        }
+    }
+    walker.pos()
+}
+
+/// `(`: the start of this sentence, or of the one before when already at
+/// the start.
+pub fn sentence_backward(buffer: &Buffer, pos: (usize, usize)) -> (usize, usize) {
+    let mut walker = Walker::new(buffer, pos);
+    while walker.prev() {
+        if is_sentence_start(buffer, walker.pos()) {
+            break;
+        }
+    }
+    walker.pos()
+}
+
+/// `}`: the empty line after this paragraph, or the end of the buffer.
+pub fn paragraph_forward(buffer: &Buffer, row: usize) -> (usize, usize) {
+    let last = buffer.count().saturating_sub(1);
+    let mut row = row;
+    while row < last && buffer.line_len(row) == 0 {
+        row += 1;
+    }
+    while row < last && buffer.line_len(row) > 0 {
+        row += 1;
+    }
+    if buffer.line_len(row) > 0 {
+        (row, buffer.line_len(row))
+    } else {
+        (row, 0)
+    }
+}
+
+/// `{`: the empty line before this paragraph, or the start of the buffer.
+pub fn paragraph_backward(buffer: &Buffer, row: usize) -> (usize, usize) {
+    let mut row = row;
+    while row > 0 && buffer.line_len(row) == 0 {
+        row -= 1;
+    }
+    while row > 0 && buffer.line_len(row) > 0 {
+        row -= 1;
+    }
+    (row, 0)
+}
+
+/// Sets `inside` to whether each char of `row` looks like part of a string
+/// or char literal, quotes included. A guess for C-like text rather than
+/// syntax info: strings are in `"` on one line and may hold `\"`, char
+/// literals are a char or an escape in `'`.
+fn literals(buffer: &Buffer, row: usize, inside: &mut Vec<bool>) {
+    inside.clear();
+    let line = match buffer.get_line_at(row) {
+        Option::Some(line) => line,
+        Option::None => return,
+    };
+    let len = line.count();
+    let ch = |at: usize| line.get_char_at(at).copied();
+    inside.resize(len, false);
+    let mut index = 0;
+    while index < len {
+        let end = match ch(index) {
+            Option::Some('"') => (index + 1..len)
+                .scan(false, |escaped, at| {
+                    let found = ch(at) == Option::Some('"') && !*escaped;
+                    *escaped = ch(at) == Option::Some('\\') && !*escaped;
+                    Option::Some((at, found))
+                })
+                .find(|&(_, found)| found)
+                .map(|(at, _)| at),
+            Option::Some('\'') if ch(index + 2) == Option::Some('\'') => Option::Some(index + 2),
+            Option::Some('\'') if ch(index + 1) == Option::Some('\\') && ch(index + 3) == Option::Some('\'') => {
+                Option::Some(index + 3)
+            }
+            _ => Option::None,
+        };
+        match end {
+            Option::Some(end) => {
+                inside[index..=end].iter_mut().for_each(|inside| *inside = true);
+                index = end + 1;
+            }
+            Option::None => index += 1,
+        }
+    }
+}
+
+/// The `close` matching `open` after `pos`, or the `open` matching `close`
+/// before it when not `forward`, skipping pairs nested in between. With
+/// `skip_literals`, outside what `literals` guesses are string and char
+/// literals the ones inside are skipped, and inside one the ones outside
+/// it.
+pub fn find_unmatched(buffer: &Buffer, pos: (usize, usize), open: char, close: char, forward: bool,
+                      skip_literals: bool) -> Option<(usize, usize)> {
+    find_unmatched_in(buffer, pos, open, close, forward, skip_literals, 0..buffer.count())
+}
+
+/// As `find_unmatched`, but giving up outside `rows`.
+pub fn find_unmatched_in(buffer: &Buffer, pos: (usize, usize), open: char, close: char, forward: bool,
+                         skip_literals: bool, rows: Range<usize>) -> Option<(usize, usize)> {
+    let mut walker = Walker::new(buffer, pos);
+    let (deeper, shallower) = if forward { (open, close) } else { (close, open) };
+    let mut inside = Vec::new();
+    if skip_literals {
+        literals(buffer, pos.0, &mut inside);
+    }
+    let in_literal = inside.get(pos.1).copied().unwrap_or(false);
+    let mut row = pos.0;
+    let mut depth = 0;
+    while if forward { walker.next() } else { walker.prev() } {
+        if walker.row != row {
+            row = walker.row;
+            if !rows.contains(&row) {
+                break;
+            }
+            if skip_literals {
+                literals(buffer, row, &mut inside);
+            }
+        }
+        if inside.get(walker.col).copied().unwrap_or(false) != in_literal {
+            continue;
+        }
+        match walker.ch() {
+            Option::Some(ch) if ch == shallower && depth == 0 => return Option::Some(walker.pos()),
+            Option::Some(ch) if ch == shallower => depth -= 1,
+            Option::Some(ch) if ch == deeper => depth += 1,
+            _ => (),
+        }
+    }
+    Option::None
+}
+
+/// `%`: the bracket matching the first one of `pairs` at or after `col`
+/// in `row`.
+pub fn match_pair(buffer: &Buffer, (row, col): (usize, usize), pairs: &[(char, char)], skip_literals: bool)
+                  -> Option<(usize, usize)> {
+    let line = buffer.get_line_at(row)?;
+    let (at, ch, (open, close)) = (col..line.count()).find_map(|at| {
+        let ch = *line.get_char_at(at)?;
+        pairs.iter().find(|&&(open, close)| ch == open || ch == close).map(|&pair| (at, ch, pair))
+    })?;
+    find_unmatched(buffer, (row, at), open, close, ch == open, skip_literals)
+}
+
+/// `f`, `F`, `t` and `T`: the column of the `count`th `ch` after or before
+/// `col` in `row`, or of the char next to it on this side for `till`.
+/// `None` when the line does not have that many.
+pub fn find_char(buffer: &Buffer, (row, col): (usize, usize), ch: char, forward: bool, till: bool,
+                 count: usize) -> Option<usize> {
+    let line = buffer.get_line_at(row)?;
+    let mut at = col;
+    for _ in 0..count {
+        at = line.find_char(ch, at, forward)?;
+    }
+    Option::Some(match (till, forward) {
+        (false, _)    => at,
+        (true, true)  => at - 1,
+        (true, false) => at + 1,
+    })
+}
+
+/// `^`: the column of the first char of `row` that is not a blank.
+pub fn first_non_blank(buffer: &Buffer, row: usize) -> usize {
+    let line = buffer.line_text(row).unwrap_or_default();
+    line.chars().take_while(|&ch| ch == ' ' || ch == '\t').count()
+}
+
+/// `g_`: the column of the last char of `row` that is not a blank.
+pub fn last_non_blank(buffer: &Buffer, row: usize) -> usize {
+    let line: Vec<char> = buffer.line_text(row).unwrap_or_default().chars().collect();
+    line.iter().rposition(|&ch| ch != ' ' && ch != '\t').unwrap_or(0)
+}
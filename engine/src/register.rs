@@ -0,0 +1,198 @@
+//! Where deleted, changed and yanked text is kept.
+
+use std::collections::HashMap;
+
+/// How the text of a register goes back into the buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegisterKind {
+    /// Into the line, at the cursor.
+    Chars,
+    /// As whole lines of their own.
+    Lines,
+    /// As a block, one line of the text into each line of the buffer.
+    Block,
+}
+
+/// Text kept for putting back.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Register {
+    pub text: String,
+    pub kind: RegisterKind,
+}
+
+impl Register {
+    /// `text` taken charwise.
+    pub fn chars(text: String) -> Self {
+        Register { text, kind: RegisterKind::Chars }
+    }
+
+    /// The whole `lines`, each ending in a newline.
+    pub fn lines(lines: &[String]) -> Self {
+        let mut text = String::new();
+        for line in lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+        Register { text, kind: RegisterKind::Lines }
+    }
+
+    /// The `pieces` of a block, one per line.
+    pub fn block(pieces: &[String]) -> Self {
+        Register { text: pieces.join("\n"), kind: RegisterKind::Block }
+    }
+}
+
+/// Every register, by the name it is selected with after `"`.
+#[derive(Default)]
+pub struct Registers {
+    /// `""`: the text of the last delete, change or yank, whatever
+    /// register it went to.
+    unnamed: Option<Register>,
+    /// `"a` to `"z`.
+    named: HashMap<char, Register>,
+    /// `"0` holds the last yank, `"1` to `"9` the last deletes of more than
+    /// part of a line, newest first.
+    numbered: [Option<Register>; 10],
+    /// `"-`: the last delete within a line.
+    small_delete: Option<Register>,
+    /// `".`: the text last typed in insert mode.
+    last_inserted: Option<String>,
+    /// `":`: the last command-line command.
+    last_command: Option<String>,
+}
+
+impl Registers {
+    pub fn new() -> Self {
+        Registers::default()
+    }
+
+    /// Whether `"` followed by `name` selects a register. `"%` and the
+    /// others that cannot be written can still be put from.
+    pub fn is_name(name: char) -> bool {
+        name.is_ascii_alphanumeric() || "\"-_.%:/".contains(name)
+    }
+
+    /// The register `name`, if anything is in it. `"%` and `"/` are for the
+    /// engine to answer, as only it knows the file name and the last
+    /// search.
+    pub fn get(&self, name: char) -> Option<Register> {
+        match name {
+            '"' => self.unnamed.clone(),
+            'a'..='z' | 'A'..='Z' => self.named.get(&name.to_ascii_lowercase()).cloned(),
+            '0'..='9' => self.numbered[name as usize - '0' as usize].clone(),
+            '-' => self.small_delete.clone(),
+            '.' => self.last_inserted.clone().map(Register::chars),
+            ':' => self.last_command.clone().map(Register::chars),
+            _ => Option::None,
+        }
+    }
+
+    /// Keeps yanked text in register `name`, or in `"0` without a name.
+    pub fn yank(&mut self, name: Option<char>, register: Register) -> Result<(), String> {
+        match name {
+            Option::None | Option::Some('"') => {
+                self.numbered[0] = Option::Some(register.clone());
+                self.unnamed = Option::Some(register);
+                Ok(())
+            }
+            Option::Some(name) => self.write(name, register),
+        }
+    }
+
+    /// Keeps deleted text in register `name`. Deletes of more than part of
+    /// a line also go to `"1`, moving the older ones up to `"9`; without a
+    /// name, smaller ones go to `"-` instead.
+    pub fn delete(&mut self, name: Option<char>, register: Register) -> Result<(), String> {
+        if name == Option::Some('_') {
+            return Ok(());
+        }
+        if let Some(name) = name {
+            if !Registers::is_writable(name) {
+                return Err(invalid(name));
+            }
+        }
+        let small = register.kind == RegisterKind::Chars && !register.text.contains('\n');
+        if !small {
+            self.numbered[1..].rotate_right(1);
+            self.numbered[1] = Option::Some(register.clone());
+        }
+        match name {
+            Option::None | Option::Some('"') => {
+                if small {
+                    self.small_delete = Option::Some(register.clone());
+                }
+                self.unnamed = Option::Some(register);
+                Ok(())
+            }
+            Option::Some(name) => self.write(name, register),
+        }
+    }
+
+    pub fn set_last_inserted(&mut self, text: String) {
+        self.last_inserted = Option::Some(text);
+    }
+
+    pub fn set_last_command(&mut self, text: String) {
+        self.last_command = Option::Some(text);
+    }
+
+    fn is_writable(name: char) -> bool {
+        name.is_ascii_alphanumeric() || "\"-_".contains(name)
+    }
+
+    /// Puts `register` in the register `name`, an uppercase letter adding
+    /// to the end of the lowercase one. The unnamed register gets what
+    /// ends up there.
+    fn write(&mut self, name: char, register: Register) -> Result<(), String> {
+        let register = match name {
+            '_' => return Ok(()),
+            'a'..='z' => register,
+            'A'..='Z' => match self.named.remove(&name.to_ascii_lowercase()) {
+                Option::Some(old) => append(old, register),
+                Option::None      => register,
+            },
+            '0'..='9' => {
+                self.numbered[name as usize - '0' as usize] = Option::Some(register.clone());
+                self.unnamed = Option::Some(register);
+                return Ok(());
+            }
+            '-' => {
+                self.small_delete = Option::Some(register.clone());
+                self.unnamed = Option::Some(register);
+                return Ok(());
+            }
+            '"' => {
+                self.unnamed = Option::Some(register);
+                return Ok(());
+            }
+            _ => return Err(invalid(name)),
+        };
+        self.named.insert(name.to_ascii_lowercase(), register.clone());
+        self.unnamed = Option::Some(register);
+        Ok(())
+    }
+}
+
+fn invalid(name: char) -> String {
+    format!("Invalid register name: '{}'", name)
+}
+
+/// `new` added to the end of `old`. Whole lines on either side make the
+/// result whole lines.
+fn append(old: Register, new: Register) -> Register {
+    match (old.kind, new.kind) {
+        (RegisterKind::Lines, _) | (_, RegisterKind::Lines) => {
+            let mut text = old.text;
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&new.text);
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            Register { text, kind: RegisterKind::Lines }
+        }
+        (RegisterKind::Block, _) => Register { text: old.text + "\n" + &new.text, kind: RegisterKind::Block },
+        (kind, _) => Register { text: old.text + &new.text, kind },
+    }
+}
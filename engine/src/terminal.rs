@@ -0,0 +1,170 @@
+use rustudio_keys::{self, Key};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How a cell is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Style {
+    #[default]
+    Normal,
+    /// Filler that is not part of the text, like the `~` past the end of
+    /// the buffer.
+    NonText,
+    /// The line numbers left of the text.
+    LineNumber,
+    /// Text selected in visual mode.
+    Selected,
+    /// The bracket matching the one under the cursor.
+    MatchParen,
+}
+
+/// One character on the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+}
+
+impl Cell {
+    pub fn new(ch: char, style: Style) -> Self {
+        Cell { ch, style }
+    }
+}
+
+/// What a terminal reports back to the editor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Key(Key),
+    /// The screen changed size, everything has to be drawn again.
+    Resize,
+    /// No key came in within the timeout, see `Engine::idle`.
+    Idle,
+}
+
+/// A screen the editor draws on and reads keys from.
+pub trait Terminal {
+    /// Waits up to `timeout` for the next event, giving `Event::Idle` if
+    /// none came. `None` means no input will ever come again and the
+    /// editor should stop.
+    fn read_event(&mut self, timeout: Duration) -> Option<Event>;
+
+    /// The screen size as `(height, width)`.
+    fn size(&self) -> (usize, usize);
+
+    /// Replaces screen line `row` with `cells`, clearing whatever the old
+    /// line had past their end.
+    fn draw_cells(&mut self, row: usize, cells: &[Cell]);
+
+    fn set_cursor(&mut self, row: usize, col: usize);
+
+    /// Makes everything drawn since the last flush visible.
+    fn flush(&mut self);
+}
+
+/// An in-memory terminal that replays scripted events and records what
+/// was drawn, so the editor can run without a real screen.
+///
+/// ```
+/// use rustudio_engine::Engine;
+/// use rustudio_engine::terminal::HeadlessTerminal;
+///
+/// let mut terminal = HeadlessTerminal::new(3, 20);
+/// terminal.push_keys("ihello\x1b");
+/// let mut engine = Engine::new();
+/// engine.run(&mut terminal);
+/// assert_eq!(terminal.line(0), "hello");
+/// assert_eq!(terminal.line(1), "~");
+/// ```
+pub struct HeadlessTerminal {
+    height: usize,
+    width: usize,
+    screen: Vec<Vec<Cell>>,
+    cursor: (usize, usize),
+    events: VecDeque<Event>,
+    redrawn: Vec<usize>,
+}
+
+impl HeadlessTerminal {
+    pub fn new(height: usize, width: usize) -> Self {
+        HeadlessTerminal {
+            height,
+            width,
+            screen: vec![Vec::new(); height],
+            cursor: (0, 0),
+            events: VecDeque::new(),
+            redrawn: Vec::new(),
+        }
+    }
+
+    pub fn push_event(&mut self, event: Event) {
+        self.events.push_back(event);
+    }
+
+    /// Queues every char of `keys` as a key press, control chars standing
+    /// for the keys a terminal sends them for.
+    pub fn push_keys(&mut self, keys: &str) {
+        for key in rustudio_keys::keys(keys) {
+            self.push_event(Event::Key(key));
+        }
+    }
+
+    /// Changes the screen size and queues the matching `Event::Resize`.
+    pub fn resize(&mut self, height: usize, width: usize) {
+        self.height = height;
+        self.width = width;
+        self.screen.resize(height, Vec::new());
+        for line in &mut self.screen {
+            line.truncate(width);
+        }
+        self.push_event(Event::Resize);
+    }
+
+    pub fn cells(&self, row: usize) -> &[Cell] {
+        self.screen.get(row).map(|line| line.as_slice()).unwrap_or(&[])
+    }
+
+    /// The text on screen line `row`.
+    pub fn line(&self, row: usize) -> String {
+        self.cells(row).iter().map(|cell| cell.ch).collect()
+    }
+
+    /// The text of every screen line, top to bottom.
+    pub fn screen(&self) -> Vec<String> {
+        (0..self.height).map(|row| self.line(row)).collect()
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    /// The rows drawn since the last call, in drawing order.
+    pub fn take_redrawn(&mut self) -> Vec<usize> {
+        ::std::mem::take(&mut self.redrawn)
+    }
+}
+
+impl Terminal for HeadlessTerminal {
+    /// Scripted events come at once, `Event::Idle` stands for the time
+    /// running out.
+    fn read_event(&mut self, _timeout: Duration) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    fn size(&self) -> (usize, usize) {
+        (self.height, self.width)
+    }
+
+    fn draw_cells(&mut self, row: usize, cells: &[Cell]) {
+        if let Some(line) = self.screen.get_mut(row) {
+            line.clear();
+            line.extend(cells.iter().take(self.width));
+            self.redrawn.push(row);
+        }
+    }
+
+    fn set_cursor(&mut self, row: usize, col: usize) {
+        self.cursor = (row, col);
+    }
+
+    fn flush(&mut self) {}
+}
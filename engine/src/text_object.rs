@@ -0,0 +1,306 @@
+//! Text objects, the text around the cursor an operator or visual mode
+//! can take as a whole: `iw`, `a(`, `it` and the like.
+//!
+//! Each function takes the cursor and the count and gives the selected
+//! text, or `None` when there is no such object around the cursor. The
+//! `i` objects take what is inside, the `a` objects also the delimiters or
+//! the white space around.
+
+use motion;
+use rustudio_buffer::Buffer;
+use {MotionKind, Selection};
+
+/// Runs of equal items as `(first, last, item)`, so that `aabccc` gives
+/// `(0, 1, a)`, `(2, 2, b)` and `(3, 5, c)`.
+fn runs<T: Copy + PartialEq>(items: &[T]) -> Vec<(usize, usize, T)> {
+    let mut runs: Vec<(usize, usize, T)> = Vec::new();
+    for (index, &item) in items.iter().enumerate() {
+        match runs.last_mut() {
+            Option::Some(run) if run.2 == item => run.1 = index,
+            _ => runs.push((index, index, item)),
+        }
+    }
+    runs
+}
+
+/// The first and last run of `count` things from run `at` on, each a run
+/// together with the blank run after it. Starting on a blank run, the
+/// blank goes before the thing instead. Without blanks after the last
+/// thing, the blanks before the first one are taken if there are any.
+fn around<T>(runs: &[(usize, usize, T)], at: usize, count: usize, is_blank: &dyn Fn(&T) -> bool)
+             -> (usize, usize) {
+    let starts_blank = is_blank(&runs[at].2);
+    let mut last = at;
+    for index in 0..count {
+        if index > 0 {
+            if last + 1 == runs.len() {
+                break;
+            }
+            last += 1;
+        }
+        if starts_blank {
+            if is_blank(&runs[last].2) && last + 1 < runs.len() {
+                last += 1;
+            }
+        } else if last + 1 < runs.len() && is_blank(&runs[last + 1].2) {
+            last += 1;
+        }
+    }
+    let first = if !starts_blank && !is_blank(&runs[last].2) && at > 0 && is_blank(&runs[at - 1].2) {
+        at - 1
+    } else {
+        at
+    };
+    (first, last)
+}
+
+/// `iw`, `aw`, `iW` and `aW`: `count` words from the cursor on, the white
+/// space between them counting as words for `iw`. Words go on over line
+/// breaks, which count as white space, and an empty line is a word of its
+/// own. The white space `aw` takes after the last word stops at the end of
+/// its line, as in Vim.
+pub fn word(buffer: &Buffer, (row, col): (usize, usize), around_it: bool, big: bool, count: usize)
+            -> Option<(Selection, MotionKind)> {
+    // the chars from the cursor line on with their class: 0 for blanks, 1
+    // for punctuation and 2 for word chars, as in `motion`, and 3 for empty
+    // lines. Line breaks are blanks at the end of their line.
+    let mut items: Vec<((usize, usize), u8)> = Vec::new();
+    let mut changes = 0;
+    for line_row in row..buffer.count() {
+        // no more lines than `count` words and the blanks around them need
+        if line_row > row && changes > 2 * count + 2 {
+            break;
+        }
+        let from = items.len();
+        if line_row > row {
+            items.push(((line_row - 1, buffer.line_len(line_row - 1)), 0));
+        }
+        let line = buffer.line_text(line_row)?;
+        if line.is_empty() {
+            items.push(((line_row, 0), 3));
+        }
+        items.extend(line.chars().enumerate().map(|(at, ch)| ((line_row, at), match ch {
+            ' ' | '\t' => 0,
+            _ if big => 2,
+            _ if ch.is_alphanumeric() || ch == '_' => 2,
+            _ => 1,
+        })));
+        if line_row > row {
+            changes += items[from - 1..].windows(2).filter(|pair| pair[0].1 != pair[1].1).count();
+        }
+    }
+    let classes: Vec<u8> = items.iter().map(|&(_, class)| class).collect();
+    let runs = runs(&classes);
+    let at = runs.iter().position(|run| items[run.0].0 <= (row, col) && (row, col) <= items[run.1].0)?;
+    let (mut first, mut last) = if around_it {
+        around(&runs, at, count, &|&class| class == 0)
+    } else {
+        (at, (at + count - 1).min(runs.len() - 1))
+    };
+    let mut end = runs[last].1;
+    let is_line_break = |index: usize| {
+        let (item_row, item_col) = items[index].0;
+        classes[index] == 0 && item_col == buffer.line_len(item_row)
+    };
+    if around_it && last > at && runs[last].2 == 0 {
+        match (runs[last].0..=end).find(|&index| is_line_break(index)) {
+            Option::Some(line_break) if line_break > runs[last].0 => end = line_break - 1,
+            Option::Some(_) => {
+                last -= 1;
+                end = runs[last].1;
+                if first == at && at > 0 && runs[at - 1].2 == 0 {
+                    first = at - 1;
+                }
+            }
+            Option::None => (),
+        }
+    }
+    Option::Some((Selection::new(items[runs[first].0].0, items[end].0), MotionKind::Inclusive))
+}
+
+/// `is` and `as`: `count` sentences from the one under the cursor, `as`
+/// with the white space after them.
+pub fn sentence(buffer: &Buffer, pos: (usize, usize), around_it: bool, count: usize)
+                -> Option<(Selection, MotionKind)> {
+    let start = if motion::is_sentence_start(buffer, pos) { pos } else { motion::sentence_backward(buffer, pos) };
+    let mut end = (0..count).fold(start, |end, _| motion::sentence_forward(buffer, end));
+    if !around_it {
+        loop {
+            let blank_before = match end {
+                (row, 0) => row > start.0,
+                (row, col) => buffer.get_line_at(row)
+                    .and_then(|line| line.get_char_at(col - 1))
+                    .is_some_and(|&ch| ch == ' ' || ch == '\t'),
+            };
+            if !blank_before || end <= start {
+                break;
+            }
+            end = match end {
+                (row, 0) => (row - 1, buffer.line_len(row - 1)),
+                (row, col) => (row, col - 1),
+            };
+        }
+    }
+    Option::Some((Selection::new(start, end), MotionKind::Exclusive))
+}
+
+/// `ip` and `ap`: `count` paragraphs from the one under the cursor, blank
+/// lines between them counting as paragraphs for `ip`.
+pub fn paragraph(buffer: &Buffer, row: usize, around_it: bool, count: usize) -> Option<(Selection, MotionKind)> {
+    let blanks: Vec<bool> = (0..buffer.count())
+        .map(|row| buffer.line_text(row).is_none_or(|line| line.trim().is_empty()))
+        .collect();
+    let runs = runs(&blanks);
+    let at = runs.iter().position(|run| run.0 <= row && row <= run.1)?;
+    let (first, last) = if around_it {
+        around(&runs, at, count, &|&blank| blank)
+    } else {
+        (at, (at + count - 1).min(runs.len() - 1))
+    };
+    Option::Some((Selection::new((runs[first].0, 0), (runs[last].1, 0)), MotionKind::Linewise))
+}
+
+/// `i"`, `a"` and the same for `'` and `` ` ``: the quoted text around the
+/// cursor, quotes escaped with a backslash not counting. On a quote, the
+/// quotes pair up from the start of the line. `a"` also takes the white
+/// space after the closing quote, or else the one before the opening one.
+/// As in Vim, quotes stay within their line and a count of 2 makes `i"`
+/// take the quotes without the white space.
+pub fn quote(buffer: &Buffer, (row, col): (usize, usize), quote: char, around_it: bool, count: usize)
+             -> Option<(Selection, MotionKind)> {
+    let line: Vec<char> = buffer.line_text(row)?.chars().collect();
+    let quotes: Vec<usize> = (0..line.len())
+        .filter(|&index| line[index] == quote && (index == 0 || line[index - 1] != '\\'))
+        .collect();
+    let (open, close) = match quotes.iter().position(|&index| index == col) {
+        Option::Some(at) if at % 2 == 0 => (*quotes.get(at)?, *quotes.get(at + 1)?),
+        Option::Some(at) => (quotes[at - 1], quotes[at]),
+        Option::None => match (quotes.iter().rev().find(|&&index| index < col), quotes.iter().position(|&index| index > col)) {
+            (Option::Some(&open), Option::Some(after)) => (open, quotes[after]),
+            (Option::None, Option::Some(after)) => (quotes[after], *quotes.get(after + 1)?),
+            _ => return Option::None,
+        },
+    };
+    if !around_it && count > 1 {
+        return Option::Some((Selection::new((row, open), (row, close)), MotionKind::Inclusive));
+    }
+    if !around_it {
+        return Option::Some((Selection::new((row, open + 1), (row, close)), MotionKind::Exclusive));
+    }
+    let is_blank = |index: &usize| line[*index] == ' ' || line[*index] == '\t';
+    let after = (close + 1..line.len()).take_while(is_blank).count();
+    let before = if after == 0 { (0..open).rev().take_while(is_blank).count() } else { 0 };
+    Option::Some((Selection::new((row, open - before), (row, close + after)), MotionKind::Inclusive))
+}
+
+/// `i(`, `a(` and the same for `[`, `{` and `<`: the text between the
+/// `count`th pair of `open` and `close` around the cursor, over any number
+/// of lines. When the brackets are alone at the end and start of their
+/// lines, `i(` takes the whole lines between them. `skip_literals` is as
+/// for `motion::find_unmatched`.
+pub fn bracket(buffer: &Buffer, pos: (usize, usize), open: char, close: char, around_it: bool, count: usize,
+               skip_literals: bool) -> Option<(Selection, MotionKind)> {
+    let find = |pos, forward| motion::find_unmatched(buffer, pos, open, close, forward, skip_literals);
+    let ch = buffer.get_line_at(pos.0).and_then(|line| line.get_char_at(pos.1)).copied();
+    let (mut start, mut end) = match ch {
+        Option::Some(ch) if ch == open => (pos, find(pos, true)?),
+        Option::Some(ch) if ch == close => (find(pos, false)?, pos),
+        _ => {
+            let start = find(pos, false)?;
+            (start, find(start, true)?)
+        }
+    };
+    for _ in 1..count {
+        start = find(start, false)?;
+        end = find(start, true)?;
+    }
+    if around_it {
+        return Option::Some((Selection::new(start, end), MotionKind::Inclusive));
+    }
+    let open_ends_line = start.1 + 1 >= buffer.line_len(start.0);
+    let close_starts_line = end.1 <= motion::first_non_blank(buffer, end.0);
+    if open_ends_line && close_starts_line && end.0 > start.0 + 1 {
+        return Option::Some((Selection::new((start.0 + 1, 0), (end.0 - 1, 0)), MotionKind::Linewise));
+    }
+    let inner_start = if open_ends_line && end.0 > start.0 { (start.0 + 1, 0) } else { (start.0, start.1 + 1) };
+    let inner_end = if close_starts_line && end.0 > inner_start.0 {
+        (end.0 - 1, buffer.line_len(end.0 - 1))
+    } else {
+        end
+    };
+    Option::Some((Selection::new(inner_start, inner_end), MotionKind::Exclusive))
+}
+
+/// A tag as found by `tags`: where its `<` and `>` are, its name and
+/// whether it is a closing tag.
+struct Tag {
+    start: (usize, usize),
+    end: (usize, usize),
+    name: String,
+    closing: bool,
+}
+
+/// The XML tags of the buffer in order, leaving out self-closing ones and
+/// `<!...>` and `<?...>`. A tag may break over lines.
+fn tags(buffer: &Buffer) -> Vec<Tag> {
+    let mut chars = Vec::new();
+    for row in 0..buffer.count() {
+        let line = buffer.line_text(row).unwrap_or_default();
+        chars.extend(line.chars().enumerate().map(|(col, ch)| ((row, col), ch)));
+        chars.push(((row, line.chars().count()), '\n'));
+    }
+    let mut tags = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        if chars[index].1 != '<' {
+            index += 1;
+            continue;
+        }
+        let closing = chars.get(index + 1).is_some_and(|&(_, ch)| ch == '/');
+        let name_start = if closing { index + 2 } else { index + 1 };
+        let name: String = chars[name_start.min(chars.len())..].iter()
+            .map(|&(_, ch)| ch)
+            .take_while(|&ch| ch.is_alphanumeric() || "-_:.".contains(ch))
+            .collect();
+        let end = chars[index + 1..].iter().position(|&(_, ch)| ch == '<' || ch == '>').map(|end| index + 1 + end);
+        match end {
+            Option::Some(end) if chars[end].1 == '>' && !name.is_empty() => {
+                if chars[end - 1].1 != '/' {
+                    tags.push(Tag { start: chars[index].0, end: chars[end].0, name, closing });
+                }
+                index = end + 1;
+            }
+            _ => index += 1,
+        }
+    }
+    tags
+}
+
+/// `it` and `at`: the text between the `count`th pair of matching tags
+/// around the cursor, `at` with the tags themselves.
+pub fn tag(buffer: &Buffer, pos: (usize, usize), around_it: bool, count: usize) -> Option<(Selection, MotionKind)> {
+    let mut open: Vec<Tag> = Vec::new();
+    // pairs of opening and closing tags around the cursor, inner ones
+    // first since they close first
+    let mut pairs = Vec::new();
+    for tag in tags(buffer) {
+        if !tag.closing {
+            open.push(tag);
+            continue;
+        }
+        if let Some(at) = open.iter().rposition(|opening| opening.name == tag.name) {
+            // tags opened inside and never closed do not pair up
+            let opening = open.remove(at);
+            open.truncate(at);
+            if opening.start <= pos && pos <= tag.end {
+                pairs.push((opening, tag));
+            }
+        }
+    }
+    let (opening, closing) = pairs.get(count - 1)?;
+    if around_it {
+        return Option::Some((Selection::new(opening.start, closing.end), MotionKind::Inclusive));
+    }
+    let start = (opening.end.0, opening.end.1 + 1);
+    Option::Some((Selection::new(start, closing.start), MotionKind::Exclusive))
+}
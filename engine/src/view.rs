@@ -0,0 +1,39 @@
+use terminal::{Cell, Terminal};
+
+/// The last frame handed to a `Terminal`, kept so that the next frame only
+/// redraws the lines that changed.
+#[derive(Default)]
+pub struct View {
+    size: (usize, usize),
+    lines: Vec<Vec<Cell>>,
+}
+
+impl View {
+    pub fn new() -> Self {
+        View::default()
+    }
+
+    /// Draws `lines` with the cursor at `cursor`. Lines that are the same
+    /// as in the previous frame are left alone, unless the screen changed
+    /// size in between.
+    pub fn update(&mut self, lines: Vec<Vec<Cell>>, cursor: (usize, usize), terminal: &mut dyn Terminal) {
+        let size = terminal.size();
+        if size != self.size {
+            self.size = size;
+            self.lines.clear();
+        }
+        for (row, line) in lines.iter().enumerate() {
+            if self.lines.get(row) != Option::Some(line) {
+                terminal.draw_cells(row, line);
+            }
+        }
+        self.lines = lines;
+        terminal.set_cursor(cursor.0, cursor.1);
+        terminal.flush();
+    }
+
+    /// Forgets the previous frame so that the next one is drawn in full.
+    pub fn invalidate(&mut self) {
+        self.lines.clear();
+    }
+}
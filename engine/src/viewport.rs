@@ -0,0 +1,79 @@
+/// The part of the buffer that is on screen: `height` lines starting at
+/// line `top`, and `width` columns starting at column `left`.
+#[derive(Debug, Default)]
+pub struct Viewport {
+    pub top: usize,
+    pub left: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Viewport {
+    pub fn new() -> Self {
+        Viewport::default()
+    }
+
+    pub fn resize(&mut self, height: usize, width: usize) {
+        self.height = height;
+        self.width = width;
+    }
+
+    /// The last buffer line on screen.
+    pub fn bottom(&self) -> usize {
+        self.top + self.height.max(1) - 1
+    }
+
+    /// Scrolls as little as possible to bring `(row, col)` on screen.
+    pub fn follow(&mut self, row: usize, col: usize) {
+        if row < self.top {
+            self.top = row;
+        } else if row > self.bottom() {
+            self.top = row + 1 - self.height.max(1);
+        }
+        if col < self.left {
+            self.left = col;
+        } else if col >= self.left + self.width.max(1) {
+            self.left = col + 1 - self.width.max(1);
+        }
+    }
+
+    /// Moves the view `count` lines further into a buffer of `line_count`
+    /// lines, stopping once its last line is at the top.
+    pub fn scroll_down(&mut self, count: usize, line_count: usize) {
+        let last_top = line_count.saturating_sub(1);
+        self.top = (self.top + count).min(last_top.max(self.top));
+    }
+
+    pub fn scroll_up(&mut self, count: usize) {
+        self.top = self.top.saturating_sub(count);
+    }
+
+    /// `zt`: puts `row` at the top of the screen.
+    pub fn show_at_top(&mut self, row: usize) {
+        self.top = row;
+    }
+
+    /// `zz`: puts `row` in the middle of the screen.
+    pub fn show_at_center(&mut self, row: usize) {
+        self.top = row.saturating_sub(self.height / 2);
+    }
+
+    /// `zb`: puts `row` at the bottom of the screen.
+    pub fn show_at_bottom(&mut self, row: usize) {
+        self.top = (row + 1).saturating_sub(self.height.max(1));
+    }
+
+    /// The line closest to `row` that is on screen.
+    pub fn clamp_row(&self, row: usize) -> usize {
+        row.max(self.top).min(self.bottom())
+    }
+
+    /// Where buffer position `(row, col)` is on screen, if it is visible.
+    pub fn to_screen(&self, row: usize, col: usize) -> Option<(usize, usize)> {
+        if row >= self.top && row <= self.bottom() && col >= self.left && col < self.left + self.width {
+            Option::Some((row - self.top, col - self.left))
+        } else {
+            Option::None
+        }
+    }
+}
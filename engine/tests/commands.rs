@@ -0,0 +1,67 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::{scratch_dir, Editor};
+use rustudio_engine::Engine;
+
+#[test]
+fn q_quits_an_untouched_new_buffer() {
+    let mut editor = Editor::with_engine(Engine::new(), (5, 40));
+    assert!(editor.quits_on(":q\r"));
+}
+
+#[test]
+fn q_quits_an_untouched_file_that_does_not_exist_yet() {
+    let path = scratch_dir("q-new-file").join("new.txt");
+    let mut editor = Editor::with_engine(Engine::open(&path).unwrap(), (5, 40));
+    assert!(editor.quits_on(":q\r"));
+    assert!(!path.exists());
+}
+
+#[test]
+fn q_refuses_to_lose_changes() {
+    let mut editor = Editor::with_engine(Engine::new(), (5, 40));
+    editor.type_chunks(&["ihi\x1b"]);
+    assert!(!editor.quits_on(":q\r"));
+    assert_eq!(editor.engine.message(), Option::Some("No write since last change (add ! to override)"));
+    assert!(editor.quits_on(":q!\r"));
+}
+
+#[test]
+fn x_quits_an_untouched_buffer_without_writing() {
+    let mut editor = Editor::with_engine(Engine::new(), (5, 40));
+    assert!(editor.quits_on(":x\r"));
+
+    let path = scratch_dir("x-new-file").join("new.txt");
+    let mut editor = Editor::with_engine(Engine::open(&path).unwrap(), (5, 40));
+    assert!(editor.quits_on(":x\r"));
+    assert!(!path.exists());
+}
+
+#[test]
+fn x_writes_a_changed_buffer_before_quitting() {
+    let path = scratch_dir("x-changed").join("new.txt");
+    let mut editor = Editor::with_engine(Engine::open(&path).unwrap(), (5, 40));
+    editor.type_chunks(&["ihi\x1b"]);
+    assert!(editor.quits_on(":x\r"));
+    assert_eq!(::std::fs::read_to_string(&path).unwrap(), "hi\n");
+}
+
+#[test]
+fn writes_of_a_read_only_buffer_need_a_bang() {
+    let dir = scratch_dir("read-only");
+    let path = dir.join("file.txt");
+    let mut editor = Editor::with_engine(Engine::open(&path).unwrap(), (5, 60));
+    editor.engine.set_read_only(true);
+    editor.type_chunks(&["ihi\x1b", ":w\r"]);
+    assert_eq!(editor.engine.message(), Option::Some("'readonly' option is set (add ! to override)"));
+    editor.type_chunks(&[&format!(":saveas {}\r", dir.join("other.txt").display())]);
+    assert_eq!(editor.engine.message(), Option::Some("'readonly' option is set (add ! to override)"));
+    assert!(!path.exists() && !dir.join("other.txt").exists());
+
+    editor.type_chunks(&[":w!\r"]);
+    assert_eq!(::std::fs::read_to_string(&path).unwrap(), "hi\n");
+}
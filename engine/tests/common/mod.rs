@@ -0,0 +1,88 @@
+//! The editor the integration tests drive: an `Engine` showing its buffer
+//! on a `HeadlessTerminal`.
+
+// each test file uses its own share of the helpers
+#![allow(dead_code)]
+
+use rustudio_buffer::Buffer;
+use rustudio_engine::terminal::{Event, HeadlessTerminal};
+use rustudio_engine::{config, default_configuration, Engine};
+use std::fs;
+use std::path::PathBuf;
+
+pub struct Editor {
+    pub engine: Engine,
+    pub terminal: HeadlessTerminal,
+}
+
+impl Editor {
+    /// An editor on `text` with a `height` by `width` screen and, when
+    /// given, the TOML `options` set on top of the default ones.
+    pub fn new(text: &str, options: Option<&str>, (height, width): (usize, usize)) -> Self {
+        let mut engine = Engine::with_buffer(Buffer::from_reader(text.as_bytes()).unwrap());
+        if let Option::Some(options) = options {
+            let mut conf = default_configuration();
+            config::apply(&mut conf, &format!("[options]\n{}", options)).unwrap();
+            engine.set_configuration(conf);
+        }
+        Editor::with_engine(engine, (height, width))
+    }
+
+    pub fn with_engine(engine: Engine, (height, width): (usize, usize)) -> Self {
+        Editor { engine, terminal: HeadlessTerminal::new(height, width) }
+    }
+
+    /// Draws the screen and handles whatever events are queued.
+    pub fn run(&mut self) {
+        self.engine.run(&mut self.terminal);
+    }
+
+    /// Types `keys` with no pause in between, as typed quickly.
+    pub fn type_keys(&mut self, keys: &str) {
+        self.terminal.push_keys(keys);
+        self.run();
+    }
+
+    /// Types each of `chunks` with a pause after it, so that an Esc ending
+    /// one is not taken for the start of an Alt key.
+    pub fn type_chunks(&mut self, chunks: &[&str]) {
+        for chunk in chunks {
+            self.terminal.push_keys(chunk);
+            self.terminal.push_event(Event::Idle);
+        }
+        self.run();
+    }
+
+    /// Lets `timeout_len` run out on whatever keys are waiting.
+    pub fn pause(&mut self) {
+        self.terminal.push_event(Event::Idle);
+        self.run();
+    }
+
+    /// Types `keys` until the editor quits, returning whether it did.
+    pub fn quits_on(&mut self, keys: &str) -> bool {
+        rustudio_keys::keys(keys).into_iter().any(|key| !self.engine.input(key))
+    }
+
+    /// The screen lines above the first `~` filler, which are the text
+    /// when it fits on the screen.
+    pub fn text(&self) -> Vec<String> {
+        self.terminal.screen().into_iter().take_while(|line| line != "~").collect()
+    }
+}
+
+/// The text of `text` after typing `keys` on it, on a screen big enough
+/// to show it all.
+pub fn text_after(text: &str, keys: &str) -> Vec<String> {
+    let mut editor = Editor::new(text, Option::None, (12, 40));
+    editor.type_keys(keys);
+    editor.text()
+}
+
+/// A fresh directory for `test` to write its files in.
+pub fn scratch_dir(test: &str) -> PathBuf {
+    let dir = ::std::env::temp_dir().join(format!("rustudio-engine-{}-{}", ::std::process::id(), test));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
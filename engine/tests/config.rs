@@ -0,0 +1,89 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::{scratch_dir, Editor};
+use rustudio_buffer::Buffer;
+use rustudio_engine::{config, default_configuration, Configuration, Engine};
+use rustudio_keys::notation;
+use std::fs;
+use std::path::PathBuf;
+
+/// The line and message `config::apply` gives for `text`.
+fn error(text: &str) -> (usize, String) {
+    config::apply(&mut default_configuration(), text).unwrap_err()
+}
+
+/// The error `config::load_from` gives for `paths`.
+fn load_error(paths: &[PathBuf]) -> config::ConfigError {
+    match config::load_from(paths) {
+        Ok(_) => panic!("{:?} loaded", paths),
+        Err(err) => err,
+    }
+}
+
+fn bindings(conf: &mut Configuration, mode: &str, action: &str) -> Vec<String> {
+    conf.bindings_mut(mode, action).unwrap().iter().map(|keys| notation::to_string(keys)).collect()
+}
+
+#[test]
+fn errors_come_with_their_line() {
+    assert_eq!(error("[options]\ntab_width = 4\nexpand_tab = 1\n"),
+               (3, "expand_tab must be true or false".to_string()));
+    assert_eq!(error("# comment\n\n[options]\nfoo = true\n"), (4, "unknown option 'foo'".to_string()));
+    assert_eq!(error("[options]\nleader = comma\n"),
+               (2, "'comma' is not a valid value, strings need quotes".to_string()));
+    assert_eq!(error("[options]\nleader = \",\"\n[options]\n"), (3, "table [options] defined twice".to_string()));
+    assert_eq!(error("[keys.normal]\nscroll_line_down = [\n  \"<C-e>\",\n  3,\n]\n"),
+               (2, "the bindings of scroll_line_down must be strings".to_string()));
+    assert_eq!(error("[keys.normal]\nfly = \"x\"\n"), (2, "unknown normal mode action 'fly'".to_string()));
+    assert_eq!(error("[keys.normal]\nscroll_line_down = \"<C-Nope>\"\n"), (2, "Unknown key: <C-Nope>".to_string()));
+    assert_eq!(error("[options]\nmatchpairs = \"(:)\" x\n"), (2, "unexpected 'x' after the value".to_string()));
+}
+
+#[test]
+fn the_leader_applies_to_bindings_above_it() {
+    let mut conf = default_configuration();
+    config::apply(&mut conf, "[keys.normal]\nscroll_line_down = \"<leader>j\"\n[options]\nleader = \",\"\n").unwrap();
+    assert_eq!(bindings(&mut conf, "normal", "scroll_line_down"), [",j"]);
+}
+
+#[test]
+fn the_project_file_wins_over_the_user_file() {
+    let dir = scratch_dir("config-precedence");
+    let user = dir.join("user.toml");
+    let project = dir.join("project.toml");
+    fs::write(&user, "[options]\ntab_width = 2\nline_numbers = true\n\n[keys.insert]\ngo_to_normal_mode = \"jk\"\n").unwrap();
+    fs::write(&project, "[options]\ntab_width = 8\n").unwrap();
+
+    let mut conf = config::load_from(&[user.clone(), project.clone(), dir.join("missing.toml")]).unwrap();
+    assert_eq!(conf.options.tab_width, 8);
+    assert!(conf.options.line_numbers);
+    assert_eq!(bindings(&mut conf, "insert", "go_to_normal_mode"), ["jk"]);
+
+    let mut engine = Engine::with_buffer(Buffer::from_reader(&b"a\n"[..]).unwrap());
+    engine.set_configuration(conf);
+    let mut editor = Editor::with_engine(engine, (5, 20));
+    editor.type_keys("ixjkx");
+    assert_eq!(editor.text(), ["  1 x"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn file_errors_name_the_file_and_line() {
+    let dir = scratch_dir("config-error");
+    let user = dir.join("user.toml");
+    let project = dir.join("project.toml");
+    fs::write(&user, "[options]\ntab_width = 2\n").unwrap();
+    fs::write(&project, "[options]\n\ntab_width = 0\n").unwrap();
+
+    let err = load_error(&[user, project.clone()]);
+    assert_eq!((&err.path, err.line), (&project, 3));
+    assert_eq!(err.to_string(), format!("{}:3: tab_width must be a number from 1 to 32", project.display()));
+
+    let err = load_error(&[PathBuf::from(&dir)]);
+    assert_eq!(err.line, 0);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,49 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::{text_after, Editor};
+
+const LINES: &str = "one\ntwo\nthree\nfour\nfive\nsix\n";
+
+#[test]
+fn counts_repeat_motions() {
+    let mut editor = Editor::new(LINES, Option::None, (8, 40));
+    editor.type_keys("3j");
+    assert_eq!(editor.terminal.cursor(), (3, 0));
+    editor.type_keys("2k2l");
+    assert_eq!(editor.terminal.cursor(), (1, 2));
+    editor.type_keys("99j");
+    assert_eq!(editor.terminal.cursor(), (5, 2));
+}
+
+#[test]
+fn counts_repeat_edits() {
+    assert_eq!(text_after("abcdefgh\n", "3x"), ["defgh"]);
+    assert_eq!(text_after("abc\n", "10x"), [""]);
+    assert_eq!(text_after(LINES, "j3dd"), ["one", "five", "six"]);
+    assert_eq!(text_after("a b c d e f\n", "3dw"), ["d e f"]);
+}
+
+#[test]
+fn counts_before_the_operator_and_the_motion_multiply() {
+    assert_eq!(text_after("a b c d e f g h\n", "2d3w"), ["g h"]);
+    assert_eq!(text_after(LINES, "2d2j"), ["six"]);
+}
+
+#[test]
+fn a_zero_after_a_count_is_part_of_it() {
+    let text = "a b c d e f g h i j k l\n";
+    assert_eq!(text_after(text, "10x"), ["f g h i j k l"]);
+    // without a count `0` goes to the start of the line
+    assert_eq!(text_after(text, "$0x"), [" b c d e f g h i j k l"]);
+}
+
+#[test]
+fn counts_repeat_inserts() {
+    let mut editor = Editor::new("x\n", Option::None, (5, 40));
+    editor.type_chunks(&["3ihi \x1b"]);
+    assert_eq!(editor.text(), ["hi hi hi x"]);
+}
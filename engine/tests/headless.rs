@@ -0,0 +1,113 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::Editor;
+use rustudio_engine::terminal::Style;
+use rustudio_engine::Engine;
+
+#[test]
+fn renders_text_filler_and_status_line() {
+    let mut editor = Editor::new("one\ntwo\n", Option::None, (5, 10));
+    editor.run();
+    assert_eq!(editor.terminal.screen(), vec!["one", "two", "~", "~", ""]);
+    assert_eq!(editor.terminal.cells(2)[0].style, Style::NonText);
+    assert_eq!(editor.terminal.cursor(), (0, 0));
+}
+
+#[test]
+fn renders_typed_text_and_the_cursor() {
+    let mut editor = Editor::with_engine(Engine::new(), (4, 10));
+    editor.type_chunks(&["ione\rtwo\x1b", "k"]);
+    assert_eq!(editor.terminal.screen(), vec!["one", "two", "~", ""]);
+    assert_eq!(editor.terminal.cursor().0, 0);
+}
+
+#[test]
+fn shows_the_command_line_on_the_status_line() {
+    let mut editor = Editor::new("one\n", Option::None, (3, 10));
+    editor.type_chunks(&[":wr"]);
+    assert_eq!(editor.terminal.line(2), ":wr");
+    assert_eq!(editor.terminal.cursor(), (2, 3));
+}
+
+#[test]
+fn cuts_lines_at_the_screen_width() {
+    let mut editor = Editor::new("abcdefgh\n", Option::None, (3, 4));
+    editor.run();
+    assert!(editor.terminal.screen().iter().all(|line| line.chars().count() <= 4));
+    assert!(editor.terminal.line(0).starts_with("abc"));
+}
+
+#[test]
+fn scrolls_to_keep_the_cursor_on_screen() {
+    let mut editor = Editor::new("1\n2\n3\n4\n5\n", Option::None, (3, 10));
+    editor.type_chunks(&["G"]);
+    assert_eq!(editor.terminal.line(1), "5");
+    assert_eq!(editor.terminal.cursor(), (1, 0));
+}
+
+#[test]
+fn resize_draws_every_line_again() {
+    let mut editor = Editor::new("one\ntwo\nthree\n", Option::None, (4, 10));
+    editor.run();
+    editor.terminal.take_redrawn();
+
+    editor.terminal.resize(6, 10);
+    editor.run();
+    let mut redrawn = editor.terminal.take_redrawn();
+    redrawn.sort_unstable();
+    redrawn.dedup();
+    assert_eq!(redrawn, vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(editor.terminal.screen(), vec!["one", "two", "three", "~", "~", ""]);
+}
+
+#[test]
+fn resize_fits_the_text_to_the_smaller_screen() {
+    let mut editor = Editor::new("one\ntwo\nthree\n", Option::None, (6, 10));
+    editor.type_chunks(&["G"]);
+
+    editor.terminal.resize(3, 3);
+    editor.run();
+    assert_eq!(editor.terminal.screen().len(), 3);
+    assert!(editor.terminal.screen().iter().all(|line| line.chars().count() <= 3));
+    let (row, col) = editor.terminal.cursor();
+    assert!(row < 2 && col < 3);
+    assert!(editor.terminal.line(row).starts_with("thr"));
+}
+
+#[test]
+fn nothing_is_redrawn_when_nothing_changed() {
+    let mut editor = Editor::new("one\ntwo\n", Option::None, (4, 10));
+    editor.run();
+    editor.terminal.take_redrawn();
+
+    editor.pause();
+    assert_eq!(editor.terminal.take_redrawn(), Vec::<usize>::new());
+}
+
+#[test]
+fn only_the_changed_line_is_redrawn() {
+    let mut editor = Editor::new("one\ntwo\nthree\n", Option::None, (5, 10));
+    editor.type_chunks(&["j"]);
+    editor.terminal.take_redrawn();
+
+    editor.type_chunks(&["x"]);
+    assert_eq!(editor.terminal.line(1), "wo");
+    assert_eq!(editor.terminal.take_redrawn(), vec![1]);
+}
+
+#[test]
+fn deleting_a_line_redraws_the_lines_below_it() {
+    let mut editor = Editor::new("one\ntwo\nthree\n", Option::None, (5, 10));
+    editor.run();
+    editor.terminal.take_redrawn();
+
+    editor.type_chunks(&["dd"]);
+    assert_eq!(editor.terminal.screen()[..3], ["two", "three", "~"]);
+    let redrawn = editor.terminal.take_redrawn();
+    assert!(!redrawn.contains(&3));
+    assert!([0, 1, 2].iter().all(|row| redrawn.contains(row)));
+}
@@ -0,0 +1,119 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::Editor;
+use rustudio_engine::UPDATE_TIME_MS;
+use std::time::Duration;
+
+fn editor_with(text: &str) -> Editor {
+    Editor::new(text, Option::None, (5, 40))
+}
+
+#[test]
+fn keys_that_may_grow_into_a_mapping_wait_for_timeout_len() {
+    let mut editor = editor_with("one\ntwo\nthree\n");
+    editor.type_keys(":nmap jj dd\r");
+    assert_eq!(editor.engine.input_timeout(), Duration::from_millis(UPDATE_TIME_MS));
+
+    editor.type_keys("j");
+    assert_eq!(editor.engine.input_timeout(), Duration::from_millis(1000));
+    assert_eq!(editor.terminal.cursor(), (0, 0));
+
+    editor.pause();
+    assert_eq!(editor.terminal.cursor(), (1, 0));
+    assert_eq!(editor.engine.input_timeout(), Duration::from_millis(UPDATE_TIME_MS));
+}
+
+#[test]
+fn timeout_len_comes_from_the_options() {
+    let mut editor = Editor::new("one\ntwo\n", Option::Some("timeoutlen = 250"), (5, 40));
+    editor.type_keys(":nmap jj dd\rj");
+    assert_eq!(editor.engine.input_timeout(), Duration::from_millis(250));
+}
+
+#[test]
+fn the_whole_mapping_runs_without_waiting() {
+    let mut editor = editor_with("one\ntwo\nthree\n");
+    editor.type_keys(":nmap jj dd\rjj");
+    assert_eq!(editor.terminal.screen()[..3], ["two", "three", "~"]);
+}
+
+#[test]
+fn keys_past_the_mapping_are_handled_on_their_own() {
+    let mut editor = editor_with("one\ntwo\nthree\n");
+    // `jx` is not mapped, so `j` moves down and `x` deletes there
+    editor.type_keys(":nmap jj dd\rjx");
+    assert_eq!(editor.terminal.screen()[..3], ["one", "wo", "three"]);
+}
+
+#[test]
+fn an_unbound_prefix_is_dropped_on_timeout() {
+    let mut editor = editor_with("one\ntwo\n");
+    editor.type_keys(":nmap qqq dd\rqq");
+    editor.pause();
+    assert_eq!(editor.terminal.screen()[..2], ["one", "two"]);
+    assert_eq!(editor.terminal.line(4), "Unknown key sequence: qq");
+}
+
+#[test]
+fn map_expands_into_other_mappings_and_noremap_does_not() {
+    let mut editor = editor_with("one\ntwo\nthree\n");
+    editor.type_keys(":nmap x dd\r:nmap Q x\rQ");
+    assert_eq!(editor.text(), ["two", "three"]);
+
+    editor.type_keys(":nnoremap Q x\rQ");
+    assert_eq!(editor.text(), ["wo", "three"]);
+}
+
+#[test]
+fn a_mapping_that_starts_with_its_own_keys_does_not_map_them_again() {
+    let mut editor = editor_with("one\ntwo\nthree\n");
+    editor.type_keys(":nmap j jx\rj");
+    assert_eq!(editor.text(), ["one", "wo", "three"]);
+}
+
+#[test]
+fn map_bang_is_for_insert_mode() {
+    let mut editor = editor_with("a\n");
+    editor.type_keys(":map! jk <Esc>\rixjkx");
+    assert_eq!(editor.text(), ["x"]);
+}
+
+#[test]
+fn nop_maps_keys_to_nothing() {
+    let mut editor = editor_with("one\n");
+    editor.type_keys(":nmap x <Nop>\rx");
+    assert_eq!(editor.text(), ["one"]);
+}
+
+#[test]
+fn unmap_removes_a_mapping() {
+    let mut editor = editor_with("one\ntwo\n");
+    editor.type_keys(":nmap x dd\r:nunmap x\rx");
+    assert_eq!(editor.text(), ["ne", "two"]);
+
+    editor.type_keys(":nunmap x\r");
+    assert_eq!(editor.engine.message(), Option::Some("No such mapping"));
+}
+
+#[test]
+fn endless_mappings_stop_at_the_recursion_limit() {
+    let mut editor = editor_with("one\ntwo\n");
+    editor.type_keys(":nmap a b\r:nmap b a\ra");
+    assert_eq!(editor.engine.message(), Option::Some("Recursive mapping"));
+    assert_eq!(editor.text(), ["one", "two"]);
+
+    // the keys after it are handled as usual
+    editor.type_keys("x");
+    assert_eq!(editor.text(), ["ne", "two"]);
+}
+
+#[test]
+fn map_without_rhs_lists_the_mappings() {
+    let mut editor = Editor::new("one\n", Option::None, (8, 40));
+    editor.type_keys(":nmap Q dd\r:nnoremap <leader>w x\r:nmap\r");
+    assert_eq!(editor.terminal.screen()[6..], ["n  Q             dd", "n  <Bslash>w   * x"]);
+}
@@ -0,0 +1,71 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::Editor;
+use rustudio_engine::terminal::Style;
+
+/// The screen positions drawn as the bracket matching the cursor's.
+fn highlighted(editor: &Editor, rows: usize) -> Vec<(usize, usize)> {
+    (0..rows)
+        .flat_map(|row| {
+            editor.terminal.cells(row).iter().enumerate()
+                .filter(|(_, cell)| cell.style == Style::MatchParen)
+                .map(move |(col, _)| (row, col))
+        })
+        .collect()
+}
+
+#[test]
+fn brackets_in_literals_count_without_skip_literals() {
+    let mut editor = Editor::new("f(\")\", x)\n", Option::None, (4, 40));
+    editor.type_keys("%");
+    assert_eq!(editor.terminal.cursor(), (0, 3));
+}
+
+#[test]
+fn skip_literals_skips_brackets_in_strings_and_chars() {
+    let mut editor = Editor::new("f(\")\", ')', x)\n", Option::Some("skip_literals = true"), (4, 40));
+    editor.type_keys("%");
+    assert_eq!(editor.terminal.cursor(), (0, 13));
+    editor.type_keys("%");
+    assert_eq!(editor.terminal.cursor(), (0, 1));
+}
+
+#[test]
+fn skip_literals_applies_to_text_objects() {
+    let mut editor = Editor::new("f(\")\", x)\n", Option::Some("skip_literals = true"), (4, 40));
+    editor.type_keys("fxdi(");
+    assert_eq!(editor.terminal.line(0), "f()");
+}
+
+#[test]
+fn the_matching_bracket_on_screen_is_highlighted() {
+    let mut editor = Editor::new("f(\n  x\n)\n", Option::None, (4, 40));
+    editor.run();
+    assert_eq!(highlighted(&editor, 3), vec![]);
+    editor.type_keys("l");
+    assert_eq!(highlighted(&editor, 3), vec![(2, 0)]);
+}
+
+#[test]
+fn a_matching_bracket_off_screen_is_not_looked_for() {
+    let text = format!("f(\n{})\n", "x\n".repeat(10));
+    let mut editor = Editor::new(&text, Option::None, (4, 40));
+    editor.type_keys("l");
+    assert_eq!(highlighted(&editor, 3), vec![]);
+    editor.type_keys("%");
+    assert_eq!(editor.terminal.cursor().1, 0);
+    assert_eq!(highlighted(&editor, 3), vec![]);
+}
+
+#[test]
+fn quotes_in_prose_do_not_hide_brackets_by_default() {
+    let mut editor = Editor::new("it's (x) and 'y' (z)\n", Option::None, (4, 40));
+    editor.type_keys("f(%");
+    assert_eq!(editor.terminal.cursor(), (0, 7));
+    editor.type_keys("f(%");
+    assert_eq!(editor.terminal.cursor(), (0, 19));
+}
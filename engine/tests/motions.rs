@@ -0,0 +1,114 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::{text_after, Editor};
+
+/// Where the cursor is after each time `motion` is typed on `text`,
+/// having typed `start` first.
+fn stops(text: &str, start: &str, motion: &str, times: usize) -> Vec<(usize, usize)> {
+    let mut editor = Editor::new(text, Option::None, (12, 40));
+    editor.type_keys(start);
+    (0..times).map(|_| {
+        editor.type_keys(motion);
+        editor.terminal.cursor()
+    }).collect()
+}
+
+const CODE: &str = "foo.bar(baz) qux\n\n  last-one x\n";
+
+#[test]
+fn word_motions() {
+    assert_eq!(stops(CODE, "", "w", 7), [(0, 3), (0, 4), (0, 7), (0, 8), (0, 11), (0, 13), (1, 0)]);
+    assert_eq!(stops(CODE, "G$", "b", 4), [(2, 7), (2, 6), (2, 2), (1, 0)]);
+    assert_eq!(stops(CODE, "", "e", 4), [(0, 2), (0, 3), (0, 6), (0, 7)]);
+    assert_eq!(stops(CODE, "G$", "ge", 4), [(2, 9), (2, 6), (2, 5), (1, 0)]);
+}
+
+#[test]
+fn big_word_motions() {
+    assert_eq!(stops(CODE, "", "W", 4), [(0, 13), (1, 0), (2, 2), (2, 11)]);
+    assert_eq!(stops(CODE, "G$", "B", 3), [(2, 2), (1, 0), (0, 13)]);
+    assert_eq!(stops(CODE, "", "E", 3), [(0, 11), (0, 15), (2, 9)]);
+    // an empty line counts as a word
+    assert_eq!(stops(CODE, "G$", "gE", 3), [(2, 9), (1, 0), (0, 15)]);
+}
+
+#[test]
+fn sentence_and_paragraph_motions() {
+    let text = "One two. Three four!  Five\nsix.\n\nSeven.\n";
+    assert_eq!(stops(text, "", ")", 4), [(0, 9), (0, 22), (2, 0), (3, 0)]);
+    assert_eq!(stops(text, "G", "(", 3), [(2, 0), (0, 22), (0, 9)]);
+
+    let text = "a\nb\n\nc\n\n\nd\n";
+    assert_eq!(stops(text, "", "}", 3), [(2, 0), (4, 0), (6, 0)]);
+    assert_eq!(stops(text, "G", "{", 3), [(5, 0), (2, 0), (0, 0)]);
+}
+
+#[test]
+fn line_motions() {
+    let text = "  one two  \n";
+    assert_eq!(stops(text, "", "$", 1), [(0, 10)]);
+    assert_eq!(stops(text, "$", "^", 1), [(0, 2)]);
+    assert_eq!(stops(text, "$", "0", 1), [(0, 0)]);
+    assert_eq!(stops(text, "", "g_", 1), [(0, 8)]);
+}
+
+#[test]
+fn file_and_screen_motions() {
+    let text: String = (1..=30).map(|n| format!("  {}\n", n)).collect();
+    assert_eq!(stops(&text, "", "G", 1), [(10, 2)]);
+    assert_eq!(stops(&text, "G", "gg", 1), [(0, 2)]);
+    assert_eq!(stops(&text, "", "5G", 1), [(4, 2)]);
+    assert_eq!(stops(&text, "", "L", 1), [(10, 2)]);
+    assert_eq!(stops(&text, "", "M", 1), [(5, 2)]);
+    assert_eq!(stops(&text, "G", "H", 1), [(0, 2)]);
+}
+
+#[test]
+fn motions_are_operator_targets() {
+    assert_eq!(text_after("foo.bar baz\n", "dE"), [" baz"]);
+    assert_eq!(text_after("foo bar baz\n", "wdb"), ["bar baz"]);
+    // `e` takes the char it stops on
+    assert_eq!(text_after("foo bar baz\n", "de"), [" bar baz"]);
+    assert_eq!(text_after("a\nb\n\nc\n", "d}"), ["", "c"]);
+    assert_eq!(text_after("  one two\n", "$d^"), ["  o"]);
+    assert_eq!(text_after("one\ntwo\nthree\n", "jdgg"), ["three"]);
+}
+
+#[test]
+fn find_and_till_a_char() {
+    let text = "a,b,c,d(e)\n";
+    assert_eq!(stops(text, "", "f,", 3), [(0, 1), (0, 3), (0, 5)]);
+    assert_eq!(stops(text, "$", "F,", 2), [(0, 5), (0, 3)]);
+    assert_eq!(stops(text, "", "t(", 1), [(0, 6)]);
+    assert_eq!(stops(text, "$", "T,", 1), [(0, 6)]);
+    assert_eq!(stops(text, "", "2f,", 1), [(0, 3)]);
+    // a char that is not there leaves the cursor where it was
+    assert_eq!(stops(text, "l", "fz", 1), [(0, 1)]);
+}
+
+#[test]
+fn semicolon_and_comma_repeat_the_last_find() {
+    let text = "a,b,c,d,e\n";
+    assert_eq!(stops(text, "f,", ";", 2), [(0, 3), (0, 5)]);
+    assert_eq!(stops(text, "$F,", ";", 2), [(0, 5), (0, 3)]);
+    assert_eq!(stops(text, "$3F,", ",", 2), [(0, 5), (0, 7)]);
+    assert_eq!(stops(text, "3f,", ",", 1), [(0, 3)]);
+    assert_eq!(stops(text, "f,", "2;", 1), [(0, 5)]);
+    // `t` and `T` repeated move on rather than stay before the same char
+    assert_eq!(stops(text, "t,", ";", 2), [(0, 2), (0, 4)]);
+    assert_eq!(stops(text, "$T,", ";", 1), [(0, 6)]);
+}
+
+#[test]
+fn find_and_till_as_operator_targets() {
+    // `f` and `t` take the char they stop on, `F` and `T` do not
+    assert_eq!(text_after("one, two\n", "df,"), [" two"]);
+    assert_eq!(text_after("one, two\n", "dt,"), [", two"]);
+    assert_eq!(text_after("one, two\n", "$dF,"), ["oneo"]);
+    assert_eq!(text_after("one, two\n", "$dT,"), ["one,o"]);
+    assert_eq!(text_after("a.b.c.d\n", "df.d;"), ["c.d"]);
+}
@@ -0,0 +1,82 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::{text_after, Editor};
+
+#[test]
+fn delete_takes_any_motion() {
+    assert_eq!(text_after("one two three\n", "dw"), ["two three"]);
+    assert_eq!(text_after("one two three\n", "wd$"), ["one "]);
+    assert_eq!(text_after("one two three\n", "$d0"), ["e"]);
+    assert_eq!(text_after("one\ntwo\nthree\n", "dj"), ["three"]);
+    assert_eq!(text_after("one\ntwo\nthree\n", "jdG"), ["one"]);
+    assert_eq!(text_after("one\ntwo\nthree\n", "dd"), ["two", "three"]);
+}
+
+#[test]
+fn delete_takes_text_objects() {
+    assert_eq!(text_after("f(a, b) + 1\n", "fadi("), ["f() + 1"]);
+    assert_eq!(text_after("one two three\n", "wdaw"), ["one three"]);
+}
+
+#[test]
+fn change_deletes_and_inserts() {
+    let mut editor = Editor::new("one two three\n", Option::None, (5, 40));
+    editor.type_chunks(&["cwsix\x1b"]);
+    assert_eq!(editor.text(), ["six two three"]);
+    editor.type_chunks(&["wccnew\x1b"]);
+    assert_eq!(editor.text(), ["new"]);
+}
+
+#[test]
+fn yank_leaves_the_text_alone() {
+    let mut editor = Editor::new("one two\n", Option::None, (5, 40));
+    editor.type_keys("wye");
+    assert_eq!(editor.text(), ["one two"]);
+    assert_eq!(editor.engine.register('"').unwrap().text, "two");
+    assert_eq!(editor.terminal.cursor(), (0, 4));
+}
+
+#[test]
+fn shift_and_reindent_whole_lines() {
+    let after = |text: &str, keys: &str| {
+        let mut editor = Editor::new(text, Option::Some("shift_width = 4\nexpand_tab = true"), (8, 40));
+        editor.type_keys(keys);
+        editor.text()
+    };
+    let text = "fn f() {\nx\n    y\n}\n";
+    assert_eq!(after(text, "j>j"), ["fn f() {", "    x", "        y", "}"]);
+    assert_eq!(after(text, "jj<<"), ["fn f() {", "x", "y", "}"]);
+    assert_eq!(after("    a\n\n    b\n", "=G"), ["a", "", "b"]);
+}
+
+#[test]
+fn case_operators() {
+    assert_eq!(text_after("one Two\n", "gUiw"), ["ONE Two"]);
+    assert_eq!(text_after("ONE TWO\n", "guw"), ["one TWO"]);
+    assert_eq!(text_after("One two\n", "g~$"), ["oNE TWO"]);
+    assert_eq!(text_after("a\nb\n", "gUj"), ["A", "B"]);
+}
+
+#[test]
+fn esc_or_a_key_that_is_no_motion_cancels_the_operator() {
+    let mut editor = Editor::new("one two\n", Option::None, (5, 40));
+    editor.type_chunks(&["d\x1b", "x"]);
+    assert_eq!(editor.text(), ["ne two"]);
+    editor.type_keys("dQx");
+    assert_eq!(editor.text(), ["e two"]);
+}
+
+#[test]
+fn an_exclusive_motion_to_the_start_of_a_line_can_take_whole_lines() {
+    // from at or before the first non-blank the lines go whole
+    assert_eq!(text_after("a\nb\n\nc\n", "d}"), ["", "c"]);
+    assert_eq!(text_after("  a\nb\n\nc\n", "ld}"), ["", "c"]);
+    assert_eq!(text_after("a\n\nb\nc\n", "Gd{"), ["a", "c"]);
+    // past it, only up to the end of the line before
+    assert_eq!(text_after("ab\nc\n\nd\n", "ld}"), ["a", "", "d"]);
+    assert_eq!(text_after("one\ntwo\n", "dw"), ["", "two"]);
+}
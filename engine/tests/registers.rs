@@ -0,0 +1,99 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::{text_after, Editor};
+use rustudio_engine::register::{Register, RegisterKind};
+
+fn editor(text: &str) -> Editor {
+    Editor::new(text, Option::None, (8, 40))
+}
+
+fn register(editor: &Editor, name: char) -> Option<(String, RegisterKind)> {
+    editor.engine.register(name).map(|Register { text, kind }| (text, kind))
+}
+
+#[test]
+fn named_registers_and_uppercase_append() {
+    let mut editor = editor("one two\n");
+    editor.type_keys("\"ayw");
+    editor.type_keys("w\"Ayw");
+    assert_eq!(register(&editor, 'a'), Option::Some(("one two".to_string(), RegisterKind::Chars)));
+    assert_eq!(register(&editor, '"'), register(&editor, 'a'));
+    editor.type_keys("0\"ap");
+    assert_eq!(editor.text(), ["oone twone two"]);
+}
+
+#[test]
+fn yanks_go_to_0_and_deletes_to_1_to_9_or_the_small_delete_register() {
+    let mut editor = editor("one\ntwo\nthree\nfour\n");
+    editor.type_keys("yy");
+    editor.type_keys("ddddx");
+    assert_eq!(register(&editor, '0'), Option::Some(("one\n".to_string(), RegisterKind::Lines)));
+    assert_eq!(register(&editor, '1'), Option::Some(("two\n".to_string(), RegisterKind::Lines)));
+    assert_eq!(register(&editor, '2'), Option::Some(("one\n".to_string(), RegisterKind::Lines)));
+    assert_eq!(register(&editor, '-'), Option::Some(("t".to_string(), RegisterKind::Chars)));
+    assert_eq!(register(&editor, '"'), register(&editor, '-'));
+    assert_eq!(editor.text(), ["hree", "four"]);
+}
+
+#[test]
+fn the_black_hole_register_keeps_nothing() {
+    let mut editor = editor("one\ntwo\n");
+    editor.type_keys("yy\"_dd");
+    assert_eq!(editor.text(), ["two"]);
+    assert_eq!(register(&editor, '"'), Option::Some(("one\n".to_string(), RegisterKind::Lines)));
+    assert_eq!(register(&editor, '1'), Option::None);
+}
+
+#[test]
+fn read_only_registers() {
+    let mut editor = editor("one\n");
+    editor.type_chunks(&["ihi\x1b", ":nmap Q x\r"]);
+    assert_eq!(register(&editor, '.'), Option::Some(("hi".to_string(), RegisterKind::Chars)));
+    assert_eq!(register(&editor, ':'), Option::Some(("nmap Q x".to_string(), RegisterKind::Chars)));
+    assert_eq!(register(&editor, '%'), Option::None);
+
+    editor.type_keys("\".p");
+    assert_eq!(editor.text(), ["hiohine"]);
+    editor.type_keys("\".yw");
+    assert_eq!(editor.engine.message(), Option::Some("Invalid register name: '.'"));
+    assert_eq!(register(&editor, '.'), Option::Some(("hi".to_string(), RegisterKind::Chars)));
+}
+
+#[test]
+fn put_charwise() {
+    assert_eq!(text_after("abc\n", "xp"), ["bac"]);
+    assert_eq!(text_after("abc\n", "xP"), ["abc"]);
+    assert_eq!(text_after("ab\n", "yl3p"), ["aaaab"]);
+}
+
+#[test]
+fn put_linewise() {
+    assert_eq!(text_after("one\ntwo\n", "yyjp"), ["one", "two", "one"]);
+    assert_eq!(text_after("one\ntwo\n", "yyjP"), ["one", "one", "two"]);
+    assert_eq!(text_after("one\ntwo\n", "ddp"), ["two", "one"]);
+}
+
+#[test]
+fn gp_and_big_gp_leave_the_cursor_after_the_text() {
+    let mut editor = editor("one\ntwo\nthree\n");
+    editor.type_keys("yjgp");
+    assert_eq!(editor.text(), ["one", "one", "two", "two", "three"]);
+    assert_eq!(editor.terminal.cursor(), (3, 0));
+
+    let mut editor = self::editor("ab\n");
+    editor.type_keys("ylgP");
+    assert_eq!(editor.text(), ["aab"]);
+    assert_eq!(editor.terminal.cursor(), (0, 1));
+}
+
+#[test]
+fn put_blockwise() {
+    let mut editor = editor("ab\ncd\nef\n");
+    editor.type_keys("\x16jy$p");
+    assert_eq!(register(&editor, '"'), Option::Some(("a\nc".to_string(), RegisterKind::Block)));
+    assert_eq!(editor.text(), ["aba", "cdc", "ef"]);
+}
@@ -0,0 +1,94 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::Editor;
+
+fn editor_with(text: &str) -> Editor {
+    Editor::new(text, Option::None, (6, 60))
+}
+
+#[test]
+fn patterns_are_literal_text() {
+    let mut editor = editor_with("foo bar\nfoo.*bar\n");
+    editor.type_keys("/foo.*bar\r");
+    assert_eq!(editor.terminal.cursor(), (1, 0));
+}
+
+#[test]
+fn a_missed_pattern_with_regex_chars_says_search_is_literal() {
+    let mut editor = editor_with("foo bar\n");
+    editor.type_keys("/fo+ b.r\r");
+    assert_eq!(editor.engine.message(), Option::Some("Pattern not found (search is literal, not a regex): fo+ b.r"));
+    editor.type_keys("/baz\r");
+    assert_eq!(editor.engine.message(), Option::Some("Pattern not found: baz"));
+}
+
+#[test]
+fn n_and_big_n_repeat_the_search_and_say_when_it_wraps() {
+    let mut editor = editor_with("one\ntwo one\none\n");
+    editor.type_keys("/one\r");
+    assert_eq!(editor.terminal.cursor(), (1, 4));
+    assert_eq!(editor.engine.message(), Option::Some("/one"));
+    editor.type_keys("n");
+    assert_eq!(editor.terminal.cursor(), (2, 0));
+    editor.type_keys("n");
+    assert_eq!(editor.terminal.cursor(), (0, 0));
+    assert_eq!(editor.engine.message(), Option::Some("search hit BOTTOM, continuing at TOP"));
+    editor.type_keys("N");
+    assert_eq!(editor.terminal.cursor(), (2, 0));
+    assert_eq!(editor.engine.message(), Option::Some("search hit TOP, continuing at BOTTOM"));
+    editor.type_keys("2N");
+    assert_eq!(editor.terminal.cursor(), (0, 0));
+}
+
+#[test]
+fn question_mark_searches_backward_and_n_keeps_going_that_way() {
+    let mut editor = editor_with("one\ntwo one\none\n");
+    editor.type_keys("?one\r");
+    assert_eq!(editor.terminal.cursor(), (2, 0));
+    assert_eq!(editor.engine.message(), Option::Some("search hit TOP, continuing at BOTTOM"));
+    editor.type_keys("n");
+    assert_eq!(editor.terminal.cursor(), (1, 4));
+    editor.type_keys("N");
+    assert_eq!(editor.terminal.cursor(), (2, 0));
+}
+
+#[test]
+fn n_without_a_search_says_so() {
+    let mut editor = editor_with("one\n");
+    editor.type_keys("n");
+    assert_eq!(editor.engine.message(), Option::Some("No previous regular expression"));
+}
+
+#[test]
+fn star_and_hash_search_for_the_whole_word_under_the_cursor() {
+    let mut editor = editor_with("foo foobar\nbar foo\n");
+    editor.type_keys("*");
+    assert_eq!(editor.terminal.cursor(), (1, 4));
+    assert_eq!(editor.engine.register('/').unwrap().text, "\\<foo\\>");
+    editor.type_keys("n");
+    assert_eq!(editor.terminal.cursor(), (0, 0));
+
+    editor.type_keys("#");
+    assert_eq!(editor.terminal.cursor(), (1, 4));
+    // from inside the word `#` does not stop at its own start
+    editor.type_keys("l#");
+    assert_eq!(editor.terminal.cursor(), (0, 0));
+}
+
+#[test]
+fn star_off_a_word_says_so() {
+    let mut editor = editor_with("   \n");
+    editor.type_keys("*");
+    assert_eq!(editor.engine.message(), Option::Some("No string under cursor"));
+}
+
+#[test]
+fn search_is_a_motion_for_operators() {
+    let mut editor = editor_with("one two three\n");
+    editor.type_keys("d/thr\r");
+    assert_eq!(editor.text(), ["three"]);
+}
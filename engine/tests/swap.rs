@@ -0,0 +1,80 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::{scratch_dir, Editor};
+use rustudio_buffer::swap;
+use rustudio_engine::Engine;
+use std::fs;
+use std::path::Path;
+
+/// Opens `path`, types `keys` and lets the changes reach the swap file,
+/// then drops the editor without quitting, as a crash would.
+fn crash_after(path: &Path, keys: &str) {
+    let mut editor = Editor::with_engine(Engine::open(path).unwrap(), (5, 100));
+    editor.type_chunks(&[keys]);
+    assert!(swap::swap_path(path).exists());
+}
+
+#[test]
+fn a_session_keeps_a_swap_file_until_it_quits() {
+    let path = scratch_dir("session").join("file.txt");
+    fs::write(&path, "one\n").unwrap();
+    let mut editor = Editor::with_engine(Engine::open(&path).unwrap(), (5, 100));
+    assert!(swap::swap_path(&path).exists());
+    assert!(editor.quits_on(":q\r"));
+    assert!(!swap::swap_path(&path).exists());
+}
+
+#[test]
+fn changes_are_journaled_when_idle() {
+    let path = scratch_dir("journal").join("file.txt");
+    fs::write(&path, "one\n").unwrap();
+    crash_after(&path, "x");
+    let recovered = swap::recover(&swap::swap_path(&path)).unwrap();
+    assert_eq!(recovered.line_text(0), Option::Some("ne".to_string()));
+}
+
+#[test]
+fn recovering_brings_back_the_lost_changes() {
+    let path = scratch_dir("recover").join("file.txt");
+    fs::write(&path, "one\ntwo\n").unwrap();
+    crash_after(&path, "dd");
+
+    let mut editor = Editor::with_engine(Engine::open(&path).unwrap(), (5, 100));
+    assert!(editor.engine.message().unwrap().starts_with("Found swap file"));
+    editor.type_keys("r");
+    assert_eq!(editor.terminal.screen()[..2], ["two", "~"]);
+    assert!(editor.quits_on(":wq\r"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "two\n");
+    assert!(!swap::swap_path(&path).exists());
+}
+
+#[test]
+fn deleting_the_swap_file_keeps_the_file_as_it_is() {
+    let path = scratch_dir("delete").join("file.txt");
+    fs::write(&path, "one\ntwo\n").unwrap();
+    crash_after(&path, "dd");
+
+    let mut editor = Editor::with_engine(Engine::open(&path).unwrap(), (5, 100));
+    editor.type_keys("d");
+    assert_eq!(editor.terminal.screen()[..2], ["one", "two"]);
+    assert!(editor.quits_on(":q\r"));
+    assert!(!swap::swap_path(&path).exists());
+}
+
+#[test]
+fn the_swap_file_can_be_left_alone_by_opening_read_only() {
+    let path = scratch_dir("read-only").join("file.txt");
+    fs::write(&path, "one\n").unwrap();
+    crash_after(&path, "x");
+
+    let mut editor = Editor::with_engine(Engine::open(&path).unwrap(), (5, 100));
+    editor.type_keys("o");
+    assert!(editor.engine.is_read_only());
+    assert_eq!(editor.terminal.line(0), "one");
+    assert!(editor.quits_on(":q\r"));
+    assert!(swap::swap_path(&path).exists());
+}
@@ -0,0 +1,110 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::Editor;
+
+/// The lines of `text` after typing `keys` on it, and what was deleted.
+fn after(text: &str, keys: &str) -> (Vec<String>, String) {
+    let mut editor = Editor::new(text, Option::None, (12, 40));
+    editor.type_keys(keys);
+    let deleted = editor.engine.register('"').map(|register| register.text).unwrap_or_default();
+    (editor.text(), deleted)
+}
+
+#[test]
+fn inner_word() {
+    assert_eq!(after("one two three\n", "wdiw").0, ["one  three"]);
+    assert_eq!(after("one two three\n", "wd3iw").0, ["one "]);
+}
+
+#[test]
+fn a_word_takes_the_blanks_after_or_else_before() {
+    assert_eq!(after("one two three\n", "wdaw").0, ["one three"]);
+    assert_eq!(after("one two three\n", "$daw").0, ["one two"]);
+}
+
+#[test]
+fn counted_words_go_on_over_line_breaks() {
+    assert_eq!(after("one two\nthree four\n", "wd3iw"), (vec!["one  four".to_string()], "two\nthree".to_string()));
+    assert_eq!(after("one two\nthree four\n", "wd3aw"), (vec!["one".to_string()], " two\nthree four".to_string()));
+    assert_eq!(after("one two\n  three four\n", "wd2aw").0, ["one four"]);
+}
+
+#[test]
+fn a_word_at_the_end_of_a_line_keeps_the_line_break() {
+    assert_eq!(after("one two\nthree\n", "wdaw").0, ["one", "three"]);
+}
+
+#[test]
+fn counted_words_stop_at_the_end_of_the_buffer() {
+    assert_eq!(after("one two\n\nthree\n", "wd9aw").0, ["one"]);
+    assert_eq!(after("one\n\ntwo\n", "jdiw").0, ["one", "", "two"]);
+}
+
+#[test]
+fn quotes() {
+    assert_eq!(after("say \"hi there\" now\n", "fhdi\"").0, ["say \"\" now"]);
+    assert_eq!(after("say \"hi there\" now\n", "fhda\"").0, ["say now"]);
+    assert_eq!(after("say \"hi there\" now\n", "fhd2i\"").0, ["say  now"]);
+}
+
+#[test]
+fn nested_parens() {
+    let text = "f(a, g(b, c), d)\n";
+    assert_eq!(after(text, "fbdi(").0, ["f(a, g(), d)"]);
+    assert_eq!(after(text, "fbda(").0, ["f(a, g, d)"]);
+    assert_eq!(after(text, "fbd2i(").0, ["f()"]);
+    assert_eq!(after(text, "fbd2a(").0, ["f"]);
+    assert_eq!(after(text, "fbd3i(").0, [text.trim_end()]);
+}
+
+#[test]
+fn parens_over_lines() {
+    let text = "call(a,\n     b)\n";
+    assert_eq!(after(text, "jfbdi(").0, ["call()"]);
+    assert_eq!(after(text, "jfbda(").0, ["call"]);
+}
+
+#[test]
+fn braces_alone_on_their_lines_take_whole_lines() {
+    let text = "fn f() {\n    a;\n    b;\n}\n";
+    let (lines, deleted) = after(text, "jdi{");
+    assert_eq!(lines, ["fn f() {", "}"]);
+    assert_eq!(deleted, "    a;\n    b;\n");
+    assert_eq!(after(text, "jda{").0, ["fn f() "]);
+}
+
+#[test]
+fn nested_braces_over_lines() {
+    let text = "a {\n    b {\n        c\n    }\n}\n";
+    assert_eq!(after(text, "jjdi{").0, ["a {", "    b {", "    }", "}"]);
+    assert_eq!(after(text, "jjd2i{").0, ["a {", "}"]);
+    assert_eq!(after(text, "jjda{").0, ["a {", "    b ", "}"]);
+    assert_eq!(after(text, "jjd2a{").0, ["a "]);
+}
+
+#[test]
+fn nested_tags() {
+    let text = "<a><b>x</b><b>y</b></a>\n";
+    assert_eq!(after(text, "fxdit").0, ["<a><b></b><b>y</b></a>"]);
+    assert_eq!(after(text, "fxdat").0, ["<a><b>y</b></a>"]);
+    assert_eq!(after(text, "fxd2it").0, ["<a></a>"]);
+    assert_eq!(after(text, "fxd2at").0, [""]);
+}
+
+#[test]
+fn tags_over_lines() {
+    let text = "<div>\n  <p>hi</p>\n  <p\n    class=\"x\">there</p>\n</div>\n";
+    assert_eq!(after(text, "jfhdit").0, ["<div>", "  <p></p>", "  <p", "    class=\"x\">there</p>", "</div>"]);
+    assert_eq!(after(text, "3jftdit").0, ["<div>", "  <p>hi</p>", "  <p", "    class=\"x\"></p>", "</div>"]);
+    assert_eq!(after(text, "jfhd2it").0, ["<div>", "</div>"]);
+}
+
+#[test]
+fn tags_left_open_do_not_pair_up() {
+    let text = "<a><br>x</a>\n";
+    assert_eq!(after(text, "fxdit").0, ["<a></a>"]);
+}
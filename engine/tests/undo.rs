@@ -0,0 +1,86 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::Editor;
+
+fn editor_with(text: &str) -> Editor {
+    Editor::new(text, Option::None, (5, 40))
+}
+
+#[test]
+fn u_reverts_a_whole_insert_and_ctrl_r_brings_it_back() {
+    let mut editor = editor_with("end\n");
+    editor.type_chunks(&["ione two \x1b"]);
+    assert_eq!(editor.terminal.line(0), "one two end");
+    editor.type_chunks(&["u"]);
+    assert_eq!(editor.terminal.line(0), "end");
+    assert_eq!(editor.engine.message(), Option::Some("before #1"));
+    editor.type_chunks(&["\x12"]);
+    assert_eq!(editor.terminal.line(0), "one two end");
+    assert_eq!(editor.engine.message(), Option::Some("after #1"));
+}
+
+#[test]
+fn u_and_ctrl_r_take_a_count() {
+    let mut editor = editor_with("abcd\n");
+    editor.type_chunks(&["x", "x", "x"]);
+    assert_eq!(editor.terminal.line(0), "d");
+    editor.type_chunks(&["2u"]);
+    assert_eq!(editor.terminal.line(0), "bcd");
+    editor.type_chunks(&["2\x12"]);
+    assert_eq!(editor.terminal.line(0), "d");
+}
+
+#[test]
+fn undoing_past_the_ends_says_so() {
+    let mut editor = editor_with("abc\n");
+    editor.type_chunks(&["u"]);
+    assert_eq!(editor.engine.message(), Option::Some("Already at oldest change"));
+    editor.type_chunks(&["\x12"]);
+    assert_eq!(editor.engine.message(), Option::Some("Already at newest change"));
+}
+
+#[test]
+fn g_minus_and_g_plus_reach_undone_branches() {
+    let mut editor = editor_with("abc\n");
+    // state 1 deletes `a`; undoing it and deleting `c` starts state 2 on
+    // another branch
+    editor.type_chunks(&["x", "u", "$x"]);
+    assert_eq!(editor.terminal.line(0), "ab");
+    editor.type_chunks(&["u", "\x12"]);
+    assert_eq!(editor.terminal.line(0), "ab");
+    editor.type_chunks(&["g-"]);
+    assert_eq!(editor.terminal.line(0), "bc");
+    editor.type_chunks(&["g-"]);
+    assert_eq!(editor.terminal.line(0), "abc");
+    editor.type_chunks(&["2g+"]);
+    assert_eq!(editor.terminal.line(0), "ab");
+}
+
+#[test]
+fn earlier_and_later_go_by_steps_or_time() {
+    let mut editor = editor_with("abcd\n");
+    editor.type_chunks(&["x", "x", "x"]);
+    editor.type_chunks(&[":earlier\r"]);
+    assert_eq!(editor.terminal.line(0), "cd");
+    editor.type_chunks(&[":earlier 2\r"]);
+    assert_eq!(editor.terminal.line(0), "abcd");
+    editor.type_chunks(&[":later 2\r"]);
+    assert_eq!(editor.terminal.line(0), "cd");
+    editor.type_chunks(&[":later 1h\r"]);
+    assert_eq!(editor.terminal.line(0), "d");
+    editor.type_chunks(&[":earlier 10m\r"]);
+    assert_eq!(editor.terminal.line(0), "abcd");
+    editor.type_chunks(&[":earlier 1s\r"]);
+    assert_eq!(editor.engine.message(), Option::Some("Already at oldest change"));
+}
+
+#[test]
+fn earlier_rejects_other_units() {
+    let mut editor = editor_with("abcd\n");
+    editor.type_chunks(&[":earlier 3x\r"]);
+    assert_eq!(editor.engine.message(), Option::Some("Invalid argument: 3x"));
+}
@@ -0,0 +1,55 @@
+extern crate rustudio_buffer;
+extern crate rustudio_engine;
+extern crate rustudio_keys;
+
+mod common;
+
+use common::Editor;
+use rustudio_engine::terminal::Style;
+
+/// Runs `keys` on a buffer holding `text` and gives the screen, selected
+/// text in uppercase.
+fn screen_after(text: &str, keys: &str) -> (Vec<String>, (usize, usize)) {
+    let mut editor = Editor::new(text, Option::None, (6, 20));
+    editor.type_keys(keys);
+    let screen = (0..5).map(|row| {
+        editor.terminal.cells(row).iter()
+            .map(|cell| if cell.style == Style::Selected { cell.ch.to_ascii_uppercase() } else { cell.ch })
+            .collect()
+    }).collect();
+    (screen, editor.terminal.cursor())
+}
+
+#[test]
+fn g_goes_to_the_last_line() {
+    let (screen, cursor) = screen_after("one\ntwo\nthree\n", "vG");
+    assert_eq!(screen[..3], ["ONE", "TWO", "Three"]);
+    assert_eq!(cursor, (2, 0));
+}
+
+#[test]
+fn g_with_a_count_goes_to_that_line() {
+    let (screen, cursor) = screen_after("one\ntwo\nthree\n", "v2G");
+    assert_eq!(screen[..3], ["ONE", "Two", "three"]);
+    assert_eq!(cursor, (1, 0));
+}
+
+#[test]
+fn percent_goes_to_the_matching_bracket() {
+    let (screen, cursor) = screen_after("f(a, b) x\nnext\n", "f(v%");
+    assert_eq!(screen[..2], ["f(A, B) x", "next"]);
+    assert_eq!(cursor, (0, 6));
+}
+
+#[test]
+fn percent_with_a_count_goes_that_far_into_the_file() {
+    let (_, cursor) = screen_after("(\n1\n2\n)\n", "v50%");
+    assert_eq!(cursor, (1, 0));
+}
+
+#[test]
+fn motions_take_the_count() {
+    let (screen, cursor) = screen_after("one two three four\n", "v2w");
+    assert_eq!(screen[0], "ONE TWO Three four");
+    assert_eq!(cursor, (0, 8));
+}
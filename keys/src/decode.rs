@@ -0,0 +1,372 @@
+//! Turning what terminals send into `Key`s: the key codes ncurses' `getch`
+//! returns with `keypad` on, and the raw bytes of escape sequences and
+//! UTF-8 text it leaves alone.
+
+use std::collections::VecDeque;
+use {Key, KeyCode, Modifiers};
+
+// The ncurses key codes, as in <curses.h>.
+const KEY_DOWN: i32 = 0x102;
+const KEY_UP: i32 = 0x103;
+const KEY_LEFT: i32 = 0x104;
+const KEY_RIGHT: i32 = 0x105;
+const KEY_HOME: i32 = 0x106;
+const KEY_BACKSPACE: i32 = 0x107;
+const KEY_F0: i32 = 0x108;
+const KEY_DC: i32 = 0x14a;
+const KEY_IC: i32 = 0x14b;
+const KEY_SF: i32 = 0x150;
+const KEY_SR: i32 = 0x151;
+const KEY_NPAGE: i32 = 0x152;
+const KEY_PPAGE: i32 = 0x153;
+const KEY_ENTER: i32 = 0x157;
+const KEY_BTAB: i32 = 0x161;
+const KEY_END: i32 = 0x168;
+const KEY_SDC: i32 = 0x17f;
+const KEY_SEND: i32 = 0x182;
+const KEY_SHOME: i32 = 0x187;
+const KEY_SIC: i32 = 0x188;
+const KEY_SLEFT: i32 = 0x189;
+const KEY_SNEXT: i32 = 0x18c;
+const KEY_SPREVIOUS: i32 = 0x18e;
+const KEY_SRIGHT: i32 = 0x192;
+
+/// The key for an ncurses key code above 255. Byte values are not key
+/// codes, they go through a `Decoder`.
+pub fn from_curses(code: i32) -> Option<Key> {
+    let shifted = |code| Key::with_modifiers(code, Modifiers::SHIFT);
+    Option::Some(match code {
+        KEY_DOWN      => Key::new(KeyCode::Down),
+        KEY_UP        => Key::new(KeyCode::Up),
+        KEY_LEFT      => Key::new(KeyCode::Left),
+        KEY_RIGHT     => Key::new(KeyCode::Right),
+        KEY_HOME      => Key::new(KeyCode::Home),
+        KEY_END       => Key::new(KeyCode::End),
+        KEY_BACKSPACE => Key::new(KeyCode::Backspace),
+        KEY_DC        => Key::new(KeyCode::Delete),
+        KEY_IC        => Key::new(KeyCode::Insert),
+        KEY_NPAGE     => Key::new(KeyCode::PageDown),
+        KEY_PPAGE     => Key::new(KeyCode::PageUp),
+        KEY_ENTER     => Key::new(KeyCode::Enter),
+        KEY_BTAB      => shifted(KeyCode::Tab),
+        KEY_SF        => shifted(KeyCode::Down),
+        KEY_SR        => shifted(KeyCode::Up),
+        KEY_SLEFT     => shifted(KeyCode::Left),
+        KEY_SRIGHT    => shifted(KeyCode::Right),
+        KEY_SHOME     => shifted(KeyCode::Home),
+        KEY_SEND      => shifted(KeyCode::End),
+        KEY_SDC       => shifted(KeyCode::Delete),
+        KEY_SIC       => shifted(KeyCode::Insert),
+        KEY_SNEXT     => shifted(KeyCode::PageDown),
+        KEY_SPREVIOUS => shifted(KeyCode::PageUp),
+        // xterm reports Shift-F1 to Shift-F12 as F13 to F24
+        _ if code > KEY_F0 && code <= KEY_F0 + 12 => Key::new(KeyCode::F((code - KEY_F0) as u8)),
+        _ if code > KEY_F0 + 12 && code <= KEY_F0 + 24 => shifted(KeyCode::F((code - KEY_F0 - 12) as u8)),
+        _ => return Option::None,
+    })
+}
+
+/// What the bytes fed so far amount to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decoded {
+    /// A whole key, made of the first `len` bytes.
+    Key(Key, usize),
+    /// The start of a longer sequence, more bytes are needed.
+    Incomplete,
+    /// The first `len` bytes mean nothing and should be dropped.
+    Invalid(usize),
+}
+
+/// Decodes the key at the start of `bytes`: a UTF-8 char, a control char,
+/// an `Alt` chord sent as Esc followed by the key, or a VT100/xterm escape
+/// sequence such as `\x1b[A` or `\x1b[1;5C`.
+///
+/// A lone Esc is `Incomplete` since it may start a sequence; once no more
+/// bytes come in time, `finish` settles it.
+pub fn decode(bytes: &[u8]) -> Decoded {
+    match bytes.first() {
+        Option::None       => Decoded::Incomplete,
+        Option::Some(0x1b) => decode_escape(bytes),
+        Option::Some(_)    => decode_char(bytes),
+    }
+}
+
+/// Decodes the start of `bytes` knowing that no more will follow it.
+pub fn finish(bytes: &[u8]) -> Decoded {
+    match decode(bytes) {
+        // a lone Esc, or a sequence cut short: the Esc goes alone
+        Decoded::Incomplete if bytes.first() == Option::Some(&0x1b) => Decoded::Key(Key::new(KeyCode::Esc), 1),
+        Decoded::Incomplete if bytes.is_empty() => Decoded::Incomplete,
+        Decoded::Incomplete => Decoded::Invalid(bytes.len()),
+        decoded => decoded,
+    }
+}
+
+fn decode_char(bytes: &[u8]) -> Decoded {
+    let len = match bytes[0] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _           => return Decoded::Invalid(1),
+    };
+    if bytes.len() < len {
+        return if bytes[1..].iter().all(|&byte| byte & 0xc0 == 0x80) {
+            Decoded::Incomplete
+        } else {
+            Decoded::Invalid(1)
+        };
+    }
+    match ::std::str::from_utf8(&bytes[..len]).ok().and_then(|text| text.chars().next()) {
+        Option::Some(ch) => Decoded::Key(Key::from_char(ch), len),
+        Option::None     => Decoded::Invalid(1),
+    }
+}
+
+fn decode_escape(bytes: &[u8]) -> Decoded {
+    match bytes.get(1) {
+        Option::None => Decoded::Incomplete,
+        Option::Some(b'[') => decode_csi(bytes),
+        Option::Some(b'O') => match bytes.get(2) {
+            Option::None => Decoded::Incomplete,
+            Option::Some(&last) => match final_key(last) {
+                Option::Some(code) => Decoded::Key(Key::new(code), 3),
+                Option::None       => Decoded::Invalid(3),
+            },
+        },
+        // Esc then a key is that key with Alt held down
+        Option::Some(_) => match decode(&bytes[1..]) {
+            Decoded::Key(key, len) => Decoded::Key(
+                Key { code: key.code, modifiers: key.modifiers.with(Modifiers::ALT) }, len + 1),
+            Decoded::Incomplete => Decoded::Incomplete,
+            Decoded::Invalid(_) => Decoded::Invalid(1),
+        },
+    }
+}
+
+/// `Esc [ {params} {final}`, where the parameters are digits and `;`.
+fn decode_csi(bytes: &[u8]) -> Decoded {
+    let end = match bytes[2..].iter().position(|byte| !(byte.is_ascii_digit() || *byte == b';')) {
+        Option::Some(end) => end + 2,
+        Option::None      => return Decoded::Incomplete,
+    };
+    let len = end + 1;
+    let params: Vec<u32> = ::std::str::from_utf8(&bytes[2..end]).unwrap_or("")
+        .split(';')
+        .map(|param| param.parse().unwrap_or(1))
+        .collect();
+    let modifiers = match params.get(1) {
+        Option::Some(&param) => {
+            let bits = param.saturating_sub(1);
+            Modifiers { shift: bits & 1 != 0, alt: bits & 2 != 0, ctrl: bits & 4 != 0 }
+        }
+        Option::None => Modifiers::NONE,
+    };
+    let code = match bytes[end] {
+        b'~' => match params[0] {
+            1 | 7   => Option::Some(KeyCode::Home),
+            2       => Option::Some(KeyCode::Insert),
+            3       => Option::Some(KeyCode::Delete),
+            4 | 8   => Option::Some(KeyCode::End),
+            5       => Option::Some(KeyCode::PageUp),
+            6       => Option::Some(KeyCode::PageDown),
+            11..=15 => Option::Some(KeyCode::F((params[0] - 10) as u8)),
+            17..=21 => Option::Some(KeyCode::F((params[0] - 11) as u8)),
+            23 | 24 => Option::Some(KeyCode::F((params[0] - 12) as u8)),
+            _       => Option::None,
+        },
+        b'Z' => return Decoded::Key(Key::with_modifiers(KeyCode::Tab, modifiers.with(Modifiers::SHIFT)), len),
+        last => final_key(last),
+    };
+    match code {
+        Option::Some(code) => Decoded::Key(Key::with_modifiers(code, modifiers), len),
+        Option::None       => Decoded::Invalid(len),
+    }
+}
+
+/// The key named by the last byte of `Esc [ X` and `Esc O X`.
+fn final_key(last: u8) -> Option<KeyCode> {
+    match last {
+        b'A' => Option::Some(KeyCode::Up),
+        b'B' => Option::Some(KeyCode::Down),
+        b'C' => Option::Some(KeyCode::Right),
+        b'D' => Option::Some(KeyCode::Left),
+        b'H' => Option::Some(KeyCode::Home),
+        b'F' => Option::Some(KeyCode::End),
+        b'P'..=b'S' => Option::Some(KeyCode::F(last - b'P' + 1)),
+        _ => Option::None,
+    }
+}
+
+/// Collects bytes as they arrive and hands out the keys they make up.
+///
+/// ```
+/// use rustudio_keys::{Key, KeyCode, Modifiers};
+/// use rustudio_keys::decode::Decoder;
+///
+/// let mut decoder = Decoder::new();
+/// decoder.push_bytes(b"\x1b[1;5Cj\xc3");
+/// assert_eq!(decoder.next_key(), Some(Key::with_modifiers(KeyCode::Right, Modifiers::CTRL)));
+/// assert_eq!(decoder.next_key(), Some(Key::from_char('j')));
+/// assert_eq!(decoder.next_key(), None);
+/// decoder.push(0xa9);
+/// assert_eq!(decoder.next_key(), Some(Key::from_char('é')));
+/// ```
+#[derive(Debug, Default)]
+pub struct Decoder {
+    bytes: VecDeque<u8>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder::default()
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        self.bytes.push_back(byte);
+    }
+
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend(bytes);
+    }
+
+    /// Whether bytes of an unfinished key are waiting for more.
+    pub fn is_pending(&self) -> bool {
+        !self.bytes.is_empty()
+    }
+
+    /// The next whole key, or `None` until more bytes come in.
+    pub fn next_key(&mut self) -> Option<Key> {
+        self.take(decode)
+    }
+
+    /// The next key, taking the bytes as they are when no more are coming,
+    /// so that a lone Esc becomes the Esc key.
+    pub fn flush_key(&mut self) -> Option<Key> {
+        self.take(finish)
+    }
+
+    fn take(&mut self, decode: fn(&[u8]) -> Decoded) -> Option<Key> {
+        loop {
+            let decoded = decode(self.bytes.make_contiguous());
+            match decoded {
+                Decoded::Key(key, len) => {
+                    self.bytes.drain(..len);
+                    return Option::Some(key);
+                }
+                Decoded::Invalid(len) => {
+                    self.bytes.drain(..len);
+                }
+                Decoded::Incomplete => return Option::None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: Modifiers) -> Decoded {
+        Decoded::Key(Key::with_modifiers(code, modifiers), 0)
+    }
+
+    /// `decode` of `bytes`, checking that the key takes all of them.
+    fn whole(bytes: &[u8]) -> Decoded {
+        match decode(bytes) {
+            Decoded::Key(key, len) => {
+                assert_eq!(len, bytes.len(), "{:?} left bytes over", bytes);
+                Decoded::Key(key, 0)
+            }
+            decoded => decoded,
+        }
+    }
+
+    #[test]
+    fn csi_sequences() {
+        assert_eq!(whole(b"\x1b[A"), key(KeyCode::Up, Modifiers::NONE));
+        assert_eq!(whole(b"\x1b[D"), key(KeyCode::Left, Modifiers::NONE));
+        assert_eq!(whole(b"\x1b[1;5C"), key(KeyCode::Right, Modifiers::CTRL));
+        assert_eq!(whole(b"\x1b[1;2H"), key(KeyCode::Home, Modifiers::SHIFT));
+        assert_eq!(whole(b"\x1b[1;3F"), key(KeyCode::End, Modifiers::ALT));
+        assert_eq!(whole(b"\x1b[3~"), key(KeyCode::Delete, Modifiers::NONE));
+        assert_eq!(whole(b"\x1b[5;5~"), key(KeyCode::PageUp, Modifiers::CTRL));
+        assert_eq!(whole(b"\x1b[15~"), key(KeyCode::F(5), Modifiers::NONE));
+        assert_eq!(whole(b"\x1b[24~"), key(KeyCode::F(12), Modifiers::NONE));
+        assert_eq!(whole(b"\x1b[Z"), key(KeyCode::Tab, Modifiers::SHIFT));
+    }
+
+    #[test]
+    fn ss3_sequences() {
+        assert_eq!(whole(b"\x1bOA"), key(KeyCode::Up, Modifiers::NONE));
+        assert_eq!(whole(b"\x1bOP"), key(KeyCode::F(1), Modifiers::NONE));
+        assert_eq!(whole(b"\x1bOS"), key(KeyCode::F(4), Modifiers::NONE));
+        assert_eq!(decode(b"\x1bOx"), Decoded::Invalid(3));
+    }
+
+    #[test]
+    fn unknown_csi_sequences_are_dropped_whole() {
+        assert_eq!(decode(b"\x1b[99~j"), Decoded::Invalid(5));
+        assert_eq!(decode(b"\x1b[1;5x"), Decoded::Invalid(6));
+    }
+
+    #[test]
+    fn chars_control_chars_and_alt() {
+        assert_eq!(whole(b"j"), key(KeyCode::Char('j'), Modifiers::NONE));
+        assert_eq!(whole("é".as_bytes()), key(KeyCode::Char('é'), Modifiers::NONE));
+        assert_eq!(whole(b"\x05"), key(KeyCode::Char('e'), Modifiers::CTRL));
+        assert_eq!(whole(b"\r"), key(KeyCode::Enter, Modifiers::NONE));
+        assert_eq!(whole(b"\x7f"), key(KeyCode::Backspace, Modifiers::NONE));
+        assert_eq!(whole(b"\x1bj"), key(KeyCode::Char('j'), Modifiers::ALT));
+        assert_eq!(whole("\x1bé".as_bytes()), key(KeyCode::Char('é'), Modifiers::ALT));
+    }
+
+    #[test]
+    fn unfinished_input_waits_and_finish_settles_it() {
+        for bytes in [&b"\x1b"[..], b"\x1b[", b"\x1b[1;5", b"\x1bO", b"\xc3"] {
+            assert_eq!(decode(bytes), Decoded::Incomplete, "{:?}", bytes);
+        }
+        assert_eq!(finish(b"\x1b"), Decoded::Key(Key::new(KeyCode::Esc), 1));
+        assert_eq!(finish(b"\x1b[1;5"), Decoded::Key(Key::new(KeyCode::Esc), 1));
+        assert_eq!(finish(b"\xc3"), Decoded::Invalid(1));
+        assert_eq!(finish(b""), Decoded::Incomplete);
+    }
+
+    #[test]
+    fn bytes_that_are_not_utf8_are_dropped() {
+        assert_eq!(decode(b"\xffj"), Decoded::Invalid(1));
+        assert_eq!(decode(b"\xc3j"), Decoded::Invalid(1));
+        let mut decoder = Decoder::new();
+        decoder.push_bytes(b"\xff\x80j");
+        assert_eq!(decoder.next_key(), Option::Some(Key::from_char('j')));
+        assert!(!decoder.is_pending());
+    }
+
+    #[test]
+    fn the_decoder_waits_for_the_rest_of_a_sequence() {
+        let mut decoder = Decoder::new();
+        decoder.push_bytes(b"\x1b[");
+        assert_eq!(decoder.next_key(), Option::None);
+        assert!(decoder.is_pending());
+        decoder.push(b'B');
+        assert_eq!(decoder.next_key(), Option::Some(Key::new(KeyCode::Down)));
+
+        decoder.push(0x1b);
+        assert_eq!(decoder.next_key(), Option::None);
+        assert_eq!(decoder.flush_key(), Option::Some(Key::new(KeyCode::Esc)));
+        assert!(!decoder.is_pending());
+    }
+
+    #[test]
+    fn curses_key_codes() {
+        assert_eq!(from_curses(KEY_UP), Option::Some(Key::new(KeyCode::Up)));
+        assert_eq!(from_curses(KEY_BACKSPACE), Option::Some(Key::new(KeyCode::Backspace)));
+        assert_eq!(from_curses(KEY_BTAB), Option::Some(Key::with_modifiers(KeyCode::Tab, Modifiers::SHIFT)));
+        assert_eq!(from_curses(KEY_SRIGHT), Option::Some(Key::with_modifiers(KeyCode::Right, Modifiers::SHIFT)));
+        assert_eq!(from_curses(KEY_F0 + 1), Option::Some(Key::new(KeyCode::F(1))));
+        assert_eq!(from_curses(KEY_F0 + 12), Option::Some(Key::new(KeyCode::F(12))));
+        assert_eq!(from_curses(KEY_F0 + 13), Option::Some(Key::with_modifiers(KeyCode::F(1), Modifiers::SHIFT)));
+        assert_eq!(from_curses(KEY_F0), Option::None);
+        assert_eq!(from_curses(0x1ff), Option::None);
+    }
+}
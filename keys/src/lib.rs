@@ -1,5 +1,148 @@
-pub const Ctrl: char = 17 as char;
-pub const Esc: char = 27 as char;
-pub const Backspace: char = 127 as char;
+pub mod decode;
+pub mod notation;
 
+/// A key on the keyboard, without the modifiers held down with it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    /// A function key, `F(1)` to `F(12)`.
+    F(u8),
+    Tab,
+    Enter,
+    Esc,
+    Backspace,
+    Delete,
+    Insert,
+}
 
+/// The modifier keys held down with a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers { ctrl: false, alt: false, shift: false };
+    pub const CTRL: Modifiers = Modifiers { ctrl: true, alt: false, shift: false };
+    pub const ALT: Modifiers = Modifiers { ctrl: false, alt: true, shift: false };
+    pub const SHIFT: Modifiers = Modifiers { ctrl: false, alt: false, shift: true };
+
+    pub fn is_empty(&self) -> bool {
+        *self == Modifiers::NONE
+    }
+
+    /// Both sets of modifiers together.
+    pub fn with(self, other: Modifiers) -> Modifiers {
+        Modifiers {
+            ctrl: self.ctrl || other.ctrl,
+            alt: self.alt || other.alt,
+            shift: self.shift || other.shift,
+        }
+    }
+}
+
+/// One key press.
+///
+/// Keys are kept in the form terminals can report them in, so that a key
+/// built by hand compares equal to the decoded one: `Ctrl-[` is `Esc`,
+/// `Ctrl-I` is `Tab`, `Ctrl-M` is `Enter`, a control letter is always
+/// lowercase and a shifted letter is just the uppercase letter.
+///
+/// ```
+/// use rustudio_keys::{Key, KeyCode};
+///
+/// assert_eq!(Key::ctrl('['), Key::new(KeyCode::Esc));
+/// assert_eq!(Key::from_char('\x05'), Key::ctrl('E'));
+/// assert_eq!(Key::from_char('A').code, KeyCode::Char('A'));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode) -> Self {
+        Key { code, modifiers: Modifiers::NONE }
+    }
+
+    pub fn with_modifiers(code: KeyCode, modifiers: Modifiers) -> Self {
+        match code {
+            KeyCode::Char(ch) if modifiers.ctrl => {
+                let key = Key::ctrl(ch);
+                Key { code: key.code, modifiers: key.modifiers.with(Modifiers { ctrl: false, ..modifiers }) }
+            }
+            KeyCode::Char(ch) if modifiers.shift && ch.is_alphabetic() => {
+                Key { code: KeyCode::Char(ch.to_uppercase().next().unwrap_or(ch)),
+                      modifiers: Modifiers { shift: false, ..modifiers } }
+            }
+            _ => Key { code, modifiers },
+        }
+    }
+
+    /// `ch` typed with Ctrl held down.
+    pub fn ctrl(ch: char) -> Self {
+        match ch {
+            '@'..='_' | 'a'..='z' => Key::from_char(((ch.to_ascii_uppercase() as u8) & 0x1f) as char),
+            _ => Key { code: KeyCode::Char(ch), modifiers: Modifiers::CTRL },
+        }
+    }
+
+    /// `ch` typed with Alt held down.
+    pub fn alt(ch: char) -> Self {
+        let key = Key::from_char(ch);
+        Key { code: key.code, modifiers: key.modifiers.with(Modifiers::ALT) }
+    }
+
+    /// The key a terminal means by sending `ch`: control characters become
+    /// the named keys or the Ctrl chord they stand for.
+    pub fn from_char(ch: char) -> Self {
+        match ch {
+            '\x1b'        => Key::new(KeyCode::Esc),
+            '\r' | '\n'   => Key::new(KeyCode::Enter),
+            '\t'          => Key::new(KeyCode::Tab),
+            '\x7f'        => Key::new(KeyCode::Backspace),
+            '\x00'..='\x1f' => {
+                let base = ((ch as u8) | 0x40) as char;
+                Key { code: KeyCode::Char(base.to_ascii_lowercase()), modifiers: Modifiers::CTRL }
+            }
+            _ => Key::new(KeyCode::Char(ch)),
+        }
+    }
+
+    /// The char this key types into the text, if any.
+    pub fn to_char(&self) -> Option<char> {
+        if self.modifiers.ctrl || self.modifiers.alt {
+            return Option::None;
+        }
+        match self.code {
+            KeyCode::Char(ch) => Option::Some(ch),
+            KeyCode::Tab      => Option::Some('\t'),
+            KeyCode::Enter    => Option::Some('\n'),
+            _                 => Option::None,
+        }
+    }
+
+    /// Whether this is the plain key `ch`, without modifiers.
+    pub fn is_char(&self, ch: char) -> bool {
+        *self == Key::new(KeyCode::Char(ch))
+    }
+}
+
+/// A sequence of keys that together trigger a command, like `zt`.
+pub type KeyCombination = Vec<Key>;
+
+/// The keys of `text`, one per char as `Key::from_char` reads them.
+pub fn keys(text: &str) -> KeyCombination {
+    text.chars().map(Key::from_char).collect()
+}
@@ -0,0 +1,204 @@
+//! Vim's key notation: `<C-[>`, `<Esc>`, `<leader>ff`, `<A-j>`, `<S-Tab>`.
+
+use std::fmt;
+use {Key, KeyCode, KeyCombination, Modifiers};
+
+/// The key `<leader>` stands for unless told otherwise.
+pub const DEFAULT_LEADER: char = '\\';
+
+/// Names of the keys that are written between `<` and `>`, matched
+/// without regard to case. The first name of a key is the one it is
+/// printed with.
+const NAMES: &[(&str, KeyCode)] = &[
+    ("Esc", KeyCode::Esc),
+    ("CR", KeyCode::Enter),
+    ("Enter", KeyCode::Enter),
+    ("Return", KeyCode::Enter),
+    ("NL", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("BS", KeyCode::Backspace),
+    ("Backspace", KeyCode::Backspace),
+    ("Del", KeyCode::Delete),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Ins", KeyCode::Insert),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Space", KeyCode::Char(' ')),
+    ("lt", KeyCode::Char('<')),
+    ("Bar", KeyCode::Char('|')),
+    ("Bslash", KeyCode::Char('\\')),
+];
+
+/// Reads the keys written in `text`, with `<leader>` meaning
+/// `DEFAULT_LEADER`.
+///
+/// ```
+/// use rustudio_keys::{Key, KeyCode, Modifiers};
+/// use rustudio_keys::notation;
+///
+/// assert_eq!(notation::parse("<C-[>").unwrap(), vec![Key::new(KeyCode::Esc)]);
+/// assert_eq!(notation::parse("<leader>ff").unwrap(), rustudio_keys::keys("\\ff"));
+/// assert_eq!(notation::parse("<S-Tab>").unwrap(), vec![Key::with_modifiers(KeyCode::Tab, Modifiers::SHIFT)]);
+/// assert_eq!(notation::to_string(&notation::parse("<a-J>x<lt>").unwrap()), "<A-J>x<lt>");
+/// ```
+pub fn parse(text: &str) -> Result<KeyCombination, String> {
+    parse_with_leader(text, &[Key::from_char(DEFAULT_LEADER)])
+}
+
+/// Reads the keys written in `text`, with `<leader>` meaning `leader`.
+///
+/// As in Vim, brackets around something that is not a key name, like
+/// `<div>`, are just text. A modifier with an unknown key, like `<C-Foo>`,
+/// is an error.
+pub fn parse_with_leader(text: &str, leader: &[Key]) -> Result<KeyCombination, String> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        if ch == '<' {
+            if let Some(end) = rest[1..].find('>').map(|end| end + 1) {
+                let name = &rest[1..end];
+                let key = if name.eq_ignore_ascii_case("leader") {
+                    Option::Some(leader.to_vec())
+                } else {
+                    parse_key(name).map(|key| vec![key])
+                };
+                match key {
+                    Option::Some(key) => {
+                        keys.extend(key);
+                        rest = &rest[end + 1..];
+                        continue;
+                    }
+                    Option::None if has_modifier(name) => return Err(format!("Unknown key: <{}>", name)),
+                    Option::None => (),
+                }
+            }
+        }
+        keys.push(Key::from_char(ch));
+        rest = &rest[ch.len_utf8()..];
+    }
+    Ok(keys)
+}
+
+fn has_modifier(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() > 2 && bytes[1] == b'-' && b"CcAaMmSs".contains(&bytes[0])
+}
+
+/// One key written without its brackets, like `C-[` or `PageUp`.
+fn parse_key(name: &str) -> Option<Key> {
+    let mut modifiers = Modifiers::NONE;
+    let mut rest = name;
+    loop {
+        let bytes = rest.as_bytes();
+        if bytes.len() <= 2 || bytes[1] != b'-' {
+            break;
+        }
+        match bytes[0].to_ascii_uppercase() {
+            b'C'        => modifiers.ctrl = true,
+            b'A' | b'M' => modifiers.alt = true,
+            b'S'        => modifiers.shift = true,
+            _           => return Option::None,
+        }
+        rest = &rest[2..];
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        // a lone char only names a key with a modifier, `<a>` is text
+        (Option::Some(ch), Option::None) if !modifiers.is_empty() => KeyCode::Char(ch),
+        _ => match NAMES.iter().find(|&&(key_name, _)| key_name.eq_ignore_ascii_case(rest)) {
+            Option::Some(&(_, code)) => code,
+            Option::None => match rest.strip_prefix(['F', 'f']).and_then(|number| number.parse().ok()) {
+                Option::Some(number @ 1..=12) => KeyCode::F(number),
+                _ => return Option::None,
+            },
+        },
+    };
+    Option::Some(Key::with_modifiers(code, modifiers))
+}
+
+/// Writes `keys` down in key notation; `parse` reads it back.
+pub fn to_string(keys: &[Key]) -> String {
+    keys.iter().map(|key| key.to_string()).collect()
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.code {
+            KeyCode::Char(ch) => match NAMES.iter().find(|&&(_, code)| code == self.code) {
+                Option::Some(&(name, _)) => name.to_string(),
+                Option::None if self.modifiers.is_empty() => return write!(f, "{}", ch),
+                Option::None => ch.to_string(),
+            },
+            KeyCode::F(number) => format!("F{}", number),
+            code => NAMES.iter().find(|&&(_, named)| named == code)
+                .map(|&(name, _)| name.to_string())
+                .unwrap_or_default(),
+        };
+        f.write_str("<")?;
+        if self.modifiers.ctrl {
+            f.write_str("C-")?;
+        }
+        if self.modifiers.alt {
+            f.write_str("A-")?;
+        }
+        if self.modifiers.shift {
+            f.write_str("S-")?;
+        }
+        write!(f, "{}>", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) -> String {
+        to_string(&parse(text).unwrap())
+    }
+
+    #[test]
+    fn printed_keys_read_back_the_same() {
+        for text in ["ifoo<Esc>", "<C-w>j", "<A-J>", "<S-Tab>", "<C-A-S-F12>", "<PageDown><Home>",
+                     "<Space>x<lt>", "<Bar><Bslash>", "<Enter>", "é<BS>", "<F1><S-F5>"] {
+            let keys = parse(text).unwrap();
+            assert_eq!(parse(&to_string(&keys)).unwrap(), keys, "{}", text);
+        }
+    }
+
+    #[test]
+    fn names_are_printed_one_way() {
+        assert_eq!(round_trip("<CR><return><NL>"), "<CR><CR><CR>");
+        assert_eq!(round_trip("<m-x><a-x>"), "<A-x><A-x>");
+        assert_eq!(round_trip("<s-c-del>"), "<C-S-Del>");
+        assert_eq!(round_trip("<f3>"), "<F3>");
+        assert_eq!(round_trip(" <"), "<Space><lt>");
+    }
+
+    #[test]
+    fn brackets_around_other_things_are_text() {
+        assert_eq!(parse("<div>").unwrap(), ::keys("<div>"));
+        assert_eq!(parse("<a>").unwrap(), ::keys("<a>"));
+        assert_eq!(parse("<F13>").unwrap(), ::keys("<F13>"));
+        assert_eq!(parse("a<b").unwrap(), ::keys("a<b"));
+    }
+
+    #[test]
+    fn a_modifier_with_an_unknown_key_is_an_error() {
+        assert_eq!(parse("<C-Foo>"), Err("Unknown key: <C-Foo>".to_string()));
+        assert_eq!(parse("x<S-Nope>"), Err("Unknown key: <S-Nope>".to_string()));
+    }
+
+    #[test]
+    fn the_leader_is_whatever_it_is_set_to() {
+        assert_eq!(parse("<Leader>w").unwrap(), ::keys("\\w"));
+        assert_eq!(parse_with_leader("<leader>w", &::keys(",")).unwrap(), ::keys(",w"));
+        assert_eq!(parse_with_leader("<leader>w", &[Key::from_char(' ')]).unwrap(), ::keys(" w"));
+    }
+}